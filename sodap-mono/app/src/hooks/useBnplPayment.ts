@@ -368,7 +368,7 @@ export const useBnplPayment = () => {
             storePublicKey, // store public key
             new PublicKey(walletAddress), // user public key
             originalPurchaseAmount, // original purchase amount in SOL
-            "Purchase" // point type
+            updatedLoanAccount.purchaseReceipt // receipt the loan paid for
           );
 
           if (loyaltyResult) {
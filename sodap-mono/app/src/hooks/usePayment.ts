@@ -1,9 +1,16 @@
 import { useAnchor } from "@/hooks/useAnchor";
 import { CartItem } from "@/types/cart";
-import { Keypair, PublicKey, TransactionResponse } from "@solana/web3.js";
+import { PublicKey, TransactionResponse } from "@solana/web3.js";
 import { BN } from "@coral-xyz/anchor";
 import * as anchor from "@coral-xyz/anchor";
 import { toast } from "sonner";
+import {
+  findDailySalesPDA,
+  findEscrowPDA,
+  findProductPDA,
+  findReceiptPDA,
+  uuidToBytes,
+} from "@/utils/pdaHelpers";
 
 export interface PaymentResult {
   transactionSignature: string;
@@ -80,8 +87,13 @@ export const usePayment = () => {
       const walletBalance = await connection.getBalance(walletPubkey);
       console.log("💰 Wallet balance:", walletBalance / 1e9, "SOL");
 
-      // Create a new receipt account
-      const receipt = Keypair.generate();
+      // Receipts are PDAs keyed by a per-buyer order id
+      const orderId = new BN(Date.now());
+      const receipt = findReceiptPDA(
+        new PublicKey(storeWallet),
+        walletPubkey,
+        orderId
+      );
 
       // Calculate total amount in lamports
       const totalAmount = cartItems.reduce(
@@ -125,13 +137,16 @@ export const usePayment = () => {
       }
 
       // Convert cart items to the format expected by the program
-      const productIds = cartItems.map(
-        (item) => new PublicKey(item.product.id)
+      const productUuids = cartItems.map((item) =>
+        Array.from(uuidToBytes(item.product.id))
+      );
+      const productPDAs = cartItems.map((item) =>
+        findProductPDA(new PublicKey(storeWallet), item.product.id)
       );
       const quantities = cartItems.map((item) => new BN(item.quantity));
 
       console.log("📦 Cart items:", {
-        productIds: productIds.map((p) => p.toString()),
+        productIds: cartItems.map((item) => item.product.id),
         quantities: quantities.map((q) => q.toString()),
       });
 
@@ -141,17 +156,30 @@ export const usePayment = () => {
       // Create the transaction with the correct accounts
       // Send the transaction
       const tx = await program.methods
-        .purchaseCart(
-          productIds,
-          quantities.map((q) => new BN(q)),
-          totalAmountLamports
-        )
+        .purchaseCart(orderId, {
+          productUuids,
+          variantIds: [],
+          quantities,
+          totalAmountPaid: totalAmountLamports,
+          gasFee,
+          status: { completed: {} },
+          couponCode: null,
+        })
         .accounts({
           store: new PublicKey(storeWallet),
-          receipt: receipt.publicKey,
-          buyer: new PublicKey(walletAddress),
+          receipt,
+          buyer: walletPubkey,
+          storeOwner: new PublicKey(storeOwnerWallet),
+          escrowAccount: findEscrowPDA(new PublicKey(storeWallet)),
+          dailySales: findDailySalesPDA(new PublicKey(storeWallet)),
         })
-        .signers([receipt])
+        .remainingAccounts(
+          productPDAs.map((pubkey) => ({
+            pubkey,
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
         .rpc();
 
       console.log("Transaction sent:", tx);
@@ -236,7 +264,7 @@ export const usePayment = () => {
       // Return detailed payment result
       const result: PaymentResult = {
         transactionSignature: tx,
-        receiptAddress: receipt.publicKey.toString(),
+        receiptAddress: receipt.toString(),
         storeAddress: storeWallet,
         buyerAddress: walletAddress,
         totalAmount: totalAmount,
@@ -32,7 +32,7 @@ const Store: React.FC = () => {
   );
 
   const handleApproveRefund = (id: string) => {
-    // In a real app, this would call the refund_from_escrow instruction
+    // In a real app, this would call the approve_return instruction
     setRefunds(refunds.filter(refund => refund.id !== id));
     alert(`Refund ${id} approved! In a real app, this would trigger the on-chain refund.`);
   };
@@ -15,6 +15,9 @@ import {
   findStorePDA,
   findEscrowPDA,
   findReceiptPDA,
+  findDailySalesPDA,
+  findProductPDA,
+  uuidToBytes,
   solToLamports,
 } from "@/utils/pdaHelpers";
 
@@ -96,11 +99,12 @@ export const createPurchaseTransaction = async (
       const storePDA = findStorePDA(storePublicKey);
       const escrowPDA = findEscrowPDA(storePDA);
       
-      // Create a receipt PDA for this purchase
-      const receipt = findReceiptPDA(walletPublicKey, storePublicKey);
+      // Create a receipt PDA for this purchase, keyed by a new order id
+      const orderId = new BN(Date.now());
+      const receipt = findReceiptPDA(storePDA, walletPublicKey, orderId);
       
       // Convert cart items to the format expected by the program
-      const productIds = cartItems.map(item => new PublicKey(item.product.id));
+      const productUuids = cartItems.map(item => Array.from(uuidToBytes(item.product.id)));
       const quantities = cartItems.map(item => new BN(item.quantity));
       
       console.log("Store PDA:", storePDA.toString());
@@ -109,18 +113,31 @@ export const createPurchaseTransaction = async (
       
       // Add the purchase instruction
       const purchaseIx = await program.methods
-        .purchaseCart(
-          productIds,
+        .purchaseCart(orderId, {
+          productUuids,
+          variantIds: [],
           quantities,
-          amountLamports
-        )
+          totalAmountPaid: amountLamports,
+          gasFee: new BN(0),
+          status: { completed: {} },
+          couponCode: null,
+        })
         .accounts({
           store: storePDA,
           escrowAccount: escrowPDA,
           receipt: receipt,
           buyer: walletPublicKey,
+          storeOwner: storePublicKey,
+          dailySales: findDailySalesPDA(storePDA),
           systemProgram: SystemProgram.programId,
         })
+        .remainingAccounts(
+          cartItems.map(item => ({
+            pubkey: findProductPDA(storePDA, item.product.id),
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
         .instruction();
       
       transaction.add(purchaseIx);
@@ -0,0 +1,202 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use sodap::state::catalog::MAX_BATCH_ITEMS;
+use sodap::types::{ProductRegistration, ProductUpdate};
+use sodap::utils::pda::{find_barcode_pda, find_product_pda};
+
+/// Largest serialized transaction the cluster accepts
+const PACKET_DATA_SIZE: usize = 1232;
+
+/// Store and signer the batches are built for
+pub struct BatchTarget {
+    pub store: Pubkey,
+    pub authority: Pubkey,
+}
+
+/// Split registrations into `register_products` instructions that each fit
+/// in a single transaction
+pub fn register_batches(
+    target: &BatchTarget,
+    items: Vec<ProductRegistration>,
+    max_items: usize,
+) -> Vec<(Vec<[u8; 16]>, Instruction)> {
+    chunk(items, max_items, |batch| {
+        register_instruction(target, batch.to_vec())
+    })
+    .into_iter()
+    .map(|(batch, ix)| (batch.iter().map(|item| item.product_uuid).collect(), ix))
+    .collect()
+}
+
+/// Split product changes into `update_products` instructions that each fit
+/// in a single transaction
+pub fn update_batches(
+    target: &BatchTarget,
+    updates: Vec<ProductUpdate>,
+    max_items: usize,
+) -> Vec<(Vec<[u8; 16]>, Instruction)> {
+    chunk(updates, max_items, |batch| {
+        update_instruction(target, batch.to_vec())
+    })
+    .into_iter()
+    .map(|(batch, ix)| (batch.iter().map(|update| update.product_uuid).collect(), ix))
+    .collect()
+}
+
+fn register_instruction(target: &BatchTarget, items: Vec<ProductRegistration>) -> Instruction {
+    let mut accounts = sodap::accounts::RegisterProducts {
+        store: target.store,
+        authority: target.authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    for item in &items {
+        let (product, _) = find_product_pda(&sodap::ID, &target.store, &item.product_uuid);
+        accounts.push(AccountMeta::new(product, false));
+        if let Some(gtin_hash) = item.gtin_hash {
+            let (index, _) = find_barcode_pda(&sodap::ID, &target.store, &gtin_hash);
+            accounts.push(AccountMeta::new(index, false));
+        }
+    }
+
+    Instruction {
+        program_id: sodap::ID,
+        accounts,
+        data: sodap::instruction::RegisterProducts { items }.data(),
+    }
+}
+
+fn update_instruction(target: &BatchTarget, updates: Vec<ProductUpdate>) -> Instruction {
+    let mut accounts = sodap::accounts::UpdateProducts {
+        store: target.store,
+        authority: target.authority,
+    }
+    .to_account_metas(None);
+    for update in &updates {
+        let (product, _) = find_product_pda(&sodap::ID, &target.store, &update.product_uuid);
+        accounts.push(AccountMeta::new(product, false));
+    }
+
+    Instruction {
+        program_id: sodap::ID,
+        accounts,
+        data: sodap::instruction::UpdateProducts { updates }.data(),
+    }
+}
+
+/// Greedily group items so each instruction stays under `max_items` and its
+/// transaction under the packet size limit
+fn chunk<T>(
+    items: Vec<T>,
+    max_items: usize,
+    build: impl Fn(&[T]) -> Instruction,
+) -> Vec<(Vec<T>, Instruction)> {
+    let max_items = max_items.clamp(1, MAX_BATCH_ITEMS);
+    let mut batches = Vec::new();
+    let mut current: Vec<T> = Vec::new();
+
+    for item in items {
+        current.push(item);
+        let fits =
+            current.len() <= max_items && transaction_size(&build(&current)) <= PACKET_DATA_SIZE;
+        if !fits && current.len() > 1 {
+            let item = current.pop().expect("batch is not empty");
+            let batch = std::mem::replace(&mut current, vec![item]);
+            let ix = build(&batch);
+            batches.push((batch, ix));
+        }
+    }
+    if !current.is_empty() {
+        let ix = build(&current);
+        batches.push((current, ix));
+    }
+    batches
+}
+
+/// Serialized size of a transaction carrying only `ix`, signed by the fee payer
+fn transaction_size(ix: &Instruction) -> usize {
+    let mut keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    keys.push(ix.program_id);
+    keys.sort();
+    keys.dedup();
+
+    let signatures = 1 + 64;
+    let header = 3;
+    let account_keys = compact_len(keys.len()) + keys.len() * 32;
+    let blockhash = 32;
+    let instruction = 1
+        + 1
+        + compact_len(ix.accounts.len())
+        + ix.accounts.len()
+        + compact_len(ix.data.len())
+        + ix.data.len();
+    signatures + header + account_keys + blockhash + instruction
+}
+
+/// Bytes used by the compact-u16 length prefix
+fn compact_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sodap::types::TokenizedType;
+
+    fn registration(index: u8) -> ProductRegistration {
+        ProductRegistration {
+            product_uuid: [index; 16],
+            price: 1,
+            usdc_price: 1,
+            sol_price: 1,
+            stock: 1,
+            tokenized_type: TokenizedType::None,
+            metadata_uri: format!("https://example.com/products/{index}.json"),
+            is_fixed_pricing: true,
+            gtin_hash: Some([index; 32]),
+        }
+    }
+
+    #[test]
+    fn test_register_batches_fit_in_a_transaction() {
+        let target = BatchTarget {
+            store: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+        };
+        let items: Vec<_> = (0..40).map(registration).collect();
+
+        let batches = register_batches(&target, items, MAX_BATCH_ITEMS);
+        assert!(batches.len() > 1);
+        assert_eq!(
+            batches.iter().map(|(uuids, _)| uuids.len()).sum::<usize>(),
+            40
+        );
+        for (_, ix) in &batches {
+            assert!(transaction_size(ix) <= PACKET_DATA_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_update_batches_respect_max_items() {
+        let target = BatchTarget {
+            store: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+        };
+        let updates: Vec<_> = (0..7)
+            .map(|index| ProductUpdate {
+                product_uuid: [index; 16],
+                stock_adjustment: 1,
+                ..Default::default()
+            })
+            .collect();
+
+        let batches = update_batches(&target, updates, 3);
+        let sizes: Vec<_> = batches.iter().map(|(uuids, _)| uuids.len()).collect();
+        assert_eq!(sizes, vec![3, 3, 1]);
+    }
+}
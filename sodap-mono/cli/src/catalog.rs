@@ -0,0 +1,172 @@
+use anchor_lang::solana_program::hash::hash;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use sodap::state::product::Product;
+use sodap::types::{ProductRegistration, ProductUpdate, TokenizedType};
+use std::io::Read;
+
+/// A catalog row for a new product
+#[derive(Debug, Deserialize)]
+struct RegistrationRow {
+    uuid: String,
+    price: u64,
+    usdc_price: u64,
+    sol_price: u64,
+    stock: u64,
+    tokenized_type: String, // none, fungible or non_fungible
+    metadata_uri: String,
+    fixed_pricing: bool,
+    gtin: Option<String>, // Barcode digits, indexed for scan checkout
+}
+
+/// A catalog row changing an existing product. Empty cells are left unchanged.
+#[derive(Debug, Deserialize)]
+struct UpdateRow {
+    uuid: String,
+    stock_adjustment: Option<i64>,
+    price: Option<u64>,
+    usdc_price: Option<u64>,
+    sol_price: Option<u64>,
+    active: Option<bool>,
+}
+
+/// Read new products from a CSV catalog with a header row
+pub fn read_registrations(reader: impl Read) -> Result<Vec<ProductRegistration>> {
+    let mut csv = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut items = Vec::new();
+    for (line, row) in csv.deserialize::<RegistrationRow>().enumerate() {
+        let row = row.with_context(|| format!("catalog row {}", line + 1))?;
+        items
+            .push(registration_from_row(row).with_context(|| format!("catalog row {}", line + 1))?);
+    }
+    Ok(items)
+}
+
+/// Read product changes from a CSV catalog with a header row
+pub fn read_updates(reader: impl Read) -> Result<Vec<ProductUpdate>> {
+    let mut csv = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut updates = Vec::new();
+    for (line, row) in csv.deserialize::<UpdateRow>().enumerate() {
+        let row = row.with_context(|| format!("catalog row {}", line + 1))?;
+        let product_uuid =
+            parse_uuid(&row.uuid).with_context(|| format!("catalog row {}", line + 1))?;
+        updates.push(ProductUpdate {
+            product_uuid,
+            stock_adjustment: row.stock_adjustment.unwrap_or(0),
+            new_price: row.price,
+            new_usdc_price: row.usdc_price,
+            new_sol_price: row.sol_price,
+            is_active: row.active,
+        });
+    }
+    Ok(updates)
+}
+
+fn registration_from_row(row: RegistrationRow) -> Result<ProductRegistration> {
+    if row.metadata_uri.len() > Product::MAX_METADATA_URI_LEN {
+        bail!(
+            "metadata_uri is longer than {} bytes",
+            Product::MAX_METADATA_URI_LEN
+        );
+    }
+    let tokenized_type = match row.tokenized_type.to_ascii_lowercase().as_str() {
+        "" | "none" => TokenizedType::None,
+        "fungible" => TokenizedType::Fungible,
+        "non_fungible" | "nft" => TokenizedType::NonFungible,
+        other => bail!("unknown tokenized_type {other:?}"),
+    };
+    let gtin_hash = row
+        .gtin
+        .filter(|gtin| !gtin.is_empty())
+        .map(|gtin| hash(gtin.as_bytes()).to_bytes());
+
+    Ok(ProductRegistration {
+        product_uuid: parse_uuid(&row.uuid)?,
+        price: row.price,
+        usdc_price: row.usdc_price,
+        sol_price: row.sol_price,
+        stock: row.stock,
+        tokenized_type,
+        metadata_uri: row.metadata_uri,
+        is_fixed_pricing: row.fixed_pricing,
+        gtin_hash,
+    })
+}
+
+/// Parse a UUID written as 32 hex digits, with or without dashes
+pub fn parse_uuid(uuid: &str) -> Result<[u8; 16]> {
+    let digits: Vec<u8> = uuid.bytes().filter(|byte| *byte != b'-').collect();
+    if digits.len() != 32 {
+        bail!("uuid {uuid:?} must have 32 hex digits");
+    }
+
+    let mut bytes = [0u8; 16];
+    for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+        let pair = std::str::from_utf8(pair)?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| anyhow!("uuid {uuid:?} is not hex"))?;
+    }
+    Ok(bytes)
+}
+
+/// Format a product UUID with dashes, for reports
+pub fn format_uuid(uuid: &[u8; 16]) -> String {
+    let hex: String = uuid.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uuid() {
+        let uuid = parse_uuid("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        assert_eq!(uuid[0], 0x55);
+        assert_eq!(uuid[15], 0x00);
+        assert_eq!(format_uuid(&uuid), "550e8400-e29b-41d4-a716-446655440000");
+
+        assert!(parse_uuid("550e8400").is_err());
+        assert!(parse_uuid("zz0e8400e29b41d4a716446655440000").is_err());
+    }
+
+    #[test]
+    fn test_read_registrations() {
+        let csv = "\
+uuid,price,usdc_price,sol_price,stock,tokenized_type,metadata_uri,fixed_pricing,gtin
+550e8400e29b41d4a716446655440000,100,1000000,10000000,5,none,https://example.com/1.json,true,4006381333931
+550e8400e29b41d4a716446655440001,200,2000000,20000000,1,nft,https://example.com/2.json,false,
+";
+        let items = read_registrations(csv.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].tokenized_type, TokenizedType::None);
+        assert_eq!(items[0].gtin_hash, Some(hash(b"4006381333931").to_bytes()));
+        assert_eq!(items[1].tokenized_type, TokenizedType::NonFungible);
+        assert_eq!(items[1].gtin_hash, None);
+    }
+
+    #[test]
+    fn test_read_updates_leaves_empty_cells_unchanged() {
+        let csv = "\
+uuid,stock_adjustment,price,usdc_price,sol_price,active
+550e8400e29b41d4a716446655440000,-3,,,,
+550e8400e29b41d4a716446655440001,,150,,,false
+";
+        let updates = read_updates(csv.as_bytes()).unwrap();
+        assert_eq!(updates[0].stock_adjustment, -3);
+        assert_eq!(updates[0].new_price, None);
+        assert_eq!(updates[1].stock_adjustment, 0);
+        assert_eq!(updates[1].new_price, Some(150));
+        assert_eq!(updates[1].is_active, Some(false));
+    }
+}
@@ -0,0 +1,118 @@
+//! Imports a CSV product catalog into SoDap batch instructions.
+//!
+//! Rows are grouped into `register_products` or `update_products`
+//! instructions that each fit in one transaction. Every batch is written as a
+//! JSON line holding the instruction, ready to be signed by the store's
+//! authority and sent with any Solana client.
+
+mod batch;
+mod catalog;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anyhow::{Context, Result};
+use base64::Engine;
+use batch::{register_batches, update_batches, BatchTarget};
+use catalog::format_uuid;
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+use sodap::state::catalog::MAX_BATCH_ITEMS;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "sodap-catalog",
+    about = "Turn a CSV catalog into SoDap batch instructions"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Register new products. Columns: uuid, price, usdc_price, sol_price,
+    /// stock, tokenized_type, metadata_uri, fixed_pricing, gtin
+    Register(BatchArgs),
+    /// Change existing products. Columns: uuid, stock_adjustment, price,
+    /// usdc_price, sol_price, active (empty cells are left unchanged)
+    Update(BatchArgs),
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// CSV catalog with a header row
+    catalog: PathBuf,
+    /// Store account the products belong to
+    #[arg(long)]
+    store: Pubkey,
+    /// Store owner or admin who signs the batches
+    #[arg(long)]
+    authority: Pubkey,
+    /// Most products per batch
+    #[arg(long, default_value_t = MAX_BATCH_ITEMS)]
+    batch_size: usize,
+    /// Write batches here instead of stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let (args, batches) = match cli.command {
+        Command::Register(args) => {
+            let items = catalog::read_registrations(open_catalog(&args)?)?;
+            let batches = register_batches(&target(&args), items, args.batch_size);
+            (args, batches)
+        }
+        Command::Update(args) => {
+            let updates = catalog::read_updates(open_catalog(&args)?)?;
+            let batches = update_batches(&target(&args), updates, args.batch_size);
+            (args, batches)
+        }
+    };
+
+    let mut out: Box<dyn Write> = match &args.out {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("creating {}", path.display()))?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    for (index, (uuids, ix)) in batches.iter().enumerate() {
+        writeln!(out, "{}", batch_json(index, uuids, ix))?;
+    }
+    out.flush()?;
+
+    let products: usize = batches.iter().map(|(uuids, _)| uuids.len()).sum();
+    eprintln!("{products} product(s) in {} batch(es)", batches.len());
+    Ok(())
+}
+
+fn open_catalog(args: &BatchArgs) -> Result<File> {
+    File::open(&args.catalog).with_context(|| format!("opening {}", args.catalog.display()))
+}
+
+fn target(args: &BatchArgs) -> BatchTarget {
+    BatchTarget {
+        store: args.store,
+        authority: args.authority,
+    }
+}
+
+fn batch_json(index: usize, uuids: &[[u8; 16]], ix: &Instruction) -> serde_json::Value {
+    json!({
+        "batch": index + 1,
+        "products": uuids.iter().map(format_uuid).collect::<Vec<_>>(),
+        "instruction": {
+            "program_id": ix.program_id.to_string(),
+            "accounts": ix.accounts.iter().map(|meta| json!({
+                "pubkey": meta.pubkey.to_string(),
+                "is_signer": meta.is_signer,
+                "is_writable": meta.is_writable,
+            })).collect::<Vec<_>>(),
+            "data": base64::engine::general_purpose::STANDARD.encode(&ix.data),
+        },
+    })
+}
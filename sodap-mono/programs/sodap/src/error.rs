@@ -24,6 +24,50 @@ pub enum CustomError {
     InvalidCart,
     #[msg("Product not found")]
     ProductNotFound,
+    #[msg("Product variant not found")]
+    VariantNotFound,
+    #[msg("Invalid price schedule")]
+    InvalidPriceSchedule,
+    #[msg("Bundle is inactive")]
+    BundleInactive,
+    #[msg("Receipt has no pending digital delivery")]
+    DeliveryNotRequired,
+    #[msg("Delivery payload is empty or too large")]
+    InvalidDeliveryPayload,
+    #[msg("Rating must be between 1 and 5")]
+    InvalidRating,
+    #[msg("Receipt does not prove a purchase of this product")]
+    ReviewNotEligible,
+    #[msg("Product must be deactivated before it can be closed")]
+    ProductStillActive,
+    #[msg("Product has open reservations")]
+    ProductHasOpenReservations,
+    #[msg("Store escrow still holds funds")]
+    EscrowNotEmpty,
+    #[msg("Scanned barcode is not registered in this store")]
+    BarcodeNotFound,
+    #[msg("Barcode is already assigned to a product")]
+    BarcodeInUse,
+    #[msg("Barcode index account missing or does not match")]
+    BarcodeIndexMismatch,
+    #[msg("Product sale has not started")]
+    SaleNotStarted,
+    #[msg("Product purchase limit exceeded")]
+    PurchaseLimitExceeded,
+    #[msg("Wallet is not on the product allowlist")]
+    NotAllowlisted,
+    #[msg("Purchase counter missing for a limited product")]
+    PurchaseCounterMissing,
+    #[msg("Reservation has expired")]
+    ReservationExpired,
+    #[msg("Reservation has not expired yet")]
+    ReservationNotExpired,
+    #[msg("Cart does not match the reservation")]
+    ReservationMismatch,
+    #[msg("Token accounts are required for tokenized products")]
+    MissingTokenAccounts,
+    #[msg("Product token mint does not match")]
+    InvalidProductMint,
     #[msg("Insufficient stock")]
     InsufficientStock,
     #[msg("Price overflow when summing cart")]
@@ -76,6 +120,48 @@ pub enum CustomError {
     InvalidLoyaltyPoints,
     #[msg("Transfer hook error")]
     TransferHookError,
+    // Referral-related errors
+    #[msg("Invalid referral code")]
+    InvalidReferralCode,
+    #[msg("Users cannot refer themselves")]
+    SelfReferral,
+    #[msg("Referral already rewarded")]
+    ReferralAlreadyRewarded,
+    #[msg("Referred user has no qualifying purchase")]
+    ReferralNotQualified,
+    #[msg("Loyalty points for this receipt were already reversed")]
+    LoyaltyPointsAlreadyReversed,
+    #[msg("Loyalty transaction does not match the refunded receipt")]
+    LoyaltyReceiptMismatch,
+    // Reward catalog errors
+    #[msg("Reward is inactive")]
+    RewardInactive,
+    #[msg("Reward is out of stock")]
+    RewardOutOfStock,
+    #[msg("Reward claim limit reached")]
+    RewardLimitReached,
+    #[msg("Accounts do not match the reward")]
+    InvalidReward,
+    #[msg("Voucher is invalid for this purchase")]
+    InvalidVoucher,
+    #[msg("Voucher already redeemed")]
+    VoucherAlreadyRedeemed,
+    // Coupon errors
+    #[msg("Coupon is invalid for this purchase")]
+    InvalidCoupon,
+    #[msg("Coupon has expired")]
+    CouponExpired,
+    #[msg("Coupon usage limit reached")]
+    CouponLimitReached,
+    #[msg("Cart total is below the coupon minimum")]
+    CouponMinCartNotMet,
+    // Achievement badge errors
+    #[msg("Achievement is inactive")]
+    AchievementInactive,
+    #[msg("Achievement criteria not met")]
+    AchievementNotEarned,
+    #[msg("Account proving the achievement is missing")]
+    AchievementProofMissing,
     // BNPL-related errors
     #[msg("Invalid BNPL configuration")]
     InvalidBnplConfig,
@@ -97,4 +183,52 @@ pub enum CustomError {
     BnplTermsNotSupported,
     #[msg("BNPL downpayment required")]
     BnplDownpaymentRequired,
+    // Category errors
+    #[msg("Category is not part of this store's or the platform's tree")]
+    InvalidCategory,
+    #[msg("Category index account missing or does not match")]
+    CategoryIndexMismatch,
+    #[msg("Category index is full")]
+    CategoryFull,
+    // Return errors
+    #[msg("Receipt line cannot be returned")]
+    InvalidReturn,
+    #[msg("Return quantity exceeds the units left on the line")]
+    ReturnQuantityExceeded,
+    // Dispute errors
+    #[msg("Order is under dispute")]
+    OrderDisputed,
+    #[msg("Dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("Nothing left in escrow to dispute")]
+    NothingToDispute,
+    #[msg("Dispute is already resolved")]
+    DisputeResolved,
+    #[msg("Dispute split exceeds the frozen amount")]
+    InvalidDisputeSplit,
+    // Settlement errors
+    #[msg("Order is already settled")]
+    OrderAlreadySettled,
+    #[msg("Order hold period has not passed")]
+    HoldPeriodActive,
+    #[msg("Digital goods have not been delivered")]
+    DeliveryPending,
+    #[msg("Order cannot be settled in its current status")]
+    OrderNotSettleable,
+    // Accounting errors
+    #[msg("Escrow balance does not match the store's open orders")]
+    EscrowInvariantViolated,
+    // Loyalty record errors
+    #[msg("Loyalty points were already earned for this receipt")]
+    LoyaltyPointsAlreadyEarned,
+    #[msg("Loyalty record is still needed to reverse a refund")]
+    LoyaltyRecordInUse,
+    // Settlement errors
+    #[msg("Hold period is shorter than the dispute window")]
+    HoldPeriodTooShort,
+    // Migration errors
+    #[msg("Account is not of the type being migrated")]
+    MigrationTypeMismatch,
+    #[msg("Account already has the current layout")]
+    AccountAlreadyMigrated,
 }
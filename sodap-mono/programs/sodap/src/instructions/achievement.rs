@@ -0,0 +1,231 @@
+use crate::error::CustomError;
+use crate::state::achievement::{
+    Achievement, AchievementCreated, BadgeClaimed, ClaimBadge, CreateAchievement, UpdateAchievement,
+};
+use crate::types::AchievementCriterion;
+use crate::utils::token::{create_metadata_mint, TokenMintAccounts, BADGE_TOKEN_SYMBOL};
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::{self, MintTo, SetAuthority};
+
+/// Define an achievement users can claim a badge for
+pub fn create_achievement(
+    ctx: Context<CreateAchievement>,
+    achievement_id: [u8; 16],
+    name: String,
+    uri: String,
+    criterion: AchievementCriterion,
+) -> Result<()> {
+    require!(
+        name.len() <= Achievement::MAX_NAME_LEN,
+        CustomError::InvalidParameters
+    );
+    require!(
+        uri.len() <= Achievement::MAX_URI_LEN,
+        CustomError::InvalidParameters
+    );
+
+    let achievement = &mut ctx.accounts.achievement;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    achievement.store = ctx.accounts.store.key();
+    achievement.achievement_id = achievement_id;
+    achievement.name = name;
+    achievement.uri = uri;
+    achievement.criterion = criterion;
+    achievement.is_active = true;
+    achievement.badges_issued = 0;
+    achievement.created_at = current_time;
+    achievement.bump = ctx.bumps.achievement;
+
+    emit!(AchievementCreated {
+        achievement: achievement.key(),
+        store: achievement.store,
+        criterion,
+        created_at: current_time,
+    });
+
+    msg!(
+        "Achievement {} created for store {}",
+        achievement.name,
+        achievement.store
+    );
+    Ok(())
+}
+
+/// Update an achievement's badge URI or availability
+pub fn update_achievement(
+    ctx: Context<UpdateAchievement>,
+    uri: Option<String>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let achievement = &mut ctx.accounts.achievement;
+
+    if let Some(uri) = uri {
+        require!(
+            uri.len() <= Achievement::MAX_URI_LEN,
+            CustomError::InvalidParameters
+        );
+        achievement.uri = uri;
+    }
+    if let Some(active) = is_active {
+        achievement.is_active = active;
+    }
+
+    msg!("Achievement {} updated", achievement.name);
+    Ok(())
+}
+
+/// Mint a soulbound badge to a user who meets the achievement criteria
+pub fn claim_badge(ctx: Context<ClaimBadge>) -> Result<()> {
+    let achievement = &ctx.accounts.achievement;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(achievement.is_active, CustomError::AchievementInactive);
+
+    // Check the criteria against the proving account
+    let earned = match achievement.criterion {
+        AchievementCriterion::Purchases { count } => {
+            let account = ctx
+                .accounts
+                .loyalty_account
+                .as_ref()
+                .ok_or(CustomError::AchievementProofMissing)?;
+            account.purchase_count >= count
+        }
+        AchievementCriterion::LoyaltyPoints { points } => {
+            let account = ctx
+                .accounts
+                .loyalty_account
+                .as_ref()
+                .ok_or(CustomError::AchievementProofMissing)?;
+            account.total_points >= points
+        }
+        AchievementCriterion::LoyaltyTier { tier } => {
+            let account = ctx
+                .accounts
+                .loyalty_account
+                .as_ref()
+                .ok_or(CustomError::AchievementProofMissing)?;
+            account.tier.required_points() >= tier.required_points()
+        }
+        AchievementCriterion::Referrals { count } => {
+            let account = ctx
+                .accounts
+                .loyalty_account
+                .as_ref()
+                .ok_or(CustomError::AchievementProofMissing)?;
+            account.total_referrals >= count
+        }
+        AchievementCriterion::OnTimeBnplLoans { count } => {
+            let credit_score = ctx
+                .accounts
+                .credit_score
+                .as_ref()
+                .ok_or(CustomError::AchievementProofMissing)?;
+            credit_score.on_time_loans >= count
+        }
+    };
+    require!(earned, CustomError::AchievementNotEarned);
+
+    let achievement_key = achievement.key();
+    let user_key = ctx.accounts.user.key();
+    let store_key = ctx.accounts.store.key();
+    let mint_seeds: &[&[u8]] = &[
+        b"badge_mint",
+        achievement_key.as_ref(),
+        user_key.as_ref(),
+        &[ctx.bumps.badge_mint],
+    ];
+    let authority_seeds: &[&[u8]] = &[
+        b"achievement",
+        store_key.as_ref(),
+        achievement.achievement_id.as_ref(),
+        &[achievement.bump],
+    ];
+
+    // Create the non-transferable badge mint carrying the achievement's metadata
+    create_metadata_mint(
+        &TokenMintAccounts {
+            payer: &ctx.accounts.user.to_account_info(),
+            mint: &ctx.accounts.badge_mint.to_account_info(),
+            authority: &ctx.accounts.achievement.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        },
+        mint_seeds,
+        authority_seeds,
+        achievement.name.clone(),
+        BADGE_TOKEN_SYMBOL.to_string(),
+        achievement.uri.clone(),
+        true,
+    )?;
+
+    // Create the user's token account and mint the single badge
+    associated_token::create(CpiContext::new(
+        ctx.accounts.associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: ctx.accounts.user.to_account_info(),
+            associated_token: ctx.accounts.badge_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+    ))?;
+
+    token_2022::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.badge_mint.to_account_info(),
+                to: ctx.accounts.badge_token_account.to_account_info(),
+                authority: ctx.accounts.achievement.to_account_info(),
+            },
+            &[authority_seeds],
+        ),
+        1,
+    )?;
+
+    // Fix the supply at one
+    token_2022::set_authority(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            SetAuthority {
+                current_authority: ctx.accounts.achievement.to_account_info(),
+                account_or_mint: ctx.accounts.badge_mint.to_account_info(),
+            },
+            &[authority_seeds],
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+
+    let badge = &mut ctx.accounts.badge;
+    badge.achievement = achievement_key;
+    badge.user = user_key;
+    badge.mint = ctx.accounts.badge_mint.key();
+    badge.claimed_at = current_time;
+
+    let achievement = &mut ctx.accounts.achievement;
+    achievement.badges_issued = achievement
+        .badges_issued
+        .checked_add(1)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    emit!(BadgeClaimed {
+        achievement: achievement_key,
+        store: store_key,
+        user: user_key,
+        mint: badge.mint,
+        claimed_at: current_time,
+    });
+
+    msg!(
+        "Badge for achievement {} minted to {}",
+        achievement.name,
+        user_key
+    );
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::error::CustomError;
+use crate::instructions::treasury::collect_platform_fee;
 use crate::state::bnpl::{
     BnplCreditScore, BnplLoan, BnplLoanCompleted, BnplLoanCreated, BnplLoanDefaulted, BnplPayment,
     BnplPaymentMade, CreateBnplLoan, InitializeCreditScore, LiquidateBnplLoan, MakeBnplPayment,
@@ -41,6 +42,7 @@ pub fn initialize_credit_score(ctx: Context<InitializeCreditScore>) -> Result<()
     credit_score.late_payments = 0;
     credit_score.defaults = 0;
     credit_score.last_updated = Clock::get()?.unix_timestamp;
+    credit_score.on_time_loans = 0;
 
     msg!(
         "Credit score initialized for user: {}",
@@ -113,6 +115,7 @@ pub fn create_bnpl_loan(
     loan.late_fee = (installment_amount * config.late_fee_percent as u64) / 10000;
     loan.grace_period_days = config.grace_period_days;
     loan.purchase_receipt = purchase_receipt;
+    loan.late_payments = 0;
 
     // Transfer downpayment to store
     let transfer_instruction = system_program::Transfer {
@@ -127,6 +130,11 @@ pub fn create_bnpl_loan(
 
     system_program::transfer(cpi_ctx, downpayment)?;
 
+    // The store is owed the rest over the loan term
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.record_direct_payment(downpayment, 0)?;
+    store_stats.add_receivable(remaining_balance)?;
+
     // Update credit score if available
     if let Some(credit_score) = &mut ctx.accounts.credit_score.as_mut() {
         credit_score.total_loans += 1;
@@ -189,7 +197,20 @@ pub fn make_bnpl_payment(ctx: Context<MakeBnplPayment>) -> Result<()> {
         return Err(CustomError::InvalidCart.into());
     }
 
-    // Transfer payment to store owner
+    // Platform's cut goes to the treasury, the rest to the store owner
+    let platform_fee = ctx
+        .accounts
+        .fee_config
+        .fee_for(&loan.store, total_payment_amount);
+    collect_platform_fee(
+        ctx.accounts.borrower.to_account_info(),
+        &mut ctx.accounts.treasury,
+        ctx.accounts.system_program.to_account_info(),
+        loan.store,
+        total_payment_amount,
+        platform_fee,
+    )?;
+
     let transfer_instruction = system_program::Transfer {
         from: ctx.accounts.borrower.to_account_info(),
         to: ctx.accounts.store_owner.to_account_info(),
@@ -200,7 +221,11 @@ pub fn make_bnpl_payment(ctx: Context<MakeBnplPayment>) -> Result<()> {
         transfer_instruction,
     );
 
-    system_program::transfer(cpi_ctx, total_payment_amount)?;
+    system_program::transfer(cpi_ctx, total_payment_amount - platform_fee)?;
+
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.record_direct_payment(total_payment_amount, platform_fee)?;
+    store_stats.settle_receivable(loan.installment_amount)?;
 
     // Update loan
     loan.remaining_balance = loan
@@ -221,6 +246,7 @@ pub fn make_bnpl_payment(ctx: Context<MakeBnplPayment>) -> Result<()> {
     let credit_score = &mut ctx.accounts.credit_score;
     if is_late {
         credit_score.late_payments += 1;
+        loan.late_payments = loan.late_payments.saturating_add(1);
         loan.status = BnplLoanStatus::DefaultedGrace;
     } else {
         credit_score.successful_payments += 1;
@@ -232,6 +258,9 @@ pub fn make_bnpl_payment(ctx: Context<MakeBnplPayment>) -> Result<()> {
     // Check if loan is completed
     if loan.payments_made >= loan.total_payments || loan.remaining_balance == 0 {
         loan.status = BnplLoanStatus::Completed;
+        if loan.late_payments == 0 {
+            credit_score.on_time_loans += 1;
+        }
 
         emit!(BnplLoanCompleted {
             loan_id: loan.loan_id,
@@ -249,6 +278,7 @@ pub fn make_bnpl_payment(ctx: Context<MakeBnplPayment>) -> Result<()> {
         remaining_balance: loan.remaining_balance,
         payment_date: current_time,
         was_late: is_late,
+        platform_fee,
     });
 
     msg!(
@@ -292,6 +322,10 @@ pub fn liquidate_bnpl_loan(ctx: Context<LiquidateBnplLoan>) -> Result<()> {
 
     system_program::transfer(cpi_ctx, total_outstanding)?;
 
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.record_direct_payment(total_outstanding, 0)?;
+    store_stats.settle_receivable(loan.remaining_balance)?;
+
     // Update loan status
     loan.status = BnplLoanStatus::Liquidated;
     loan.remaining_balance = 0;
@@ -0,0 +1,262 @@
+use crate::error::CustomError;
+use crate::instructions::product::{
+    check_purchase_limits, has_digital_products, record_wallet_purchases, update_cart_stock,
+};
+use crate::state::bundle::{Bundle, BundleCreated, CreateBundle, PurchaseBundle, UpdateBundle};
+use crate::state::product::{CartPurchased, Product};
+use crate::types::{AnomalyFlag, BundleComponent, Currency, TransactionStatus};
+use crate::utils::pricing::PriceConverter;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+/// Group several products into a bundle sold at one price
+pub fn create_bundle(
+    ctx: Context<CreateBundle>,
+    bundle_id: [u8; 16],
+    name: String,
+    components: Vec<BundleComponent>,
+    price: u64,
+) -> Result<()> {
+    require!(
+        name.len() <= Bundle::MAX_NAME_LEN,
+        CustomError::InvalidParameters
+    );
+    require!(
+        !components.is_empty() && components.len() <= Bundle::MAX_COMPONENTS,
+        CustomError::InvalidParameters
+    );
+    require!(
+        components.iter().all(|component| component.quantity > 0),
+        CustomError::InvalidParameters
+    );
+    require!(price > 0, CustomError::InvalidParameters);
+
+    let bundle = &mut ctx.accounts.bundle;
+    bundle.store = ctx.accounts.store.key();
+    bundle.bundle_id = bundle_id;
+    bundle.name = name;
+    bundle.components = components;
+    bundle.price = price;
+    bundle.is_active = true;
+    bundle.created_at = Clock::get()?.unix_timestamp;
+
+    emit!(BundleCreated {
+        bundle: bundle.key(),
+        store: bundle.store,
+        components: bundle.components.clone(),
+        price,
+    });
+
+    msg!("Bundle {} created for store {}", bundle.name, bundle.store);
+    Ok(())
+}
+
+/// Update a bundle's price or availability
+pub fn update_bundle(
+    ctx: Context<UpdateBundle>,
+    price: Option<u64>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let bundle = &mut ctx.accounts.bundle;
+
+    if let Some(price) = price {
+        require!(price > 0, CustomError::InvalidParameters);
+        bundle.price = price;
+    }
+    if let Some(active) = is_active {
+        bundle.is_active = active;
+    }
+
+    msg!("Bundle {} updated", bundle.name);
+    Ok(())
+}
+
+/// Buy `quantity` bundles, taking stock from every component product.
+/// Remaining accounts hold the component products in bundle order, followed
+/// by the buyer's purchase counters of components with purchase limits.
+pub fn purchase_bundle<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PurchaseBundle<'info>>,
+    order_id: u64,
+    quantity: u64,
+    total_amount_paid: u64,
+    gas_fee: u64,
+) -> Result<()> {
+    let bundle = &ctx.accounts.bundle;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(bundle.is_active, CustomError::BundleInactive);
+    require!(quantity > 0, CustomError::InvalidCart);
+
+    let total_price = bundle
+        .price
+        .checked_mul(quantity)
+        .ok_or(CustomError::ArithmeticError)?;
+    require!(
+        total_amount_paid >= total_price,
+        CustomError::InsufficientPayment
+    );
+
+    // Expand the bundle into component lines
+    let mut product_uuids = Vec::with_capacity(bundle.components.len());
+    let mut quantities = Vec::with_capacity(bundle.components.len());
+    for component in &bundle.components {
+        product_uuids.push(component.product_uuid);
+        quantities.push(
+            component
+                .quantity
+                .checked_mul(quantity)
+                .ok_or(CustomError::ArithmeticError)?,
+        );
+    }
+    let variant_ids = vec![None; product_uuids.len()];
+
+    // Components must be this store's product accounts, in bundle order, and
+    // within their purchase limits
+    let store_key = ctx.accounts.store.key();
+    let buyer = ctx.accounts.buyer.key();
+    require!(
+        ctx.remaining_accounts.len() >= product_uuids.len(),
+        CustomError::ProductNotFound
+    );
+    for (product_uuid, product_info) in product_uuids.iter().zip(ctx.remaining_accounts) {
+        let (product_key, _) = Pubkey::find_program_address(
+            &[b"product", store_key.as_ref(), product_uuid.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(
+            product_info.key(),
+            product_key,
+            CustomError::ProductNotFound
+        );
+        let product = Account::<Product>::try_from(product_info)?;
+        require_keys_eq!(product.store, store_key, CustomError::ProductNotFound);
+        check_purchase_limits(
+            &product,
+            product_key,
+            &product_uuids,
+            &quantities,
+            ctx.remaining_accounts,
+            buyer,
+        )?;
+    }
+
+    update_cart_stock(
+        &product_uuids,
+        &variant_ids,
+        &quantities,
+        ctx.remaining_accounts,
+        store_key,
+        false,
+    )?;
+    record_wallet_purchases(
+        &product_uuids,
+        &quantities,
+        ctx.remaining_accounts,
+        store_key,
+        buyer,
+    )?;
+
+    // Transfer payment from buyer to escrow account
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.escrow_account.to_account_info(),
+            },
+        ),
+        total_price,
+    )?;
+
+    // Count the sale in the store's totals and today's sales
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.ensure_initialized(store_key, ctx.accounts.escrow_account.balance);
+    store_stats.record_sale(total_price, true)?;
+    let track_top_sellers = store_stats.track_top_sellers;
+    let daily_sales = &mut ctx.accounts.daily_sales;
+    daily_sales.ensure_initialized(store_key, current_time);
+    daily_sales.record_order(
+        Currency::SOL,
+        total_price,
+        &product_uuids,
+        &quantities,
+        track_top_sellers,
+    )?;
+
+    let awaiting_delivery = has_digital_products(product_uuids.len(), ctx.remaining_accounts)?;
+    let escrow = &mut ctx.accounts.escrow_account;
+    escrow.balance = escrow
+        .balance
+        .checked_add(total_price)
+        .ok_or(CustomError::ArithmeticError)?;
+    if awaiting_delivery {
+        escrow.held_for_delivery = escrow
+            .held_for_delivery
+            .checked_add(total_price)
+            .ok_or(CustomError::ArithmeticError)?;
+    } else {
+        escrow.unsettled = escrow
+            .unsettled
+            .checked_add(total_price)
+            .ok_or(CustomError::ArithmeticError)?;
+    }
+
+    // Spread the bundle price over its components by their list prices
+    let line_values = quantities
+        .iter()
+        .zip(ctx.remaining_accounts)
+        .map(|(quantity, product_info)| {
+            let product = Account::<Product>::try_from(product_info)?;
+            product
+                .unit_price(current_time)
+                .checked_mul(*quantity)
+                .ok_or_else(|| CustomError::ArithmeticError.into())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Create receipt listing the components
+    let bundle_key = ctx.accounts.bundle.key();
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.product_uuids = product_uuids.clone();
+    receipt.variant_ids = variant_ids.clone();
+    receipt.quantities = quantities.clone();
+    receipt.total_paid = total_price;
+    receipt.gas_fee = gas_fee;
+    receipt.status = TransactionStatus::Completed;
+    receipt.anomaly = AnomalyFlag::None;
+    receipt.store = ctx.accounts.store.key();
+    receipt.buyer = ctx.accounts.buyer.key();
+    receipt.ts = current_time;
+    receipt.token_mints = vec![None; product_uuids.len()];
+    receipt.coupon = None;
+    receipt.coupon_discount = 0;
+    receipt.bundle = Some(bundle_key);
+    receipt.awaiting_delivery = awaiting_delivery;
+    receipt.line_amounts = PriceConverter::split_by_weight(total_price, &line_values)?;
+    receipt.returned_quantities = vec![0; receipt.quantities.len()];
+    receipt.refunded_amount = 0;
+    receipt.disputed = false;
+    receipt.settled = false;
+    receipt.platform_fee = ctx.accounts.fee_config.fee_for(&receipt.store, total_price);
+    receipt.order_id = order_id;
+    receipt.release_after = ctx.accounts.escrow_account.settles_at(current_time);
+
+    if let Some(loyalty_account) = ctx.accounts.loyalty_account.as_mut() {
+        loyalty_account.record_purchase()?;
+    }
+
+    emit!(CartPurchased {
+        store_id: ctx.accounts.store.key(),
+        buyer_id: ctx.accounts.buyer.key(),
+        product_uuids,
+        variant_ids,
+        quantities,
+        total_paid: total_price,
+        gas_fee,
+        timestamp: current_time,
+        bundle: Some(bundle_key),
+        platform_fee: receipt.platform_fee,
+    });
+
+    Ok(())
+}
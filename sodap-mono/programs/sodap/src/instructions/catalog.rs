@@ -0,0 +1,212 @@
+use crate::error::CustomError;
+use crate::instructions::product::{create_barcode_index, emit_stock_alerts};
+use crate::state::catalog::{
+    ProductBatchProcessed, RegisterProducts, UpdateProducts, MAX_BATCH_ITEMS,
+};
+use crate::state::product::{BarcodeIndex, Product};
+use crate::types::{
+    BatchItemResult, BatchItemStatus, ProductRegistration, ProductUpdate, PurchaseLimits,
+    StablePrice,
+};
+use crate::utils::pda::{create_pda_account, find_barcode_pda, find_product_pda};
+use anchor_lang::prelude::*;
+
+/// Register many products at once. Remaining accounts hold, per item, the
+/// product PDA followed by its barcode index PDA when a GTIN is given.
+/// Items that can't be registered are skipped and reported in the result.
+pub fn register_products<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RegisterProducts<'info>>,
+    items: Vec<ProductRegistration>,
+) -> Result<Vec<BatchItemResult>> {
+    require!(
+        !items.is_empty() && items.len() <= MAX_BATCH_ITEMS,
+        CustomError::InvalidParameters
+    );
+
+    let store = ctx.accounts.store.key();
+    let authority = ctx.accounts.authority.to_account_info();
+    let system_program_info = ctx.accounts.system_program.to_account_info();
+    let current_time = Clock::get()?.unix_timestamp;
+    let mut accounts = ctx.remaining_accounts.iter();
+    let mut results = Vec::with_capacity(items.len());
+
+    for item in items {
+        let product_info = accounts.next().ok_or(CustomError::ProductNotFound)?;
+        let (product_key, bump) = find_product_pda(&crate::ID, &store, &item.product_uuid);
+        require_keys_eq!(
+            product_info.key(),
+            product_key,
+            CustomError::ProductNotFound
+        );
+        let index_info = match item.gtin_hash {
+            Some(gtin_hash) => {
+                let index_info = accounts.next().ok_or(CustomError::BarcodeIndexMismatch)?;
+                let (index_key, _) = find_barcode_pda(&crate::ID, &store, &gtin_hash);
+                require_keys_eq!(
+                    index_info.key(),
+                    index_key,
+                    CustomError::BarcodeIndexMismatch
+                );
+                Some(index_info)
+            }
+            None => None,
+        };
+
+        let product_uuid = item.product_uuid;
+        let status = if item.metadata_uri.len() > Product::MAX_METADATA_URI_LEN {
+            BatchItemStatus::InvalidInput
+        } else if !product_info.data_is_empty() {
+            BatchItemStatus::AlreadyExists
+        } else if index_info.is_some_and(|info| !info.data_is_empty()) {
+            BatchItemStatus::BarcodeInUse
+        } else {
+            create_pda_account(
+                product_info,
+                &authority,
+                &system_program_info,
+                &[b"product", store.as_ref(), product_uuid.as_ref(), &[bump]],
+                Product::LEN,
+            )?;
+            let product = new_product(item, store, authority.key(), current_time);
+            product.try_serialize(&mut &mut product_info.try_borrow_mut_data()?[..])?;
+
+            if let (Some(gtin_hash), Some(index_info)) = (product.gtin_hash, index_info) {
+                let index = BarcodeIndex {
+                    store,
+                    gtin_hash,
+                    product: product_key,
+                    product_uuid,
+                };
+                create_barcode_index(&index, index_info, &authority, &system_program_info)?;
+            }
+            BatchItemStatus::Applied
+        };
+        results.push(BatchItemResult {
+            product_uuid,
+            status,
+        });
+    }
+
+    emit!(ProductBatchProcessed {
+        store,
+        results: results.clone(),
+        timestamp: current_time,
+    });
+
+    Ok(results)
+}
+
+/// Adjust stock, prices and availability of many products at once.
+/// Remaining accounts hold the product accounts in update order.
+pub fn update_products<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateProducts<'info>>,
+    updates: Vec<ProductUpdate>,
+) -> Result<Vec<BatchItemResult>> {
+    require!(
+        !updates.is_empty() && updates.len() <= MAX_BATCH_ITEMS,
+        CustomError::InvalidParameters
+    );
+    require!(
+        ctx.remaining_accounts.len() == updates.len(),
+        CustomError::ProductNotFound
+    );
+
+    let store = ctx.accounts.store.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    let mut results = Vec::with_capacity(updates.len());
+
+    for (update, product_info) in updates.iter().zip(ctx.remaining_accounts) {
+        let (product_key, _) = find_product_pda(&crate::ID, &store, &update.product_uuid);
+        require_keys_eq!(
+            product_info.key(),
+            product_key,
+            CustomError::ProductNotFound
+        );
+
+        let status = if product_info.data_is_empty() {
+            BatchItemStatus::NotFound
+        } else {
+            let product = &mut Account::<Product>::try_from(product_info)?;
+            let previous_stock = product.stock;
+            let status = apply_update(product, update, current_time);
+            if status == BatchItemStatus::Applied {
+                product.exit(&crate::ID)?;
+                emit_stock_alerts(product, product_key, None, previous_stock, product.stock)?;
+            }
+            status
+        };
+        results.push(BatchItemResult {
+            product_uuid: update.product_uuid,
+            status,
+        });
+    }
+
+    emit!(ProductBatchProcessed {
+        store,
+        results: results.clone(),
+        timestamp: current_time,
+    });
+
+    Ok(results)
+}
+
+/// A freshly registered product, owned like `register_product`'s by the
+/// manager who registered it
+fn new_product(item: ProductRegistration, store: Pubkey, authority: Pubkey, now: i64) -> Product {
+    Product {
+        uuid: item.product_uuid,
+        price: item.price,
+        stable_pricing: StablePrice {
+            usdc_price: item.usdc_price,
+            sol_price: item.sol_price,
+            last_updated: now,
+            is_fixed: item.is_fixed_pricing,
+        },
+        stock: item.stock,
+        tokenized_type: item.tokenized_type,
+        is_active: true,
+        metadata_uri: item.metadata_uri,
+        store,
+        authority,
+        price_schedule: Vec::new(),
+        active_sale_start: None,
+        purchase_limits: PurchaseLimits::default(),
+        gtin_hash: item.gtin_hash,
+        open_reservations: 0,
+        reorder_threshold: 0,
+        is_digital: false,
+        attributes: Vec::new(),
+        category: None,
+    }
+}
+
+fn apply_update(product: &mut Product, update: &ProductUpdate, now: i64) -> BatchItemStatus {
+    let stock = if update.stock_adjustment >= 0 {
+        product.stock.checked_add(update.stock_adjustment as u64)
+    } else {
+        product
+            .stock
+            .checked_sub(update.stock_adjustment.unsigned_abs())
+    };
+    let Some(stock) = stock else {
+        return BatchItemStatus::InsufficientStock;
+    };
+
+    product.stock = stock;
+    if let Some(price) = update.new_price {
+        product.price = price;
+    }
+    if update.new_usdc_price.is_some() || update.new_sol_price.is_some() {
+        if let Some(usdc_price) = update.new_usdc_price {
+            product.stable_pricing.usdc_price = usdc_price;
+        }
+        if let Some(sol_price) = update.new_sol_price {
+            product.stable_pricing.sol_price = sol_price;
+        }
+        product.stable_pricing.last_updated = now;
+    }
+    if let Some(active) = update.is_active {
+        product.is_active = active;
+    }
+    BatchItemStatus::Applied
+}
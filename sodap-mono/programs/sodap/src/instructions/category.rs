@@ -0,0 +1,125 @@
+use crate::error::CustomError;
+use crate::state::category::{
+    Category, CategoryCreated, CategoryIndex, CreateCategory, ProductCategorized,
+    SetProductCategory,
+};
+use anchor_lang::prelude::*;
+
+/// Add a category to a store's tree, or to the platform tree when no store is
+/// given. Platform categories can only be created by platform admins.
+pub fn create_category(ctx: Context<CreateCategory>, category_id: u32, name: String) -> Result<()> {
+    require!(
+        !name.is_empty() && name.len() <= Category::MAX_NAME_LEN,
+        CustomError::InvalidParameters
+    );
+
+    let authority = ctx.accounts.authority.key();
+    let owner = match &ctx.accounts.store {
+        Some(store) => {
+            require!(store.can_manage(&authority), CustomError::Unauthorized);
+            store.key()
+        }
+        None => {
+            let platform_admins = ctx
+                .accounts
+                .platform_admins
+                .as_ref()
+                .ok_or(CustomError::Unauthorized)?;
+            require!(
+                platform_admins.admins.contains(&authority),
+                CustomError::Unauthorized
+            );
+            Pubkey::default()
+        }
+    };
+
+    let (parent, depth) = match &ctx.accounts.parent {
+        Some(parent) => {
+            require!(parent.owner == owner, CustomError::InvalidCategory);
+            require!(
+                parent.depth < Category::MAX_DEPTH,
+                CustomError::InvalidCategory
+            );
+            (Some(parent.key()), parent.depth + 1)
+        }
+        None => (None, 0),
+    };
+
+    let category = &mut ctx.accounts.category;
+    category.owner = owner;
+    category.category_id = category_id;
+    category.parent = parent;
+    category.depth = depth;
+    category.name = name;
+    category.created_at = Clock::get()?.unix_timestamp;
+
+    emit!(CategoryCreated {
+        category: category.key(),
+        owner,
+        category_id,
+        parent,
+        name: category.name.clone(),
+    });
+
+    Ok(())
+}
+
+/// Move a product into a category, keeping the store's category indexes in step
+pub fn set_product_category(ctx: Context<SetProductCategory>) -> Result<()> {
+    let store = ctx.accounts.store.key();
+    let product_uuid = ctx.accounts.product.uuid;
+    let old_category = ctx.accounts.product.category;
+    let new_category = ctx
+        .accounts
+        .category
+        .as_ref()
+        .map(|category| category.key());
+    if old_category == new_category {
+        return Ok(());
+    }
+
+    if let Some(old_category) = old_category {
+        let old_index = ctx
+            .accounts
+            .old_category_index
+            .as_mut()
+            .ok_or(CustomError::CategoryIndexMismatch)?;
+        require_keys_eq!(
+            old_index.category,
+            old_category,
+            CustomError::CategoryIndexMismatch
+        );
+        old_index.product_uuids.retain(|uuid| *uuid != product_uuid);
+    }
+
+    if let Some(category) = &ctx.accounts.category {
+        require!(
+            category.owner == store || category.owner == Pubkey::default(),
+            CustomError::InvalidCategory
+        );
+        let index = ctx
+            .accounts
+            .category_index
+            .as_mut()
+            .ok_or(CustomError::CategoryIndexMismatch)?;
+        require!(
+            index.product_uuids.len() < CategoryIndex::MAX_PRODUCTS,
+            CustomError::CategoryFull
+        );
+        index.store = store;
+        index.category = category.key();
+        index.product_uuids.push(product_uuid);
+    }
+
+    let product = &mut ctx.accounts.product;
+    product.category = new_category;
+
+    emit!(ProductCategorized {
+        product: product.key(),
+        store,
+        old_category,
+        new_category,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use crate::error::CustomError;
+use crate::state::coupon::{Coupon, CouponCreated, CreateCoupon, UpdateCoupon};
+use crate::types::{CouponDiscount, CouponTerms};
+use anchor_lang::prelude::*;
+
+/// Issue a promo code for a store. Only the sha256 of the code is stored.
+pub fn create_coupon(
+    ctx: Context<CreateCoupon>,
+    code_hash: [u8; 32],
+    terms: CouponTerms,
+) -> Result<()> {
+    let CouponTerms {
+        discount,
+        min_cart,
+        eligible_products,
+        max_uses,
+        per_user_limit,
+        expires_at,
+    } = terms;
+    validate_discount(&discount)?;
+    require!(
+        eligible_products.len() <= Coupon::MAX_ELIGIBLE_PRODUCTS,
+        CustomError::InvalidParameters
+    );
+
+    let coupon = &mut ctx.accounts.coupon;
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        expires_at == 0 || expires_at > current_time,
+        CustomError::InvalidParameters
+    );
+
+    coupon.store = ctx.accounts.store.key();
+    coupon.code_hash = code_hash;
+    coupon.discount = discount;
+    coupon.min_cart = min_cart;
+    coupon.eligible_products = eligible_products;
+    coupon.max_uses = max_uses;
+    coupon.uses = 0;
+    coupon.per_user_limit = per_user_limit;
+    coupon.expires_at = expires_at;
+    coupon.is_active = true;
+    coupon.created_at = current_time;
+
+    emit!(CouponCreated {
+        coupon: coupon.key(),
+        store: coupon.store,
+        discount,
+        max_uses,
+        expires_at,
+    });
+
+    msg!("Coupon created for store {}", coupon.store);
+    Ok(())
+}
+
+/// Update a coupon's caps, expiry or availability
+pub fn update_coupon(
+    ctx: Context<UpdateCoupon>,
+    max_uses: Option<u64>,
+    per_user_limit: Option<u16>,
+    expires_at: Option<i64>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let coupon = &mut ctx.accounts.coupon;
+
+    if let Some(max_uses) = max_uses {
+        coupon.max_uses = max_uses;
+    }
+    if let Some(limit) = per_user_limit {
+        coupon.per_user_limit = limit;
+    }
+    if let Some(expires_at) = expires_at {
+        coupon.expires_at = expires_at;
+    }
+    if let Some(active) = is_active {
+        coupon.is_active = active;
+    }
+
+    msg!("Coupon {} updated", coupon.key());
+    Ok(())
+}
+
+fn validate_discount(discount: &CouponDiscount) -> Result<()> {
+    match discount {
+        CouponDiscount::Percent { bps } => {
+            require!(*bps > 0 && *bps <= 10_000, CustomError::InvalidParameters)
+        }
+        CouponDiscount::Fixed { amount } => require!(*amount > 0, CustomError::InvalidParameters),
+    }
+    Ok(())
+}
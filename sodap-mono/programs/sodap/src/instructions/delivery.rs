@@ -0,0 +1,51 @@
+use crate::error::CustomError;
+use crate::state::delivery::{Delivery, DigitalGoodsDelivered, RecordDelivery};
+use crate::types::DeliveryKind;
+use anchor_lang::prelude::*;
+
+/// Hand over the digital goods of a receipt and free its escrow for release
+pub fn record_delivery(
+    ctx: Context<RecordDelivery>,
+    kind: DeliveryKind,
+    payload: Vec<u8>,
+) -> Result<()> {
+    require!(
+        !payload.is_empty() && payload.len() <= Delivery::MAX_PAYLOAD_LEN,
+        CustomError::InvalidDeliveryPayload
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.awaiting_delivery = false;
+
+    // Returns already released their part of the held amount. The rest waits
+    // for the order to settle like any other.
+    let released = receipt.total_paid.saturating_sub(receipt.refunded_amount);
+    let escrow = &mut ctx.accounts.escrow_account;
+    escrow.held_for_delivery = escrow.held_for_delivery.saturating_sub(released);
+    escrow.unsettled = escrow
+        .unsettled
+        .checked_add(released)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    let delivery = &mut ctx.accounts.delivery;
+    delivery.receipt = receipt.key();
+    delivery.store = receipt.store;
+    delivery.buyer = receipt.buyer;
+    delivery.kind = kind;
+    delivery.payload = payload;
+    delivery.fulfilled_by = ctx.accounts.fulfiller.key();
+    delivery.delivered_at = current_time;
+
+    emit!(DigitalGoodsDelivered {
+        receipt: delivery.receipt,
+        store: delivery.store,
+        buyer: delivery.buyer,
+        kind,
+        fulfilled_by: delivery.fulfilled_by,
+        released,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use crate::error::CustomError;
+use crate::state::dispute::{
+    ConfigureDisputePool, Dispute, DisputeOpened, DisputeResolved, FundDisputePool, OpenDispute,
+    ResolveDispute,
+};
+use crate::types::{Currency, DisputeStatus, TransactionStatus};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+/// Set how long buyers can dispute an order and what arbitration pays
+pub fn configure_dispute_pool(
+    ctx: Context<ConfigureDisputePool>,
+    dispute_window: i64,
+    arbitration_fee: u64,
+) -> Result<()> {
+    require!(dispute_window > 0, CustomError::InvalidParameters);
+
+    let dispute_pool = &mut ctx.accounts.dispute_pool;
+    dispute_pool.dispute_window = dispute_window;
+    dispute_pool.arbitration_fee = arbitration_fee;
+
+    msg!(
+        "Dispute window set to {} seconds, arbitration fee {} lamports",
+        dispute_window,
+        arbitration_fee
+    );
+    Ok(())
+}
+
+/// Add lamports to the pool arbitration fees are paid from
+pub fn fund_dispute_pool(ctx: Context<FundDisputePool>, amount: u64) -> Result<()> {
+    require!(amount > 0, CustomError::InvalidParameters);
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.funder.to_account_info(),
+                to: ctx.accounts.dispute_pool.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let dispute_pool = &mut ctx.accounts.dispute_pool;
+    dispute_pool.balance = dispute_pool
+        .balance
+        .checked_add(amount)
+        .ok_or(CustomError::ArithmeticError)?;
+    Ok(())
+}
+
+/// Dispute an order within the dispute window, freezing what is left of its
+/// payment in escrow until a platform admin arbitrates
+pub fn open_dispute(ctx: Context<OpenDispute>, evidence_uri: String) -> Result<()> {
+    require!(
+        !evidence_uri.is_empty() && evidence_uri.len() <= Dispute::MAX_EVIDENCE_URI_LEN,
+        CustomError::InvalidParameters
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let receipt = &mut ctx.accounts.receipt;
+    require!(
+        matches!(
+            receipt.status,
+            TransactionStatus::Completed | TransactionStatus::PartiallyRefunded
+        ),
+        CustomError::NothingToDispute
+    );
+    require!(
+        current_time
+            <= receipt
+                .ts
+                .saturating_add(ctx.accounts.dispute_pool.dispute_window),
+        CustomError::DisputeWindowClosed
+    );
+
+    // The order's share of escrow moves from held or unsettled to frozen.
+    // Settled orders are refused above, their funds belong to the store.
+    let outstanding = receipt.total_paid.saturating_sub(receipt.refunded_amount);
+    let amount = ctx
+        .accounts
+        .escrow_account
+        .freeze_order(outstanding, receipt.awaiting_delivery)?;
+    require!(amount > 0, CustomError::NothingToDispute);
+    receipt.disputed = true;
+
+    let dispute = &mut ctx.accounts.dispute;
+    dispute.receipt = receipt.key();
+    dispute.store = receipt.store;
+    dispute.buyer = receipt.buyer;
+    dispute.evidence_uri = evidence_uri;
+    dispute.amount = amount;
+    dispute.status = DisputeStatus::Open;
+    dispute.opened_at = current_time;
+    dispute.buyer_amount = 0;
+    dispute.store_amount = 0;
+    dispute.resolved_by = None;
+    dispute.resolved_at = 0;
+
+    emit!(DisputeOpened {
+        dispute: dispute.key(),
+        receipt: dispute.receipt,
+        store: dispute.store,
+        buyer: dispute.buyer,
+        evidence_uri: dispute.evidence_uri.clone(),
+        amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Settle a dispute by paying `buyer_amount` of the frozen funds to the buyer
+/// and unfreezing the rest for the store. The arbitrating admin is paid the
+/// arbitration fee from the dispute pool, as far as the pool covers it.
+pub fn resolve_dispute(ctx: Context<ResolveDispute>, buyer_amount: u64) -> Result<()> {
+    let dispute = &ctx.accounts.dispute;
+    require!(
+        dispute.status == DisputeStatus::Open,
+        CustomError::DisputeResolved
+    );
+    require!(
+        buyer_amount <= dispute.amount,
+        CustomError::InvalidDisputeSplit
+    );
+    let amount = dispute.amount;
+    let store_amount = amount - buyer_amount;
+
+    // Pay the buyer's share out of escrow. The escrow and the pool are
+    // program accounts, so their lamports are moved directly.
+    if buyer_amount > 0 {
+        ctx.accounts.escrow_account.sub_lamports(buyer_amount)?;
+        ctx.accounts.buyer.add_lamports(buyer_amount)?;
+    }
+
+    ctx.accounts
+        .escrow_account
+        .resolve_frozen(amount, buyer_amount)?;
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.record_refund(buyer_amount)?;
+    if !ctx.accounts.receipt.settled {
+        store_stats.close_order()?;
+    }
+    if buyer_amount > 0 {
+        let store_key = ctx.accounts.store.key();
+        let daily_sales = &mut ctx.accounts.daily_sales;
+        daily_sales.ensure_initialized(store_key, Clock::get()?.unix_timestamp);
+        daily_sales.record_refund(Currency::SOL, buyer_amount, 0)?;
+    }
+
+    // Pay the arbitration fee out of the pool
+    let arbitration_fee = ctx
+        .accounts
+        .dispute_pool
+        .arbitration_fee
+        .min(ctx.accounts.dispute_pool.balance);
+    if arbitration_fee > 0 {
+        ctx.accounts.dispute_pool.sub_lamports(arbitration_fee)?;
+        ctx.accounts.authority.add_lamports(arbitration_fee)?;
+        ctx.accounts.dispute_pool.balance -= arbitration_fee;
+    }
+
+    // The ruling settles the order, including any pending delivery. The store's
+    // share stays in escrow for the owner to release.
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.disputed = false;
+    receipt.awaiting_delivery = false;
+    receipt.settled = true;
+    receipt.refunded_amount = receipt
+        .refunded_amount
+        .checked_add(buyer_amount)
+        .ok_or(CustomError::ArithmeticError)?;
+    if buyer_amount > 0 {
+        receipt.status = if receipt.refunded_amount >= receipt.total_paid {
+            TransactionStatus::Refunded
+        } else {
+            TransactionStatus::PartiallyRefunded
+        };
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let resolved_by = ctx.accounts.authority.key();
+    let dispute = &mut ctx.accounts.dispute;
+    dispute.status = DisputeStatus::Resolved;
+    dispute.buyer_amount = buyer_amount;
+    dispute.store_amount = store_amount;
+    dispute.resolved_by = Some(resolved_by);
+    dispute.resolved_at = current_time;
+
+    emit!(DisputeResolved {
+        dispute: dispute.key(),
+        receipt: dispute.receipt,
+        store: dispute.store,
+        buyer: dispute.buyer,
+        buyer_amount,
+        store_amount,
+        arbitration_fee,
+        resolved_by,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
@@ -1,11 +1,15 @@
 use crate::error::CustomError;
 use crate::state::loyalty::{
-    EarnLoyaltyPoints, GiftLoyaltyPoints, InitializeLoyaltyAccount, InitializeLoyaltyProgram,
-    LoyaltyAccount, LoyaltyPointsEarned, LoyaltyPointsGifted, LoyaltyPointsRedeemed,
+    AwardReferralBonus, CloseLoyaltyTransaction, DeductLoyaltyPoints, EarnLoyaltyPoints,
+    GiftLoyaltyPoints, InitializeLoyaltyAccount, InitializeLoyaltyProgram, LoyaltyAccount,
+    LoyaltyPointsEarned, LoyaltyPointsGifted, LoyaltyPointsRedeemed, LoyaltyPointsReversed,
     LoyaltyProgram, LoyaltyProgramUpdated, LoyaltyTierChanged, LoyaltyTransaction,
-    RedeemLoyaltyPoints, UpdateLoyaltyProgram,
+    RedeemLoyaltyPoints, ReferralRewarded, UpdateLoyaltyProgram,
+};
+use crate::types::{
+    LoyaltyPointType, LoyaltyProgramChanges, LoyaltyTier, LoyaltyTransactionType,
+    NegativeBalancePolicy, TransactionStatus,
 };
-use crate::types::{LoyaltyPointType, LoyaltyTier, LoyaltyTransactionType};
 use anchor_lang::prelude::*;
 
 /// Configuration for loyalty system
@@ -17,6 +21,8 @@ pub struct LoyaltyConfig {
     pub default_min_redemption: u64,    // Default minimum redemption
     pub default_max_redemption_percent: u16, // Default max redemption percentage (5000 = 50%)
     pub max_gift_amount: u64,           // Maximum points that can be gifted at once
+    pub min_referral_purchase: u64,     // Minimum purchase (lamports) for a referral to qualify
+    pub default_negative_balance_policy: NegativeBalancePolicy, // Clawback of already-spent points
 }
 
 impl Default for LoyaltyConfig {
@@ -29,6 +35,8 @@ impl Default for LoyaltyConfig {
             default_min_redemption: 100,   // Minimum 100 points to redeem
             default_max_redemption_percent: 5000, // Max 50% of purchase with points
             max_gift_amount: 10000,        // Max 10,000 points gift
+            min_referral_purchase: 100_000_000, // 0.1 SOL first purchase
+            default_negative_balance_policy: NegativeBalancePolicy::ClampToZero,
         }
     }
 }
@@ -56,6 +64,7 @@ pub fn initialize_loyalty_program(
     loyalty_program.max_redemption_percent = config.default_max_redemption_percent;
     loyalty_program.point_expiry_days = 0; // Never expire by default
     loyalty_program.tier_multiplier_enabled = true;
+    loyalty_program.negative_balance_policy = config.default_negative_balance_policy;
     loyalty_program.created_at = current_time;
     loyalty_program.updated_at = current_time;
 
@@ -77,10 +86,11 @@ pub fn initialize_loyalty_program(
 pub fn initialize_loyalty_account(
     ctx: Context<InitializeLoyaltyAccount>,
     referral_code: Option<[u8; 8]>,
-    referred_by: Option<Pubkey>,
+    referrer_code: Option<[u8; 8]>,
 ) -> Result<()> {
     let loyalty_account = &mut ctx.accounts.loyalty_account;
     let loyalty_program = &ctx.accounts.loyalty_program;
+    let user_key = ctx.accounts.user.key();
     let current_time = Clock::get()?.unix_timestamp;
 
     // Validate loyalty program is active
@@ -90,12 +100,29 @@ pub fn initialize_loyalty_account(
     );
 
     // Generate referral code if not provided
-    let user_referral_code = referral_code.unwrap_or_else(|| {
-        let user_key = ctx.accounts.user.key();
-        let mut code = [0u8; 8];
-        code.copy_from_slice(&user_key.to_bytes()[0..8]);
-        code
-    });
+    let user_referral_code = LoyaltyAccount::resolve_referral_code(referral_code, &user_key);
+
+    // Resolve the referrer through the code registry
+    let referred_by = match referrer_code {
+        Some(code) => {
+            let registry = ctx
+                .accounts
+                .referrer_code_registry
+                .as_ref()
+                .ok_or(CustomError::InvalidReferralCode)?;
+            require!(registry.code == code, CustomError::InvalidReferralCode);
+            require!(registry.referrer != user_key, CustomError::SelfReferral);
+            Some(registry.referrer)
+        }
+        None => None,
+    };
+
+    // Register the user's own code
+    let referral_code_registry = &mut ctx.accounts.referral_code_registry;
+    referral_code_registry.store = ctx.accounts.store.key();
+    referral_code_registry.code = user_referral_code;
+    referral_code_registry.referrer = user_key;
+    referral_code_registry.created_at = current_time;
 
     loyalty_account.user = ctx.accounts.user.key();
     loyalty_account.store = ctx.accounts.store.key();
@@ -110,6 +137,15 @@ pub fn initialize_loyalty_account(
     loyalty_account.referral_code = user_referral_code;
     loyalty_account.referred_by = referred_by;
     loyalty_account.total_referrals = 0;
+    loyalty_account.referral_rewarded = false;
+    loyalty_account.transaction_count = 0;
+    loyalty_account.points_debt = 0;
+    loyalty_account.purchase_count = 0;
+
+    let store_key = ctx.accounts.store.key();
+    let daily_sales = &mut ctx.accounts.daily_sales;
+    daily_sales.ensure_initialized(store_key, current_time);
+    daily_sales.record_loyalty_member()?;
 
     // Award welcome bonus
     if loyalty_program.welcome_bonus > 0 {
@@ -136,11 +172,7 @@ pub fn initialize_loyalty_account(
 }
 
 /// Earn loyalty points from a purchase
-pub fn earn_loyalty_points(
-    ctx: Context<EarnLoyaltyPoints>,
-    purchase_amount: u64,
-    point_type: LoyaltyPointType,
-) -> Result<()> {
+pub fn earn_loyalty_points(ctx: Context<EarnLoyaltyPoints>, purchase_amount: u64) -> Result<()> {
     let loyalty_account = &mut ctx.accounts.loyalty_account;
     let loyalty_program = &ctx.accounts.loyalty_program;
     let current_time = Clock::get()?.unix_timestamp;
@@ -151,15 +183,24 @@ pub fn earn_loyalty_points(
         CustomError::LoyaltyProgramInactive
     );
 
-    // Calculate points to earn
-    let points_to_earn = match point_type {
-        LoyaltyPointType::Purchase => {
-            loyalty_program.calculate_points_earned(purchase_amount, loyalty_account.tier)
-        }
-        LoyaltyPointType::Referral => loyalty_program.referral_bonus,
-        LoyaltyPointType::Bonus => purchase_amount, // For bonus, amount represents points directly
-        LoyaltyPointType::Welcome => loyalty_program.welcome_bonus,
-    };
+    // Only completed, undisputed orders earn, never more than the buyer kept
+    // paid, and a receipt earns once
+    let purchase_receipt = &mut ctx.accounts.purchase_receipt;
+    require!(
+        purchase_receipt.status == TransactionStatus::Completed && !purchase_receipt.disputed,
+        CustomError::InvalidLoyaltyPoints
+    );
+    require!(
+        purchase_amount <= purchase_receipt.net_paid(),
+        CustomError::InvalidLoyaltyPoints
+    );
+    purchase_receipt.loyalty_earned = true;
+    let receipt = Some(purchase_receipt.key());
+
+    // Purchases always earn at the program's configured rate
+    let point_type = LoyaltyPointType::Purchase;
+    let points_to_earn =
+        loyalty_program.calculate_points_earned(purchase_amount, loyalty_account.tier);
 
     let old_tier = loyalty_account.tier;
 
@@ -172,6 +213,9 @@ pub fn earn_loyalty_points(
 
     // Create transaction record
     let transaction_record = &mut ctx.accounts.transaction_record;
+    transaction_record.loyalty_account = loyalty_account.key();
+    transaction_record.sequence = loyalty_account.next_transaction_sequence()?;
+    transaction_record.payer = ctx.accounts.user.key();
     transaction_record.user = ctx.accounts.user.key();
     transaction_record.store = ctx.accounts.store.key();
     transaction_record.transaction_type = LoyaltyTransactionType::Earned;
@@ -181,6 +225,8 @@ pub fn earn_loyalty_points(
     transaction_record.description = format!("Earned {} points from purchase", points_to_earn);
     transaction_record.timestamp = current_time;
     transaction_record.related_user = None;
+    transaction_record.receipt = receipt;
+    transaction_record.points_reversed = 0;
 
     // Emit events
     emit!(LoyaltyPointsEarned {
@@ -253,6 +299,9 @@ pub fn redeem_loyalty_points(
 
     // Create transaction record
     let transaction_record = &mut ctx.accounts.transaction_record;
+    transaction_record.loyalty_account = loyalty_account.key();
+    transaction_record.sequence = loyalty_account.next_transaction_sequence()?;
+    transaction_record.payer = ctx.accounts.user.key();
     transaction_record.user = ctx.accounts.user.key();
     transaction_record.store = ctx.accounts.store.key();
     transaction_record.transaction_type = LoyaltyTransactionType::Redeemed;
@@ -265,6 +314,7 @@ pub fn redeem_loyalty_points(
     );
     transaction_record.timestamp = current_time;
     transaction_record.related_user = None;
+    transaction_record.receipt = None;
 
     emit!(LoyaltyPointsRedeemed {
         user: ctx.accounts.user.key(),
@@ -289,14 +339,18 @@ pub fn redeem_loyalty_points(
 /// Update loyalty program settings
 pub fn update_loyalty_program(
     ctx: Context<UpdateLoyaltyProgram>,
-    points_per_dollar: Option<u64>,
-    redemption_rate: Option<u64>,
-    welcome_bonus: Option<u64>,
-    referral_bonus: Option<u64>,
-    min_redemption: Option<u64>,
-    max_redemption_percent: Option<u16>,
-    is_active: Option<bool>,
+    changes: LoyaltyProgramChanges,
 ) -> Result<()> {
+    let LoyaltyProgramChanges {
+        points_per_dollar,
+        redemption_rate,
+        welcome_bonus,
+        referral_bonus,
+        min_redemption,
+        max_redemption_percent,
+        is_active,
+        negative_balance_policy,
+    } = changes;
     let loyalty_program = &mut ctx.accounts.loyalty_program;
     let current_time = Clock::get()?.unix_timestamp;
 
@@ -321,6 +375,9 @@ pub fn update_loyalty_program(
     if let Some(active) = is_active {
         loyalty_program.is_active = active;
     }
+    if let Some(policy) = negative_balance_policy {
+        loyalty_program.negative_balance_policy = policy;
+    }
 
     loyalty_program.updated_at = current_time;
 
@@ -380,6 +437,9 @@ pub fn gift_loyalty_points(
 
     // Create transaction records
     let sender_transaction = &mut ctx.accounts.sender_transaction;
+    sender_transaction.loyalty_account = sender_account.key();
+    sender_transaction.sequence = sender_account.next_transaction_sequence()?;
+    sender_transaction.payer = ctx.accounts.sender.key();
     sender_transaction.user = ctx.accounts.sender.key();
     sender_transaction.store = ctx.accounts.store.key();
     sender_transaction.transaction_type = LoyaltyTransactionType::Gifted;
@@ -389,8 +449,12 @@ pub fn gift_loyalty_points(
     sender_transaction.description = format!("Gifted {} points: {}", points_to_gift, message);
     sender_transaction.timestamp = current_time;
     sender_transaction.related_user = Some(recipient_account.user);
+    sender_transaction.receipt = None;
 
     let recipient_transaction = &mut ctx.accounts.recipient_transaction;
+    recipient_transaction.loyalty_account = recipient_account.key();
+    recipient_transaction.sequence = recipient_account.next_transaction_sequence()?;
+    recipient_transaction.payer = ctx.accounts.sender.key();
     recipient_transaction.user = recipient_account.user;
     recipient_transaction.store = ctx.accounts.store.key();
     recipient_transaction.transaction_type = LoyaltyTransactionType::Received;
@@ -400,6 +464,7 @@ pub fn gift_loyalty_points(
     recipient_transaction.description = format!("Received {} points: {}", points_to_gift, message);
     recipient_transaction.timestamp = current_time;
     recipient_transaction.related_user = Some(ctx.accounts.sender.key());
+    recipient_transaction.receipt = None;
 
     emit!(LoyaltyPointsGifted {
         sender: ctx.accounts.sender.key(),
@@ -419,9 +484,9 @@ pub fn gift_loyalty_points(
     Ok(())
 }
 
-/// Award referral bonus when someone is referred
-pub fn award_referral_bonus(ctx: Context<EarnLoyaltyPoints>, referred_user: Pubkey) -> Result<()> {
-    let loyalty_account = &mut ctx.accounts.loyalty_account;
+/// Award referral bonuses to both referrer and referee after the referee's first qualifying purchase
+pub fn award_referral_bonus(ctx: Context<AwardReferralBonus>) -> Result<()> {
+    let config = LoyaltyConfig::default();
     let loyalty_program = &ctx.accounts.loyalty_program;
     let current_time = Clock::get()?.unix_timestamp;
 
@@ -431,116 +496,209 @@ pub fn award_referral_bonus(ctx: Context<EarnLoyaltyPoints>, referred_user: Pubk
         CustomError::LoyaltyProgramInactive
     );
 
-    // Award referral bonus
-    let referral_points = loyalty_program.referral_bonus;
-    loyalty_account.add_points(referral_points, LoyaltyPointType::Referral)?;
-    loyalty_account.total_referrals += 1;
+    let referee_account = &mut ctx.accounts.referee_loyalty_account;
+    let referrer_account = &mut ctx.accounts.referrer_loyalty_account;
 
-    // Create transaction record
-    let transaction_record = &mut ctx.accounts.transaction_record;
-    transaction_record.user = ctx.accounts.user.key();
-    transaction_record.store = ctx.accounts.store.key();
-    transaction_record.transaction_type = LoyaltyTransactionType::Earned;
-    transaction_record.point_type = LoyaltyPointType::Referral;
-    transaction_record.points = referral_points;
-    transaction_record.purchase_amount = None;
-    transaction_record.description = format!("Referral bonus for referring user");
-    transaction_record.timestamp = current_time;
-    transaction_record.related_user = Some(referred_user);
+    // Each referred user is rewarded once
+    require!(
+        !referee_account.referral_rewarded,
+        CustomError::ReferralAlreadyRewarded
+    );
+    require!(
+        referee_account.user != referrer_account.user,
+        CustomError::SelfReferral
+    );
+    // Refunds and disputes can take the purchase back, so only what was
+    // kept counts towards qualifying
+    let receipt = &ctx.accounts.referee_receipt;
+    require!(
+        matches!(
+            receipt.status,
+            TransactionStatus::Completed | TransactionStatus::PartiallyRefunded
+        ) && !receipt.disputed,
+        CustomError::ReferralNotQualified
+    );
+    require!(
+        receipt.net_paid() >= config.min_referral_purchase,
+        CustomError::ReferralNotQualified
+    );
 
-    emit!(LoyaltyPointsEarned {
-        user: ctx.accounts.user.key(),
+    // Award both sides in the same instruction
+    let referral_points = loyalty_program.referral_bonus;
+    referrer_account.add_points(referral_points, LoyaltyPointType::Referral)?;
+    referrer_account.total_referrals = referrer_account
+        .total_referrals
+        .checked_add(1)
+        .ok_or(CustomError::ArithmeticError)?;
+    referee_account.add_points(referral_points, LoyaltyPointType::Referral)?;
+    referee_account.referral_rewarded = true;
+
+    // Create transaction records
+    let referrer_transaction = &mut ctx.accounts.referrer_transaction;
+    referrer_transaction.loyalty_account = referrer_account.key();
+    referrer_transaction.sequence = referrer_account.next_transaction_sequence()?;
+    referrer_transaction.payer = ctx.accounts.payer.key();
+    referrer_transaction.user = referrer_account.user;
+    referrer_transaction.store = ctx.accounts.store.key();
+    referrer_transaction.transaction_type = LoyaltyTransactionType::Earned;
+    referrer_transaction.point_type = LoyaltyPointType::Referral;
+    referrer_transaction.points = referral_points;
+    referrer_transaction.purchase_amount = None;
+    referrer_transaction.description = "Referral bonus for referring user".to_string();
+    referrer_transaction.timestamp = current_time;
+    referrer_transaction.related_user = Some(referee_account.user);
+    referrer_transaction.receipt = None;
+
+    let referee_transaction = &mut ctx.accounts.referee_transaction;
+    referee_transaction.loyalty_account = referee_account.key();
+    referee_transaction.sequence = referee_account.next_transaction_sequence()?;
+    referee_transaction.payer = ctx.accounts.payer.key();
+    referee_transaction.user = referee_account.user;
+    referee_transaction.store = ctx.accounts.store.key();
+    referee_transaction.transaction_type = LoyaltyTransactionType::Earned;
+    referee_transaction.point_type = LoyaltyPointType::Referral;
+    referee_transaction.points = referral_points;
+    referee_transaction.purchase_amount = Some(ctx.accounts.referee_receipt.total_paid);
+    referee_transaction.description = "Referral bonus for joining through a referral".to_string();
+    referee_transaction.timestamp = current_time;
+    referee_transaction.related_user = Some(referrer_account.user);
+    referee_transaction.receipt = Some(ctx.accounts.referee_receipt.key());
+
+    emit!(ReferralRewarded {
+        referrer: referrer_account.user,
+        referee: referee_account.user,
         store: ctx.accounts.store.key(),
-        points_earned: referral_points,
-        point_type: LoyaltyPointType::Referral,
-        new_total: loyalty_account.total_points,
-        new_tier: loyalty_account.tier,
-        purchase_amount: 0,
+        referrer_points: referral_points,
+        referee_points: referral_points,
         timestamp: current_time,
     });
 
     msg!(
-        "User {} earned {} referral points for referring {}",
-        ctx.accounts.user.key(),
-        referral_points,
-        referred_user
+        "Referral rewarded: {} referred {}, {} points each",
+        referrer_account.user,
+        referee_account.user,
+        referral_points
     );
 
     Ok(())
 }
 
-/// Deduct loyalty points for refunds
-pub fn deduct_loyalty_points(
-    ctx: Context<EarnLoyaltyPoints>, // Reuse same context structure
+/// Claw back the loyalty points earned for a refunded receipt
+pub fn deduct_loyalty_points(ctx: Context<DeductLoyaltyPoints>, refund_amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    reverse_receipt_points(
+        &mut accounts.loyalty_account,
+        &accounts.loyalty_program,
+        &mut accounts.earned_transaction,
+        &mut accounts.transaction_record,
+        accounts.authority.key(),
+        refund_amount,
+    )?;
+    Ok(())
+}
+
+/// Reverse the share of a receipt's earned points that `refund_amount` covers,
+/// recording the reversal in `transaction_record`. Returns the points reversed.
+pub(crate) fn reverse_receipt_points(
+    loyalty_account: &mut Account<LoyaltyAccount>,
+    loyalty_program: &LoyaltyProgram,
+    earned_transaction: &mut LoyaltyTransaction,
+    transaction_record: &mut LoyaltyTransaction,
+    authority: Pubkey,
     refund_amount: u64,
-    point_type: LoyaltyPointType,
-) -> Result<()> {
-    let loyalty_account = &mut ctx.accounts.loyalty_account;
-    let loyalty_program = &ctx.accounts.loyalty_program;
+) -> Result<u64> {
     let current_time = Clock::get()?.unix_timestamp;
+    let store = loyalty_account.store;
+    let receipt = earned_transaction
+        .receipt
+        .ok_or(CustomError::LoyaltyReceiptMismatch)?;
+
+    let remaining = earned_transaction
+        .points
+        .checked_sub(earned_transaction.points_reversed)
+        .ok_or(CustomError::ArithmeticError)?;
+    require!(remaining > 0, CustomError::LoyaltyPointsAlreadyReversed);
+
+    // A full refund reverses everything left; a partial one reverses its share of the original
+    let purchase_amount = earned_transaction.purchase_amount.unwrap_or(0);
+    let points_to_reverse = if purchase_amount == 0 || refund_amount >= purchase_amount {
+        remaining
+    } else {
+        let share = (earned_transaction.points as u128)
+            .checked_mul(refund_amount as u128)
+            .ok_or(CustomError::ArithmeticError)?
+            / purchase_amount as u128;
+        (share as u64).min(remaining)
+    };
 
-    // Validate loyalty program is active
-    require!(
-        loyalty_program.is_active,
-        CustomError::LoyaltyProgramInactive
+    earned_transaction.points_reversed = earned_transaction
+        .points_reversed
+        .checked_add(points_to_reverse)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    let old_tier = loyalty_account.tier;
+    let shortfall =
+        loyalty_account.reverse_points(points_to_reverse, loyalty_program.negative_balance_policy)?;
+
+    // Create transaction record for the reversal
+    transaction_record.loyalty_account = loyalty_account.key();
+    transaction_record.sequence = loyalty_account.next_transaction_sequence()?;
+    transaction_record.payer = authority;
+    transaction_record.user = loyalty_account.user;
+    transaction_record.store = store;
+    transaction_record.transaction_type = LoyaltyTransactionType::Reversed;
+    transaction_record.point_type = earned_transaction.point_type;
+    transaction_record.points = points_to_reverse;
+    transaction_record.purchase_amount = Some(refund_amount);
+    transaction_record.description = format!(
+        "Reversed {} points for refund of {} lamports",
+        points_to_reverse, refund_amount
     );
+    transaction_record.timestamp = current_time;
+    transaction_record.related_user = None;
+    transaction_record.receipt = Some(receipt);
+
+    emit!(LoyaltyPointsReversed {
+        user: loyalty_account.user,
+        store,
+        receipt,
+        points_reversed: points_to_reverse,
+        shortfall,
+        refund_amount,
+        remaining_points: loyalty_account.available_points,
+        new_total: loyalty_account.total_points,
+        new_tier: loyalty_account.tier,
+        timestamp: current_time,
+    });
 
-    // Calculate points to deduct based on refund amount
-    let points_to_deduct = refund_amount
-        .checked_mul(loyalty_program.points_per_dollar)
-        .unwrap_or(0)
-        .checked_div(1_000_000_000) // Convert lamports to SOL
-        .unwrap_or(0);
-
-    // Validate user has enough points to deduct (but allow partial deduction)
-    let actual_deduction = std::cmp::min(loyalty_account.total_points, points_to_deduct);
-    
-    // Only proceed if there are points to deduct
-    if actual_deduction > 0 {
-        // Deduct points
-        loyalty_account.total_points = loyalty_account.total_points.saturating_sub(actual_deduction);
-        
-        // Update tier if necessary
-        loyalty_account.update_tier();
-
-        // Create transaction record for the deduction
-        let transaction_record = &mut ctx.accounts.transaction_record;
-        transaction_record.user = ctx.accounts.user.key();
-        transaction_record.store = ctx.accounts.store.key();
-        transaction_record.transaction_type = LoyaltyTransactionType::Earned; // Use existing variant for now
-        transaction_record.point_type = point_type;
-        transaction_record.points = actual_deduction;
-        transaction_record.purchase_amount = Some(refund_amount);
-        transaction_record.description = format!("Points deducted for refund of {} SOL", refund_amount as f64 / 1_000_000_000.0);
-        transaction_record.timestamp = current_time;
-        transaction_record.related_user = None;
-
-        // Emit event for point deduction
-        emit!(LoyaltyPointsEarned {
-            user: ctx.accounts.user.key(),
-            store: ctx.accounts.store.key(),
-            points_earned: actual_deduction, // We'll handle the negative logic in frontend
-            point_type,
-            new_total: loyalty_account.total_points,
+    if old_tier != loyalty_account.tier {
+        emit!(LoyaltyTierChanged {
+            user: loyalty_account.user,
+            store,
+            old_tier,
             new_tier: loyalty_account.tier,
-            purchase_amount: refund_amount,
+            total_points: loyalty_account.total_points,
             timestamp: current_time,
         });
-
-        msg!(
-            "User {} had {} loyalty points deducted for refund of {} SOL. New total: {}",
-            ctx.accounts.user.key(),
-            actual_deduction,
-            refund_amount as f64 / 1_000_000_000.0,
-            loyalty_account.total_points
-        );
-    } else {
-        msg!(
-            "User {} has no points to deduct for refund of {} SOL",
-            ctx.accounts.user.key(),
-            refund_amount as f64 / 1_000_000_000.0
-        );
     }
 
+    msg!(
+        "User {} had {} loyalty points reversed for refund of {} lamports (shortfall: {})",
+        loyalty_account.user,
+        points_to_reverse,
+        refund_amount,
+        shortfall
+    );
+
+    Ok(points_to_reverse)
+}
+
+/// Close a loyalty transaction record and return its rent to whoever paid for it
+pub fn close_loyalty_transaction(ctx: Context<CloseLoyaltyTransaction>) -> Result<()> {
+    msg!(
+        "Closed loyalty transaction {} for user {}",
+        ctx.accounts.transaction_record.sequence,
+        ctx.accounts.user.key()
+    );
     Ok(())
 }
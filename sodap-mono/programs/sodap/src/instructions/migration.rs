@@ -0,0 +1,100 @@
+use crate::error::CustomError;
+use crate::state::bnpl::BnplLoan;
+use crate::state::migration::{AccountMigrated, LegacyPurchase, MigrateAccount};
+use crate::state::product::{Product, Purchase};
+use crate::state::Escrow;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+
+/// Grow a store's escrow to the current layout. Its delivery, dispute and
+/// settlement holds start empty, so what it held stays withdrawable.
+pub fn migrate_escrow(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate_appended::<Escrow>(ctx.accounts, Escrow::LEN)
+}
+
+/// Grow a product to the current layout, with no sales, limits, barcode,
+/// attributes or category
+pub fn migrate_product(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate_appended::<Product>(ctx.accounts, Product::LEN)
+}
+
+/// Grow a BNPL loan to the current layout, with no late installments counted
+pub fn migrate_bnpl_loan(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate_appended::<BnplLoan>(ctx.accounts, BnplLoan::LEN)
+}
+
+/// Rewrite a purchase receipt in the current layout, which inserted fields
+/// between the old ones
+pub fn migrate_purchase(ctx: Context<MigrateAccount>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let info = accounts.account.to_account_info();
+    let old_len = legacy_len::<Purchase>(&info, Purchase::LEN)?;
+    let receipt = {
+        let data = info.try_borrow_data()?;
+        LegacyPurchase::deserialize(&mut &data[8..])?.into_purchase()?
+    };
+
+    grow_account(accounts, Purchase::LEN)?;
+    receipt.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit_migrated(accounts, old_len, Purchase::LEN)
+}
+
+/// Migrate an account whose new fields were all appended, and start out
+/// zeroed
+fn migrate_appended<T>(accounts: &MigrateAccount, len: usize) -> Result<()>
+where
+    T: AccountDeserialize + Discriminator,
+{
+    let info = accounts.account.to_account_info();
+    let old_len = legacy_len::<T>(&info, len)?;
+    grow_account(accounts, len)?;
+    // Make sure the grown account reads back in the current layout
+    T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+    emit_migrated(accounts, old_len, len)
+}
+
+/// Length of an account holding a `T` shorter than the current `len`
+fn legacy_len<T: Discriminator>(info: &AccountInfo, len: usize) -> Result<usize> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.starts_with(T::DISCRIMINATOR),
+        CustomError::MigrationTypeMismatch
+    );
+    require!(data.len() < len, CustomError::AccountAlreadyMigrated);
+    Ok(data.len())
+}
+
+/// Top the account up to rent exemption at `len` bytes and zero-extend it
+fn grow_account(accounts: &MigrateAccount, len: usize) -> Result<()> {
+    let info = accounts.account.to_account_info();
+    let shortfall = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                Transfer {
+                    from: accounts.payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.realloc(len, true)?;
+    Ok(())
+}
+
+fn emit_migrated(accounts: &MigrateAccount, old_len: usize, new_len: usize) -> Result<()> {
+    emit!(AccountMigrated {
+        account: accounts.account.key(),
+        old_len: old_len as u64,
+        new_len: new_len as u64,
+        migrated_by: accounts.payer.key(),
+    });
+    Ok(())
+}
@@ -1,9 +1,24 @@
 // Submodules for instruction handlers
+pub mod achievement;
 pub mod admin;
 pub mod bnpl;
+pub mod bundle;
+pub mod catalog;
+pub mod category;
+pub mod coupon;
+pub mod delivery;
+pub mod dispute;
 pub mod loyalty;
+pub mod migration;
 pub mod product;
+pub mod reservation;
+pub mod returns;
+pub mod review;
+pub mod reward;
+pub mod settlement;
+pub mod stats;
 pub mod store;
+pub mod treasury;
 pub mod user;
 pub mod user_wallet;
 
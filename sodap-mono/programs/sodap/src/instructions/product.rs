@@ -1,19 +1,36 @@
 use crate::error::CustomError;
+use crate::state::coupon::{CartDiscounts, Coupon, CouponRedeemed};
 pub use crate::state::product::{
-    CartPurchased, DeactivateProduct, Product, PurchaseCart, RegisterProduct, UpdateProduct,
+    BarcodeIndex, CartPurchased, CloseProduct, CreateProductVariant, DeactivateProduct, LowStock,
+    OpenPurchaseCounter, OutOfStock, Product, ProductArchived, ProductRestocked,
+    ProductTokenMinted, ProductVariant, PurchaseCart, PurchaseCounter, RegisterProduct,
+    RestockProduct, SaleEnded, SaleStarted, SyncSalePrice, UpdateProduct, UpdateProductVariant,
+    MAX_SUPPLIER_LEN,
 };
+use crate::state::reward::VoucherRedeemed;
 use crate::state::store::Store;
-use crate::state::{Escrow, RefundEscrow, ReleaseEscrow};
-use crate::types::{StablePrice, TokenizedType, TransactionStatus};
+use crate::state::Escrow;
+use crate::types::{
+    CartOrder, Currency, ProductAttribute, ProductChanges, ProductRegistration, PurchaseLimits,
+    ScheduledPrice, StablePrice, TokenizedType,
+};
+use crate::utils::auth::{allowlist_leaf, verify_merkle_proof};
+use crate::utils::pda::{create_pda_account, find_barcode_pda};
+use crate::utils::pricing::PriceConverter;
+use crate::utils::token::{create_product_mint, TokenMintAccounts};
 use anchor_lang::prelude::*;
-use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::system_program;
+use anchor_spl::associated_token;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::{self, MintTo, SetAuthority};
 
 #[derive(Accounts)]
 pub struct StoreEscrow<'info> {
     #[account(
         init_if_needed,
         payer = payer,
-        space = 8 + 32 + 8, // store pubkey + balance
+        space = Escrow::LEN,
         seeds = [b"escrow", store.key().as_ref()],
         bump
     )]
@@ -27,15 +44,19 @@ pub struct StoreEscrow<'info> {
 // Product instructions
 pub fn register_product(
     ctx: Context<RegisterProduct>,
-    product_uuid: [u8; 16],
-    price: u64,
-    usdc_price: u64,
-    sol_price: u64,
-    stock: u64,
-    tokenized_type: TokenizedType,
-    metadata_uri: String,
-    is_fixed_pricing: bool,
+    registration: ProductRegistration,
 ) -> Result<()> {
+    let ProductRegistration {
+        product_uuid,
+        price,
+        usdc_price,
+        sol_price,
+        stock,
+        tokenized_type,
+        metadata_uri,
+        is_fixed_pricing,
+        gtin_hash,
+    } = registration;
     let product = &mut ctx.accounts.product;
     product.uuid = product_uuid;
     product.price = price; // Keep legacy field for backward compatibility
@@ -54,6 +75,35 @@ pub fn register_product(
     product.is_active = true;
     product.store = ctx.accounts.store.key();
     product.authority = ctx.accounts.authority.key();
+    product.price_schedule = Vec::new();
+    product.active_sale_start = None;
+    product.purchase_limits = PurchaseLimits::default();
+    product.gtin_hash = gtin_hash;
+    product.open_reservations = 0;
+    product.reorder_threshold = 0;
+    product.is_digital = false;
+    product.attributes = Vec::new();
+    product.category = None;
+
+    if let Some(gtin_hash) = gtin_hash {
+        let index = BarcodeIndex {
+            store: product.store,
+            gtin_hash,
+            product: product.key(),
+            product_uuid,
+        };
+        let index_info = ctx
+            .accounts
+            .barcode_index
+            .as_ref()
+            .ok_or(CustomError::BarcodeIndexMismatch)?;
+        create_barcode_index(
+            &index,
+            &index_info.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
 
     Ok(())
 }
@@ -61,14 +111,58 @@ pub fn register_product(
 pub fn update_product(
     ctx: Context<UpdateProduct>,
     _product_uuid: [u8; 16],
-    new_price: Option<u64>,
-    new_usdc_price: Option<u64>,
-    new_sol_price: Option<u64>,
-    new_stock: Option<u64>,
-    new_metadata_uri: Option<String>,
-    new_tokenized_type: Option<TokenizedType>,
-    update_price_timestamp: Option<bool>,
+    changes: ProductChanges,
 ) -> Result<()> {
+    let ProductChanges {
+        new_price,
+        new_usdc_price,
+        new_sol_price,
+        new_stock,
+        new_metadata_uri,
+        new_tokenized_type,
+        update_price_timestamp,
+        new_gtin_hash,
+    } = changes;
+    // Move the barcode index over when the product's GTIN changes
+    if let Some(gtin_hash) = new_gtin_hash {
+        let accounts = &ctx.accounts;
+        if accounts.product.gtin_hash != Some(gtin_hash) {
+            if let Some(current) = accounts.product.gtin_hash {
+                let old_index = accounts
+                    .old_barcode_index
+                    .as_ref()
+                    .ok_or(CustomError::BarcodeIndexMismatch)?;
+                require!(
+                    old_index.gtin_hash == current,
+                    CustomError::BarcodeIndexMismatch
+                );
+                old_index.close(accounts.authority.to_account_info())?;
+            }
+
+            let index = BarcodeIndex {
+                store: accounts.store.key(),
+                gtin_hash,
+                product: accounts.product.key(),
+                product_uuid: accounts.product.uuid,
+            };
+            let index_info = accounts
+                .barcode_index
+                .as_ref()
+                .ok_or(CustomError::BarcodeIndexMismatch)?;
+            let system_program_info = accounts
+                .system_program
+                .as_ref()
+                .ok_or(CustomError::BarcodeIndexMismatch)?;
+            create_barcode_index(
+                &index,
+                &index_info.to_account_info(),
+                &accounts.authority.to_account_info(),
+                &system_program_info.to_account_info(),
+            )?;
+            ctx.accounts.product.gtin_hash = Some(gtin_hash);
+        }
+    }
+
     let product = &mut ctx.accounts.product;
 
     if let Some(price) = new_price {
@@ -94,7 +188,9 @@ pub fn update_product(
     }
 
     if let Some(stock) = new_stock {
+        let previous_stock = product.stock;
         product.stock = stock;
+        emit_stock_alerts(product, product.key(), None, previous_stock, stock)?;
     }
 
     if let Some(metadata_uri) = new_metadata_uri {
@@ -108,77 +204,1015 @@ pub fn update_product(
     Ok(())
 }
 
+/// Create the barcode index PDA for `index.gtin_hash` in the product's store
+pub(crate) fn create_barcode_index<'info>(
+    index: &BarcodeIndex,
+    index_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+) -> Result<()> {
+    let (index_key, bump) = find_barcode_pda(&crate::ID, &index.store, &index.gtin_hash);
+    require_keys_eq!(
+        index_info.key(),
+        index_key,
+        CustomError::BarcodeIndexMismatch
+    );
+    // An existing index means the barcode already belongs to a product
+    require!(index_info.data_is_empty(), CustomError::BarcodeInUse);
+
+    create_pda_account(
+        index_info,
+        payer,
+        system_program_info,
+        &[
+            b"barcode",
+            index.store.as_ref(),
+            index.gtin_hash.as_ref(),
+            &[bump],
+        ],
+        BarcodeIndex::LEN,
+    )?;
+    index.try_serialize(&mut &mut index_info.try_borrow_mut_data()?[..])
+}
+
 pub fn deactivate_product(ctx: Context<DeactivateProduct>, _product_uuid: [u8; 16]) -> Result<()> {
     let product = &mut ctx.accounts.product;
     product.is_active = false;
     Ok(())
 }
 
-/// Validate product cart items against remaining accounts and calculate total
+/// Set the stock level below which `LowStock` alerts are emitted
+pub fn set_reorder_threshold(
+    ctx: Context<UpdateProduct>,
+    _product_uuid: [u8; 16],
+    threshold: u64,
+) -> Result<()> {
+    let product = &mut ctx.accounts.product;
+    product.reorder_threshold = threshold;
+
+    msg!(
+        "Reorder threshold for product {:?} set to {}",
+        product.uuid,
+        threshold
+    );
+    Ok(())
+}
+
+/// Replace a product's typed attributes
+pub fn set_product_attributes(
+    ctx: Context<UpdateProduct>,
+    _product_uuid: [u8; 16],
+    attributes: Vec<ProductAttribute>,
+) -> Result<()> {
+    validate_attributes(&attributes, Product::MAX_ATTRIBUTES)?;
+
+    let product = &mut ctx.accounts.product;
+    product.attributes = attributes;
+
+    msg!("Attributes updated for product {:?}", product.uuid);
+    Ok(())
+}
+
+/// Mark a product as digital, so escrow for it waits until delivery is recorded
+pub fn set_digital_delivery(
+    ctx: Context<UpdateProduct>,
+    _product_uuid: [u8; 16],
+    is_digital: bool,
+) -> Result<()> {
+    let product = &mut ctx.accounts.product;
+    product.is_digital = is_digital;
+
+    msg!(
+        "Product {:?} digital delivery: {}",
+        product.uuid,
+        is_digital
+    );
+    Ok(())
+}
+
+/// Whether any of the first `count` products in remaining accounts is digital
+pub(crate) fn has_digital_products<'info>(
+    count: usize,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<bool> {
+    for product_info in remaining_accounts.iter().take(count) {
+        if Account::<Product>::try_from(product_info)?.is_digital {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Record a delivery from a supplier and add it to the product's (or variant's) stock
+pub fn restock_product(
+    ctx: Context<RestockProduct>,
+    supplier: String,
+    quantity: u64,
+    cost: u64,
+) -> Result<()> {
+    require!(quantity > 0, CustomError::InvalidParameters);
+    require!(
+        supplier.len() <= MAX_SUPPLIER_LEN,
+        CustomError::InvalidParameters
+    );
+
+    let product_key = ctx.accounts.product.key();
+    let (variant_id, stock) = match ctx.accounts.variant.as_mut() {
+        Some(variant) => {
+            variant.stock = adjust_stock(variant.stock, quantity, true)?;
+            (Some(variant.variant_id), variant.stock)
+        }
+        None => {
+            let product = &mut ctx.accounts.product;
+            product.stock = adjust_stock(product.stock, quantity, true)?;
+            (None, product.stock)
+        }
+    };
+
+    emit!(ProductRestocked {
+        product: product_key,
+        store: ctx.accounts.store.key(),
+        variant_id,
+        supplier,
+        quantity,
+        cost,
+        stock,
+        restocked_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Emit `OutOfStock` or `LowStock` when a stock level crosses zero or the
+/// product's reorder threshold. Levels already below stay quiet.
+pub(crate) fn emit_stock_alerts(
+    product: &Product,
+    product_key: Pubkey,
+    variant_id: Option<[u8; 16]>,
+    previous_stock: u64,
+    stock: u64,
+) -> Result<()> {
+    if stock == 0 && previous_stock > 0 {
+        emit!(OutOfStock {
+            product: product_key,
+            store: product.store,
+            variant_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    } else if stock < product.reorder_threshold && previous_stock >= product.reorder_threshold {
+        emit!(LowStock {
+            product: product_key,
+            store: product.store,
+            variant_id,
+            stock,
+            threshold: product.reorder_threshold,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    Ok(())
+}
+
+/// Close a deactivated product and refund its rent to the store. The product's
+/// last name and price are emitted so old receipts can still be displayed.
+pub fn close_product(ctx: Context<CloseProduct>, _product_uuid: [u8; 16]) -> Result<()> {
+    let product = &ctx.accounts.product;
+
+    require!(!product.is_active, CustomError::ProductStillActive);
+    require!(
+        product.open_reservations == 0,
+        CustomError::ProductHasOpenReservations
+    );
+    require!(
+        ctx.accounts.escrow_account.balance == 0,
+        CustomError::EscrowNotEmpty
+    );
+    if let Some(category) = product.category {
+        let uuid = product.uuid;
+        let index = ctx
+            .accounts
+            .category_index
+            .as_mut()
+            .ok_or(CustomError::CategoryIndexMismatch)?;
+        require_keys_eq!(index.category, category, CustomError::CategoryIndexMismatch);
+        index
+            .product_uuids
+            .retain(|product_uuid| *product_uuid != uuid);
+    }
+    let product = &ctx.accounts.product;
+    if let Some(gtin_hash) = product.gtin_hash {
+        let index = ctx
+            .accounts
+            .barcode_index
+            .as_ref()
+            .ok_or(CustomError::BarcodeIndexMismatch)?;
+        require!(
+            index.gtin_hash == gtin_hash,
+            CustomError::BarcodeIndexMismatch
+        );
+    }
+
+    emit!(ProductArchived {
+        product: product.key(),
+        store: product.store,
+        uuid: product.uuid,
+        metadata_uri: product.metadata_uri.clone(),
+        price: product.price,
+        usdc_price: product.stable_pricing.usdc_price,
+        sol_price: product.stable_pricing.sol_price,
+        tokenized_type: product.tokenized_type,
+        closed_at: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Product {:?} closed", product.uuid);
+    Ok(())
+}
+
+/// Replace a product's schedule of sale prices
+pub fn set_price_schedule(
+    ctx: Context<UpdateProduct>,
+    _product_uuid: [u8; 16],
+    schedule: Vec<ScheduledPrice>,
+) -> Result<()> {
+    require!(
+        schedule.len() <= Product::MAX_SCHEDULED_PRICES,
+        CustomError::InvalidPriceSchedule
+    );
+
+    // Sales must be well formed, sorted and non-overlapping
+    let mut previous_end = i64::MIN;
+    for sale in &schedule {
+        require!(
+            sale.start_time < sale.end_time && sale.start_time >= previous_end,
+            CustomError::InvalidPriceSchedule
+        );
+        require!(sale.sol_price > 0, CustomError::InvalidPriceSchedule);
+        previous_end = sale.end_time;
+    }
+
+    let product = &mut ctx.accounts.product;
+    let product_key = product.key();
+    product.price_schedule = schedule;
+    sync_sale_state(product, product_key, Clock::get()?.unix_timestamp);
+
+    msg!(
+        "Price schedule set with {} sale(s)",
+        product.price_schedule.len()
+    );
+    Ok(())
+}
+
+/// Announce sales that started or ended since the product was last touched
+pub fn sync_sale_price(ctx: Context<SyncSalePrice>) -> Result<()> {
+    let product = &mut ctx.accounts.product;
+    let product_key = product.key();
+    sync_sale_state(product, product_key, Clock::get()?.unix_timestamp);
+    Ok(())
+}
+
+fn sync_sale_state(product: &mut Product, product_key: Pubkey, now: i64) {
+    let active = product.active_sale(now).copied();
+    let active_start = active.map(|sale| sale.start_time);
+    if product.active_sale_start == active_start {
+        return;
+    }
+
+    if let Some(start_time) = product.active_sale_start {
+        emit!(SaleEnded {
+            product: product_key,
+            product_uuid: product.uuid,
+            start_time,
+            timestamp: now,
+        });
+    }
+    if let Some(sale) = active {
+        emit!(SaleStarted {
+            product: product_key,
+            product_uuid: product.uuid,
+            start_time: sale.start_time,
+            end_time: sale.end_time,
+            usdc_price: sale.usdc_price,
+            sol_price: sale.sol_price,
+            timestamp: now,
+        });
+    }
+    product.active_sale_start = active_start;
+}
+
+/// Set per-order and per-wallet caps, a sale start time and an allowlist on a product
+pub fn set_purchase_limits(
+    ctx: Context<UpdateProduct>,
+    _product_uuid: [u8; 16],
+    limits: PurchaseLimits,
+) -> Result<()> {
+    require!(
+        limits.max_per_wallet == 0
+            || limits.max_per_order == 0
+            || limits.max_per_order <= limits.max_per_wallet,
+        CustomError::InvalidParameters
+    );
+
+    let product = &mut ctx.accounts.product;
+    product.purchase_limits = limits;
+
+    msg!("Purchase limits updated for product {:?}", product.uuid);
+    Ok(())
+}
+
+/// Create the buyer's counter for a limited product. For allowlisted products
+/// the Merkle proof of the buyer's wallet is checked here, once.
+pub fn open_purchase_counter(
+    ctx: Context<OpenPurchaseCounter>,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let product = &ctx.accounts.product;
+    let counter = &mut ctx.accounts.counter;
+    let buyer = ctx.accounts.buyer.key();
+
+    if counter.buyer == Pubkey::default() {
+        counter.product = product.key();
+        counter.buyer = buyer;
+        counter.purchased = 0;
+        counter.allowlisted = false;
+    }
+
+    if let Some(root) = product.purchase_limits.allowlist_root {
+        if !counter.allowlisted {
+            require!(
+                verify_merkle_proof(&proof, &root, allowlist_leaf(&buyer)),
+                CustomError::NotAllowlisted
+            );
+            counter.allowlisted = true;
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the buyer's purchase counter for a product among the remaining accounts
+fn find_purchase_counter<'b>(
+    remaining_accounts: &'b [AccountInfo<'b>],
+    product: &Pubkey,
+    buyer: &Pubkey,
+) -> Result<&'b AccountInfo<'b>> {
+    let (counter_key, _) = Pubkey::find_program_address(
+        &[b"purchase_counter", product.as_ref(), buyer.as_ref()],
+        &crate::ID,
+    );
+    remaining_accounts
+        .iter()
+        .find(|info| info.key() == counter_key)
+        .ok_or(CustomError::PurchaseCounterMissing.into())
+}
+
+/// Add a size/color variant with its own stock to a product
+pub fn create_product_variant(
+    ctx: Context<CreateProductVariant>,
+    variant_id: [u8; 16],
+    attributes: Vec<ProductAttribute>,
+    price_override: Option<u64>,
+    stock: u64,
+) -> Result<()> {
+    validate_attributes(&attributes, ProductVariant::MAX_ATTRIBUTES)?;
+
+    let variant = &mut ctx.accounts.variant;
+    variant.product = ctx.accounts.product.key();
+    variant.store = ctx.accounts.store.key();
+    variant.variant_id = variant_id;
+    variant.attributes = attributes;
+    variant.price_override = price_override;
+    variant.stock = stock;
+    variant.is_active = true;
+    variant.authority = ctx.accounts.authority.key();
+
+    msg!(
+        "Variant {:?} added to product {:?}",
+        variant_id,
+        ctx.accounts.product.uuid
+    );
+    Ok(())
+}
+
+pub fn update_product_variant(
+    ctx: Context<UpdateProductVariant>,
+    new_attributes: Option<Vec<ProductAttribute>>,
+    new_price_override: Option<u64>,
+    new_stock: Option<u64>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let variant = &mut ctx.accounts.variant;
+
+    if let Some(attributes) = new_attributes {
+        validate_attributes(&attributes, ProductVariant::MAX_ATTRIBUTES)?;
+        variant.attributes = attributes;
+    }
+
+    if let Some(price) = new_price_override {
+        variant.price_override = Some(price);
+    }
+
+    if let Some(stock) = new_stock {
+        variant.stock = stock;
+    }
+
+    if let Some(active) = is_active {
+        variant.is_active = active;
+    }
+
+    Ok(())
+}
+
+fn validate_attributes(attributes: &[ProductAttribute], max_attributes: usize) -> Result<()> {
+    require!(
+        attributes.len() <= max_attributes,
+        CustomError::InvalidParameters
+    );
+    require!(
+        attributes.iter().all(ProductAttribute::is_valid),
+        CustomError::InvalidParameters
+    );
+    Ok(())
+}
+
+/// Who is checking out and what discounts are offered
+struct CartCheckout<'a> {
+    store: Pubkey,
+    buyer: Pubkey,
+    coupon: Option<&'a Coupon>,
+    voucher_value: u64,
+    reserved: bool, // Stock is already held by a reservation
+}
+
+/// Cart price breakdown after discounts
+struct CartTotals {
+    cart_total: u64,
+    coupon_discount: u64,
+    voucher_discount: u64,
+    total_price: u64,
+    line_totals: Vec<u64>, // Undiscounted price of each line
+}
+
+/// Load the cart line's product from the remaining accounts, checking it is the
+/// store's product for `product_uuid`
+fn load_cart_product<'b>(
+    remaining_accounts: &'b [AccountInfo<'b>],
+    index: usize,
+    store: &Pubkey,
+    product_uuid: &[u8; 16],
+) -> Result<(&'b AccountInfo<'b>, Account<'b, Product>)> {
+    let product_info = remaining_accounts
+        .get(index)
+        .ok_or(CustomError::InvalidParameters)?;
+    let product = Account::<Product>::try_from(product_info)?;
+    require!(
+        product.store == *store && product.uuid == *product_uuid,
+        CustomError::ProductNotFound
+    );
+    Ok((product_info, product))
+}
+
+/// Validate product cart items against remaining accounts and calculate total.
+/// Remaining accounts hold the products in cart order, followed by the variant
+/// accounts of the lines that reference a variant. Products with purchase limits
+/// also need the buyer's purchase counter somewhere in the remaining accounts.
 fn validate_cart_and_payment<'a, 'b>(
     product_uuids: &'a [[u8; 16]],
+    variant_ids: &'a [Option<[u8; 16]>],
     quantities: &'a [u64],
     remaining_accounts: &'b [AccountInfo<'b>],
     total_amount_paid: u64,
-) -> Result<u64> {
+    checkout: &CartCheckout,
+) -> Result<CartTotals> {
     require!(
         product_uuids.len() == quantities.len() && !product_uuids.is_empty(),
         CustomError::InvalidCart
     );
+    require!(
+        variant_ids.is_empty() || variant_ids.len() == product_uuids.len(),
+        CustomError::InvalidCart
+    );
 
+    let current_time = Clock::get()?.unix_timestamp;
     let mut total_price = 0u64;
+    let mut eligible_subtotal = 0u64;
+    let mut line_totals = Vec::with_capacity(product_uuids.len());
+    let mut variant_index = product_uuids.len();
     let mut i = 0;
     while i < product_uuids.len() {
-        let acc_info = &remaining_accounts[i];
-        let product = Account::<Product>::try_from(acc_info)?;
-        require!(
-            product.uuid == product_uuids[i],
-            CustomError::ProductNotFound
-        );
+        let (acc_info, product) =
+            load_cart_product(remaining_accounts, i, &checkout.store, &product_uuids[i])?;
         require!(product.is_active, CustomError::ProductNotFound);
-        require!(
-            product.stock >= quantities[i],
-            CustomError::InsufficientStock
-        );
+        check_purchase_limits(
+            &product,
+            acc_info.key(),
+            product_uuids,
+            quantities,
+            remaining_accounts,
+            checkout.buyer,
+        )?;
+
+        // Variant lines are priced and stocked by the variant
+        let unit_price = match variant_ids.get(i).copied().flatten() {
+            Some(variant_id) => {
+                let variant_info = remaining_accounts
+                    .get(variant_index)
+                    .ok_or(CustomError::VariantNotFound)?;
+                let variant = Account::<ProductVariant>::try_from(variant_info)?;
+                require!(
+                    variant.product == acc_info.key() && variant.variant_id == variant_id,
+                    CustomError::VariantNotFound
+                );
+                require!(variant.is_active, CustomError::VariantNotFound);
+                require!(
+                    checkout.reserved || variant.stock >= quantities[i],
+                    CustomError::InsufficientStock
+                );
+                variant_index += 1;
+                variant.unit_price(&product, current_time)
+            }
+            None => {
+                require!(
+                    checkout.reserved || product.stock >= quantities[i],
+                    CustomError::InsufficientStock
+                );
+                product.unit_price(current_time)
+            }
+        };
 
         // Calculate price for this item
-        let item_total = product
-            .price
+        let item_total = unit_price
             .checked_mul(quantities[i])
             .ok_or(CustomError::ArithmeticError)?;
         total_price = total_price
             .checked_add(item_total)
             .ok_or(CustomError::ArithmeticError)?;
+        line_totals.push(item_total);
+        if checkout
+            .coupon
+            .is_some_and(|coupon| coupon.is_eligible(&product_uuids[i]))
+        {
+            eligible_subtotal = eligible_subtotal
+                .checked_add(item_total)
+                .ok_or(CustomError::ArithmeticError)?;
+        }
 
         i += 1;
     }
 
-    // Verify payment amount matches cart total
+    let CartDiscounts {
+        coupon_discount,
+        voucher_discount,
+        amount_due,
+    } = CartDiscounts::apply(
+        total_price,
+        eligible_subtotal,
+        checkout.coupon,
+        checkout.voucher_value,
+    )?;
+
+    // Verify payment amount matches cart total after discounts
     require!(
-        total_amount_paid >= total_price,
+        total_amount_paid >= amount_due,
         CustomError::InsufficientPayment
     );
 
-    Ok(total_price)
+    Ok(CartTotals {
+        cart_total: total_price,
+        coupon_discount,
+        voucher_discount,
+        total_price: amount_due,
+        line_totals,
+    })
+}
+
+/// Enforce a product's sale start, per-order cap, per-wallet cap and allowlist
+pub(crate) fn check_purchase_limits<'b>(
+    product: &Product,
+    product_key: Pubkey,
+    product_uuids: &[[u8; 16]],
+    quantities: &[u64],
+    remaining_accounts: &'b [AccountInfo<'b>],
+    buyer: Pubkey,
+) -> Result<()> {
+    let limits = &product.purchase_limits;
+    require!(
+        limits.sale_start == 0 || Clock::get()?.unix_timestamp >= limits.sale_start,
+        CustomError::SaleNotStarted
+    );
+    if limits.max_per_order == 0 && !limits.tracks_wallets() {
+        return Ok(());
+    }
+
+    // A product may be split over several lines, e.g. one per variant
+    let cart_quantity = product_uuids
+        .iter()
+        .zip(quantities)
+        .filter(|(uuid, _)| **uuid == product.uuid)
+        .try_fold(0u64, |total, (_, quantity)| total.checked_add(*quantity))
+        .ok_or(CustomError::ArithmeticError)?;
+    require!(
+        limits.max_per_order == 0 || cart_quantity <= limits.max_per_order,
+        CustomError::PurchaseLimitExceeded
+    );
+
+    if limits.tracks_wallets() {
+        let counter_info = find_purchase_counter(remaining_accounts, &product_key, &buyer)?;
+        let counter = Account::<PurchaseCounter>::try_from(counter_info)?;
+        require!(
+            limits.allowlist_root.is_none() || counter.allowlisted,
+            CustomError::NotAllowlisted
+        );
+        let wallet_total = counter
+            .purchased
+            .checked_add(cart_quantity)
+            .ok_or(CustomError::ArithmeticError)?;
+        require!(
+            limits.max_per_wallet == 0 || wallet_total <= limits.max_per_wallet,
+            CustomError::PurchaseLimitExceeded
+        );
+    }
+
+    Ok(())
+}
+
+/// Add each line's quantity to the buyer's counter for limited products
+pub(crate) fn record_wallet_purchases<'info>(
+    product_uuids: &[[u8; 16]],
+    quantities: &[u64],
+    remaining_accounts: &'info [AccountInfo<'info>],
+    store: Pubkey,
+    buyer: Pubkey,
+) -> Result<()> {
+    for (i, quantity) in quantities.iter().enumerate() {
+        let (product_info, product) =
+            load_cart_product(remaining_accounts, i, &store, &product_uuids[i])?;
+        if !product.purchase_limits.tracks_wallets() {
+            continue;
+        }
+
+        let counter_info = find_purchase_counter(remaining_accounts, &product_info.key(), &buyer)?;
+        let counter = &mut Account::<PurchaseCounter>::try_from(counter_info)?;
+        counter.purchased = counter
+            .purchased
+            .checked_add(*quantity)
+            .ok_or(CustomError::ArithmeticError)?;
+        counter.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+/// Take cart quantities out of product and variant stock, or put them back
+/// when `restore` is set. Uses the same remaining account layout as checkout.
+pub(crate) fn update_cart_stock<'info>(
+    product_uuids: &[[u8; 16]],
+    variant_ids: &[Option<[u8; 16]>],
+    quantities: &[u64],
+    remaining_accounts: &'info [AccountInfo<'info>],
+    store: Pubkey,
+    restore: bool,
+) -> Result<()> {
+    require!(
+        product_uuids.len() == quantities.len() && variant_ids.len() == quantities.len(),
+        CustomError::InvalidCart
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let mut variant_index = product_uuids.len();
+    let mut i = 0;
+    while i < product_uuids.len() {
+        let (product_info, mut product_account) =
+            load_cart_product(remaining_accounts, i, &store, &product_uuids[i])?;
+        let product_account = &mut product_account;
+        require!(
+            restore || product_account.is_active,
+            CustomError::ProductNotFound
+        );
+        if !restore {
+            sync_sale_state(product_account, product_info.key(), current_time);
+        }
+
+        match variant_ids[i] {
+            Some(variant_id) => {
+                let variant_info = remaining_accounts
+                    .get(variant_index)
+                    .ok_or(CustomError::VariantNotFound)?;
+                let variant_account = &mut Account::<ProductVariant>::try_from(variant_info)?;
+                require!(
+                    variant_account.product == product_info.key()
+                        && variant_account.variant_id == variant_id,
+                    CustomError::VariantNotFound
+                );
+                let previous_stock = variant_account.stock;
+                variant_account.stock = adjust_stock(previous_stock, quantities[i], restore)?;
+                variant_account.exit(&crate::ID)?;
+                emit_stock_alerts(
+                    product_account,
+                    product_info.key(),
+                    Some(variant_id),
+                    previous_stock,
+                    variant_account.stock,
+                )?;
+                variant_index += 1;
+            }
+            None => {
+                let previous_stock = product_account.stock;
+                product_account.stock = adjust_stock(previous_stock, quantities[i], restore)?;
+                emit_stock_alerts(
+                    product_account,
+                    product_info.key(),
+                    None,
+                    previous_stock,
+                    product_account.stock,
+                )?;
+            }
+        }
+        product_account.exit(&crate::ID)?;
+        i += 1;
+    }
+
+    Ok(())
+}
+
+/// Count reservation lines opened on, or released from, each cart product
+pub(crate) fn track_open_reservations<'info>(
+    product_uuids: &[[u8; 16]],
+    remaining_accounts: &'info [AccountInfo<'info>],
+    store: Pubkey,
+    opened: bool,
+) -> Result<()> {
+    for (i, product_uuid) in product_uuids.iter().enumerate() {
+        let (_, mut product_account) =
+            load_cart_product(remaining_accounts, i, &store, product_uuid)?;
+        let product_account = &mut product_account;
+        product_account.open_reservations = if opened {
+            product_account.open_reservations.checked_add(1)
+        } else {
+            product_account.open_reservations.checked_sub(1)
+        }
+        .ok_or(CustomError::ArithmeticError)?;
+        product_account.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn adjust_stock(stock: u64, quantity: u64, restore: bool) -> Result<u64> {
+    if restore {
+        stock
+            .checked_add(quantity)
+            .ok_or(CustomError::ArithmeticError.into())
+    } else {
+        stock
+            .checked_sub(quantity)
+            .ok_or(CustomError::InsufficientStock.into())
+    }
+}
+
+/// Mint an NFT for each NonFungible line and fungible units for each Fungible line.
+/// After the products and variants, remaining accounts hold a mint and the buyer's
+/// associated token account for every tokenized line, in cart order.
+fn mint_product_tokens<'info>(
+    accounts: &PurchaseCart<'info>,
+    receipt_key: Pubkey,
+    product_uuids: &[[u8; 16]],
+    variant_ids: &[Option<[u8; 16]>],
+    quantities: &[u64],
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<Option<Pubkey>>> {
+    let store_key = accounts.store.key();
+    let mut token_index =
+        product_uuids.len() + variant_ids.iter().filter(|id| id.is_some()).count();
+    let mut token_mints = Vec::with_capacity(product_uuids.len());
+
+    for (i, product_uuid) in product_uuids.iter().enumerate() {
+        let product_info = &remaining_accounts[i];
+        let product = Account::<Product>::try_from(product_info)?;
+        if product.tokenized_type == TokenizedType::None {
+            token_mints.push(None);
+            continue;
+        }
+
+        let token_program = accounts
+            .token_program
+            .as_ref()
+            .ok_or(CustomError::MissingTokenAccounts)?;
+        let associated_token_program = accounts
+            .associated_token_program
+            .as_ref()
+            .ok_or(CustomError::MissingTokenAccounts)?;
+        let mint_info = remaining_accounts
+            .get(token_index)
+            .ok_or(CustomError::MissingTokenAccounts)?;
+        let token_account_info = remaining_accounts
+            .get(token_index + 1)
+            .ok_or(CustomError::MissingTokenAccounts)?;
+        token_index += 2;
+
+        // The product PDA is the mint authority
+        let (product_key, product_bump) = Pubkey::find_program_address(
+            &[b"product", store_key.as_ref(), product_uuid.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(
+            product_key,
+            product_info.key(),
+            CustomError::ProductNotFound
+        );
+        let product_seeds: &[&[u8]] = &[
+            b"product",
+            store_key.as_ref(),
+            product_uuid.as_ref(),
+            &[product_bump],
+        ];
+
+        // NFTs get a fresh mint per receipt line, fungible units share one mint per product
+        let is_nft = product.tokenized_type == TokenizedType::NonFungible;
+        let line = [i as u8];
+        let mint_seed_prefix: &[&[u8]] = if is_nft {
+            require!(quantities[i] == 1, CustomError::InvalidCart);
+            &[b"product_nft", receipt_key.as_ref(), &line]
+        } else {
+            &[b"product_mint", product_key.as_ref()]
+        };
+        let (mint_key, mint_bump) = Pubkey::find_program_address(mint_seed_prefix, &crate::ID);
+        require_keys_eq!(mint_info.key(), mint_key, CustomError::InvalidProductMint);
+        let bump = [mint_bump];
+        let mut mint_seeds = mint_seed_prefix.to_vec();
+        mint_seeds.push(&bump);
+
+        if mint_info.data_is_empty() {
+            create_product_mint(
+                &TokenMintAccounts {
+                    payer: &accounts.buyer.to_account_info(),
+                    mint: mint_info,
+                    authority: product_info,
+                    token_program: &token_program.to_account_info(),
+                    system_program: &accounts.system_program.to_account_info(),
+                },
+                &mint_seeds,
+                product_seeds,
+                product.metadata_uri.clone(),
+            )?;
+        }
+
+        associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: accounts.buyer.to_account_info(),
+                associated_token: token_account_info.clone(),
+                authority: accounts.buyer.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: accounts.system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                MintTo {
+                    mint: mint_info.clone(),
+                    to: token_account_info.clone(),
+                    authority: product_info.clone(),
+                },
+                &[product_seeds],
+            ),
+            quantities[i],
+        )?;
+
+        // Fix the NFT supply at one
+        if is_nft {
+            token_2022::set_authority(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    SetAuthority {
+                        current_authority: product_info.clone(),
+                        account_or_mint: mint_info.clone(),
+                    },
+                    &[product_seeds],
+                ),
+                AuthorityType::MintTokens,
+                None,
+            )?;
+        }
+
+        emit!(ProductTokenMinted {
+            receipt: receipt_key,
+            product_uuid: *product_uuid,
+            mint: mint_key,
+            owner: accounts.buyer.key(),
+            amount: quantities[i],
+            tokenized_type: product.tokenized_type,
+        });
+
+        token_mints.push(Some(mint_key));
+    }
+
+    Ok(token_mints)
 }
 
 pub fn purchase_cart<'info>(
-    ctx: Context<'_, '_, 'info, 'info, PurchaseCart>,
-    product_uuids: Vec<[u8; 16]>,
-    quantities: Vec<u64>,
-    total_amount_paid: u64,
-    gas_fee: u64,
-    status: TransactionStatus,
+    ctx: Context<'_, '_, 'info, 'info, PurchaseCart<'info>>,
+    order_id: u64,
+    order: CartOrder,
 ) -> Result<()> {
+    let CartOrder {
+        product_uuids,
+        variant_ids,
+        quantities,
+        total_amount_paid,
+        gas_fee,
+        status,
+        coupon_code,
+    } = order;
     let remaining_accounts: &'info [AccountInfo<'info>] = ctx.remaining_accounts;
+    let current_time = Clock::get()?.unix_timestamp;
+    let variant_ids = if variant_ids.is_empty() {
+        vec![None; product_uuids.len()]
+    } else {
+        variant_ids
+    };
+
+    // A reservation already holds the stock for exactly this cart
+    let reserved = match &ctx.accounts.reservation {
+        Some(reservation) => {
+            require!(
+                !reservation.is_expired(current_time),
+                CustomError::ReservationExpired
+            );
+            require!(
+                reservation.product_uuids == product_uuids
+                    && reservation.variant_ids == variant_ids
+                    && reservation.quantities == quantities,
+                CustomError::ReservationMismatch
+            );
+            true
+        }
+        None => false,
+    };
+
+    // Vouchers can only be used once
+    let voucher_value = match &ctx.accounts.voucher {
+        Some(voucher) => {
+            require!(!voucher.is_redeemed, CustomError::VoucherAlreadyRedeemed);
+            voucher.value
+        }
+        None => 0,
+    };
+
+    // Coupons are matched against the hash of the code the buyer entered
+    require!(
+        ctx.accounts.coupon.is_some() || ctx.accounts.coupon_usage.is_none(),
+        CustomError::InvalidCoupon
+    );
+    if let Some(coupon) = &ctx.accounts.coupon {
+        let code = coupon_code.as_ref().ok_or(CustomError::InvalidCoupon)?;
+        require!(
+            hash(code.as_bytes()).to_bytes() == coupon.code_hash,
+            CustomError::InvalidCoupon
+        );
+        require!(coupon.is_active, CustomError::InvalidCoupon);
+        require!(
+            coupon.expires_at == 0 || current_time < coupon.expires_at,
+            CustomError::CouponExpired
+        );
+        require!(
+            coupon.max_uses == 0 || coupon.uses < coupon.max_uses,
+            CustomError::CouponLimitReached
+        );
+        let usage = ctx
+            .accounts
+            .coupon_usage
+            .as_ref()
+            .ok_or(CustomError::InvalidCoupon)?;
+        require!(
+            coupon.per_user_limit == 0 || usage.uses < coupon.per_user_limit,
+            CustomError::CouponLimitReached
+        );
+    }
 
     // Validate cart and get total price
-    let total_price = validate_cart_and_payment(
+    let totals = validate_cart_and_payment(
         &product_uuids,
+        &variant_ids,
         &quantities,
         remaining_accounts,
         total_amount_paid,
+        &CartCheckout {
+            store: ctx.accounts.store.key(),
+            buyer: ctx.accounts.buyer.key(),
+            coupon: ctx.accounts.coupon.as_deref(),
+            voucher_value,
+            reserved,
+        },
     )?;
+    let voucher_discount = totals.voucher_discount;
+    let total_price = totals.total_price;
+    msg!(
+        "Cart total {}, coupon discount {}, voucher discount {}",
+        totals.cart_total,
+        totals.coupon_discount,
+        voucher_discount
+    );
 
     // Transfer payment from buyer to escrow account
     let escrow_seeds = &[
@@ -197,98 +1231,168 @@ pub fn purchase_cart<'info>(
     );
     system_program::transfer(transfer_to_escrow, total_price)?;
 
-    // Update escrow balance
+    // Count the sale in the store's totals and today's sales
+    let store_key = ctx.accounts.store.key();
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.ensure_initialized(store_key, ctx.accounts.escrow_account.balance);
+    store_stats.record_sale(total_price, true)?;
+    let track_top_sellers = store_stats.track_top_sellers;
+    let daily_sales = &mut ctx.accounts.daily_sales;
+    daily_sales.ensure_initialized(store_key, current_time);
+    daily_sales.record_order(
+        Currency::SOL,
+        total_price,
+        &product_uuids,
+        &quantities,
+        track_top_sellers,
+    )?;
+
+    // Update escrow balance. Digital goods stay held until delivered, the
+    // rest waits for the order to settle.
+    let awaiting_delivery = has_digital_products(product_uuids.len(), remaining_accounts)?;
     let escrow = &mut ctx.accounts.escrow_account;
     escrow.balance = escrow
         .balance
         .checked_add(total_price)
         .ok_or(CustomError::ArithmeticError)?;
+    if awaiting_delivery {
+        escrow.held_for_delivery = escrow
+            .held_for_delivery
+            .checked_add(total_price)
+            .ok_or(CustomError::ArithmeticError)?;
+    } else {
+        escrow.unsettled = escrow
+            .unsettled
+            .checked_add(total_price)
+            .ok_or(CustomError::ArithmeticError)?;
+    }
 
-    // Update product stocks
-    let mut i = 0;
-    while i < product_uuids.len() {
-        let product_account = &mut Account::<Product>::try_from(&remaining_accounts[i])?;
-        product_account.stock = product_account
-            .stock
-            .checked_sub(quantities[i])
-            .ok_or(CustomError::StockUnderflow)?;
-        i += 1;
+    // Update product and variant stocks, unless a reservation already took it
+    if reserved {
+        track_open_reservations(&product_uuids, remaining_accounts, store_key, false)?;
+    } else {
+        update_cart_stock(
+            &product_uuids,
+            &variant_ids,
+            &quantities,
+            remaining_accounts,
+            store_key,
+            false,
+        )?;
     }
+    record_wallet_purchases(
+        &product_uuids,
+        &quantities,
+        remaining_accounts,
+        store_key,
+        ctx.accounts.buyer.key(),
+    )?;
+
+    // Mint ownership tokens for tokenized products
+    let receipt_key = ctx.accounts.receipt.key();
+    let token_mints = mint_product_tokens(
+        ctx.accounts,
+        receipt_key,
+        &product_uuids,
+        &variant_ids,
+        &quantities,
+        remaining_accounts,
+    )?;
 
     // Create receipt
     let receipt = &mut ctx.accounts.receipt;
     receipt.product_uuids = product_uuids.clone();
+    receipt.variant_ids = variant_ids.clone();
     receipt.quantities = quantities.clone();
     receipt.total_paid = total_price;
     receipt.gas_fee = gas_fee;
     receipt.status = status;
     receipt.store = ctx.accounts.store.key();
     receipt.buyer = ctx.accounts.buyer.key();
-    receipt.ts = Clock::get()?.unix_timestamp;
+    receipt.ts = current_time;
+    receipt.token_mints = token_mints;
+    receipt.coupon = ctx.accounts.coupon.as_ref().map(|coupon| coupon.key());
+    receipt.coupon_discount = totals.coupon_discount;
+    receipt.bundle = None;
+    receipt.awaiting_delivery = awaiting_delivery;
+    receipt.line_amounts = PriceConverter::split_by_weight(total_price, &totals.line_totals)?;
+    receipt.returned_quantities = vec![0; receipt.quantities.len()];
+    receipt.refunded_amount = 0;
+    receipt.disputed = false;
+    receipt.settled = false;
+    receipt.platform_fee = ctx.accounts.fee_config.fee_for(&receipt.store, total_price);
+    receipt.order_id = order_id;
+    receipt.release_after = ctx.accounts.escrow_account.settles_at(current_time);
+
+    // Count the coupon use globally and for this buyer
+    if let Some(coupon) = ctx.accounts.coupon.as_mut() {
+        let usage = ctx
+            .accounts
+            .coupon_usage
+            .as_mut()
+            .ok_or(CustomError::InvalidCoupon)?;
+        if usage.user == Pubkey::default() {
+            usage.coupon = coupon.key();
+            usage.user = ctx.accounts.buyer.key();
+        }
+        usage.uses = usage
+            .uses
+            .checked_add(1)
+            .ok_or(CustomError::ArithmeticError)?;
+        coupon.uses = coupon
+            .uses
+            .checked_add(1)
+            .ok_or(CustomError::ArithmeticError)?;
+
+        emit!(CouponRedeemed {
+            coupon: coupon.key(),
+            store: coupon.store,
+            buyer: ctx.accounts.buyer.key(),
+            receipt: receipt.key(),
+            discount: totals.coupon_discount,
+            uses: coupon.uses,
+            timestamp: current_time,
+        });
+    }
+
+    // Mark the voucher as spent on this receipt
+    if let Some(voucher) = ctx.accounts.voucher.as_mut() {
+        voucher.is_redeemed = true;
+        voucher.redeemed_at = current_time;
+        voucher.redeemed_receipt = Some(receipt.key());
+
+        emit!(VoucherRedeemed {
+            voucher: voucher.key(),
+            owner: voucher.owner,
+            store: voucher.store,
+            receipt: receipt.key(),
+            discount: voucher_discount,
+            timestamp: current_time,
+        });
+    }
+
+    if let Some(profile) = ctx.accounts.user_profile.as_mut() {
+        profile.total_purchases = profile
+            .total_purchases
+            .checked_add(1)
+            .ok_or(CustomError::ArithmeticError)?;
+    }
+    if let Some(loyalty_account) = ctx.accounts.loyalty_account.as_mut() {
+        loyalty_account.record_purchase()?;
+    }
 
     emit!(CartPurchased {
         store_id: ctx.accounts.store.key(),
         buyer_id: ctx.accounts.buyer.key(),
         product_uuids,
+        variant_ids,
         quantities,
         total_paid: total_price,
         gas_fee,
         timestamp: receipt.ts,
+        bundle: None,
+        platform_fee: receipt.platform_fee,
     });
 
     Ok(())
 }
-
-pub fn release_escrow(ctx: Context<ReleaseEscrow>, amount: u64) -> Result<()> {
-    let escrow_info = ctx.accounts.escrow_account.to_account_info();
-    let escrow = &mut ctx.accounts.escrow_account;
-
-    let cpi_accounts = Transfer {
-        from: escrow_info,
-        to: ctx.accounts.store_owner.to_account_info(),
-    };
-
-    let cpi_program = ctx.accounts.system_program.to_account_info();
-    let store_key = ctx.accounts.store.key();
-    let seeds = &[
-        b"escrow".as_ref(),
-        store_key.as_ref(),
-        &[ctx.bumps.escrow_account],
-    ];
-    let signer_seeds = &[&seeds[..]];
-
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-
-    system_program::transfer(cpi_ctx, amount)?;
-
-    escrow.balance = escrow.balance.checked_sub(amount).unwrap();
-
-    Ok(())
-}
-
-pub fn refund_from_escrow(ctx: Context<RefundEscrow>, amount: u64) -> Result<()> {
-    let escrow_info = ctx.accounts.escrow_account.to_account_info();
-    let escrow = &mut ctx.accounts.escrow_account;
-
-    let cpi_accounts = Transfer {
-        from: escrow_info,
-        to: ctx.accounts.buyer.to_account_info(),
-    };
-
-    let cpi_program = ctx.accounts.system_program.to_account_info();
-    let store_key = ctx.accounts.store.key();
-    let seeds = &[
-        b"escrow".as_ref(),
-        store_key.as_ref(),
-        &[ctx.bumps.escrow_account],
-    ];
-    let signer_seeds = &[&seeds[..]];
-
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-
-    system_program::transfer(cpi_ctx, amount)?;
-
-    escrow.balance = escrow.balance.checked_sub(amount).unwrap();
-
-    Ok(())
-}
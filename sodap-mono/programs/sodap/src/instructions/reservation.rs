@@ -0,0 +1,147 @@
+use crate::error::CustomError;
+use crate::instructions::product::{track_open_reservations, update_cart_stock};
+use crate::state::reservation::{
+    ItemsReserved, ReleaseReservation, Reservation, ReservationReleased, ReserveItems,
+};
+use anchor_lang::prelude::*;
+
+/// Configuration for inventory reservations
+pub struct ReservationConfig {
+    pub default_duration: i64, // Hold time in seconds when none is requested
+    pub max_duration: i64,     // Longest hold, e.g. while a BNPL loan is being approved
+}
+
+impl Default for ReservationConfig {
+    fn default() -> Self {
+        Self {
+            default_duration: 15 * 60,      // 15 minutes for a regular checkout
+            max_duration: 7 * 24 * 60 * 60, // 7 days
+        }
+    }
+}
+
+/// Hold stock for a buyer until checkout or expiry.
+/// Remaining accounts follow the checkout layout: products, then variants.
+pub fn reserve_items<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ReserveItems<'info>>,
+    reservation_id: [u8; 16],
+    product_uuids: Vec<[u8; 16]>,
+    variant_ids: Vec<Option<[u8; 16]>>,
+    quantities: Vec<u64>,
+    duration: Option<i64>,
+) -> Result<()> {
+    let config = ReservationConfig::default();
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        product_uuids.len() == quantities.len() && !product_uuids.is_empty(),
+        CustomError::InvalidCart
+    );
+    require!(
+        product_uuids.len() <= Reservation::MAX_ITEMS,
+        CustomError::CartTooLarge
+    );
+    require!(
+        variant_ids.is_empty() || variant_ids.len() == product_uuids.len(),
+        CustomError::InvalidCart
+    );
+    require!(
+        quantities.iter().all(|quantity| *quantity > 0),
+        CustomError::InvalidCart
+    );
+
+    let duration = duration.unwrap_or(config.default_duration);
+    require!(
+        duration > 0 && duration <= config.max_duration,
+        CustomError::InvalidParameters
+    );
+
+    let variant_ids = if variant_ids.is_empty() {
+        vec![None; product_uuids.len()]
+    } else {
+        variant_ids
+    };
+
+    // Take the stock now so no other checkout can sell it
+    let store_key = ctx.accounts.store.key();
+    update_cart_stock(
+        &product_uuids,
+        &variant_ids,
+        &quantities,
+        ctx.remaining_accounts,
+        store_key,
+        false,
+    )?;
+    track_open_reservations(&product_uuids, ctx.remaining_accounts, store_key, true)?;
+
+    let reservation = &mut ctx.accounts.reservation;
+    reservation.store = store_key;
+    reservation.buyer = ctx.accounts.buyer.key();
+    reservation.reservation_id = reservation_id;
+    reservation.product_uuids = product_uuids;
+    reservation.variant_ids = variant_ids;
+    reservation.quantities = quantities;
+    reservation.created_at = current_time;
+    reservation.expires_at = current_time
+        .checked_add(duration)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    emit!(ItemsReserved {
+        reservation: reservation.key(),
+        store: reservation.store,
+        buyer: reservation.buyer,
+        product_uuids: reservation.product_uuids.clone(),
+        quantities: reservation.quantities.clone(),
+        expires_at: reservation.expires_at,
+    });
+
+    msg!(
+        "Reserved {} line(s) for {} until {}",
+        reservation.product_uuids.len(),
+        reservation.buyer,
+        reservation.expires_at
+    );
+    Ok(())
+}
+
+/// Return reserved stock. The buyer can release at any time, anyone else
+/// only after the reservation has expired.
+pub fn release_reservation<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ReleaseReservation<'info>>,
+) -> Result<()> {
+    let reservation = &ctx.accounts.reservation;
+    let current_time = Clock::get()?.unix_timestamp;
+    let expired = reservation.is_expired(current_time);
+
+    require!(
+        expired || ctx.accounts.caller.key() == reservation.buyer,
+        CustomError::ReservationNotExpired
+    );
+
+    update_cart_stock(
+        &reservation.product_uuids,
+        &reservation.variant_ids,
+        &reservation.quantities,
+        ctx.remaining_accounts,
+        reservation.store,
+        true,
+    )?;
+    track_open_reservations(
+        &reservation.product_uuids,
+        ctx.remaining_accounts,
+        reservation.store,
+        false,
+    )?;
+
+    emit!(ReservationReleased {
+        reservation: reservation.key(),
+        store: reservation.store,
+        buyer: reservation.buyer,
+        released_by: ctx.accounts.caller.key(),
+        expired,
+        timestamp: current_time,
+    });
+
+    msg!("Reservation released for {}", reservation.buyer);
+    Ok(())
+}
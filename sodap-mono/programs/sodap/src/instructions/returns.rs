@@ -0,0 +1,216 @@
+use crate::error::CustomError;
+use crate::instructions::loyalty::reverse_receipt_points;
+use crate::instructions::product::adjust_stock;
+use crate::state::returns::{
+    ApproveReturn, RejectReturn, RequestReturn, ReturnApproved, ReturnRejected, ReturnRequested,
+};
+use crate::types::{Currency, ReturnReason, TransactionStatus};
+use anchor_lang::prelude::*;
+
+/// Ask the store to take back `quantity` units of one receipt line
+pub fn request_return(
+    ctx: Context<RequestReturn>,
+    line: u8,
+    quantity: u64,
+    reason: ReturnReason,
+) -> Result<()> {
+    let receipt = &ctx.accounts.receipt;
+    let index = line as usize;
+    require!(
+        matches!(
+            receipt.status,
+            TransactionStatus::Completed | TransactionStatus::PartiallyRefunded
+        ),
+        CustomError::InvalidReturn
+    );
+    require!(!receipt.disputed, CustomError::OrderDisputed);
+    require!(
+        index < receipt.product_uuids.len(),
+        CustomError::InvalidReturn
+    );
+    // Ownership tokens stay with the buyer, so tokenized lines cannot come back
+    require!(
+        receipt.token_mints.get(index).copied().flatten().is_none(),
+        CustomError::InvalidReturn
+    );
+    require!(
+        quantity > 0 && quantity <= receipt.returnable(index),
+        CustomError::ReturnQuantityExceeded
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let return_request = &mut ctx.accounts.return_request;
+    return_request.receipt = receipt.key();
+    return_request.store = receipt.store;
+    return_request.buyer = receipt.buyer;
+    return_request.line = line;
+    return_request.quantity = quantity;
+    return_request.reason = reason;
+    return_request.requested_at = current_time;
+
+    emit!(ReturnRequested {
+        return_request: return_request.key(),
+        receipt: receipt.key(),
+        store: receipt.store,
+        buyer: receipt.buyer,
+        line,
+        product_uuid: receipt.product_uuids[index],
+        quantity,
+        reason,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Accept a return: refund the line's share of the payment from escrow,
+/// restock the units and claw back the loyalty points they earned
+pub fn approve_return(ctx: Context<ApproveReturn>) -> Result<()> {
+    let line = ctx.accounts.return_request.line;
+    let index = line as usize;
+    let quantity = ctx.accounts.return_request.quantity;
+    let receipt = &ctx.accounts.receipt;
+    require!(!receipt.disputed, CustomError::OrderDisputed);
+    require!(
+        quantity <= receipt.returnable(index),
+        CustomError::ReturnQuantityExceeded
+    );
+    let product_uuid = receipt.product_uuids[index];
+    require!(
+        ctx.accounts.product.uuid == product_uuid,
+        CustomError::ProductNotFound
+    );
+
+    // Put the units back on the shelf they were sold from
+    match receipt.variant_ids.get(index).copied().flatten() {
+        Some(variant_id) => {
+            let variant = ctx
+                .accounts
+                .variant
+                .as_mut()
+                .ok_or(CustomError::VariantNotFound)?;
+            require!(
+                variant.variant_id == variant_id,
+                CustomError::VariantNotFound
+            );
+            variant.stock = adjust_stock(variant.stock, quantity, true)?;
+        }
+        None => {
+            let product = &mut ctx.accounts.product;
+            product.stock = adjust_stock(product.stock, quantity, true)?;
+        }
+    }
+
+    let refund_amount = receipt.refund_for(index, quantity);
+
+    // Unsettled orders are refunded from their own share of escrow, settled
+    // ones from what the store has not withdrawn yet
+    let awaiting_delivery = receipt.awaiting_delivery;
+    let settled = receipt.settled;
+    let escrow = &ctx.accounts.escrow_account;
+    let available = if settled {
+        escrow.releasable()
+    } else {
+        escrow.balance.saturating_sub(escrow.frozen)
+    };
+    require!(available >= refund_amount, CustomError::InsufficientFunds);
+
+    // The escrow is a program account, so its lamports are moved directly
+    if refund_amount > 0 {
+        ctx.accounts.escrow_account.sub_lamports(refund_amount)?;
+        ctx.accounts.buyer.add_lamports(refund_amount)?;
+    }
+
+    let escrow = &mut ctx.accounts.escrow_account;
+    escrow.balance = escrow
+        .balance
+        .checked_sub(refund_amount)
+        .ok_or(CustomError::InsufficientFunds)?;
+    if awaiting_delivery {
+        escrow.held_for_delivery = escrow.held_for_delivery.saturating_sub(refund_amount);
+    } else if !settled {
+        escrow.unsettled = escrow.unsettled.saturating_sub(refund_amount);
+    }
+    ctx.accounts.store_stats.record_refund(refund_amount)?;
+    let store_key = ctx.accounts.store.key();
+    let daily_sales = &mut ctx.accounts.daily_sales;
+    daily_sales.ensure_initialized(store_key, Clock::get()?.unix_timestamp);
+    daily_sales.record_refund(Currency::SOL, refund_amount, quantity)?;
+
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.returned_quantities[index] += quantity;
+    receipt.refunded_amount = receipt
+        .refunded_amount
+        .checked_add(refund_amount)
+        .ok_or(CustomError::ArithmeticError)?;
+    receipt.status = if (0..receipt.quantities.len()).all(|i| receipt.returnable(i) == 0) {
+        TransactionStatus::Refunded
+    } else {
+        TransactionStatus::PartiallyRefunded
+    };
+    let receipt_key = receipt.key();
+    let status = receipt.status;
+
+    let accounts = ctx.accounts;
+    let points_reversed = match (
+        accounts.loyalty_account.as_mut(),
+        accounts.loyalty_program.as_ref(),
+        accounts.earned_transaction.as_mut(),
+        accounts.transaction_record.as_mut(),
+    ) {
+        (
+            Some(loyalty_account),
+            Some(loyalty_program),
+            Some(earned_transaction),
+            Some(transaction_record),
+        ) => {
+            require_keys_eq!(
+                earned_transaction.loyalty_account,
+                loyalty_account.key(),
+                CustomError::LoyaltyReceiptMismatch
+            );
+            reverse_receipt_points(
+                loyalty_account,
+                loyalty_program,
+                earned_transaction,
+                transaction_record,
+                accounts.authority.key(),
+                refund_amount,
+            )?
+        }
+        (None, _, None, None) => 0,
+        _ => return err!(CustomError::LoyaltyReceiptMismatch),
+    };
+
+    emit!(ReturnApproved {
+        receipt: receipt_key,
+        store: accounts.store.key(),
+        buyer: accounts.buyer.key(),
+        line,
+        product_uuid,
+        quantity,
+        refund_amount,
+        points_reversed,
+        status,
+        approved_by: accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Turn down a return request, giving its rent back to the buyer
+pub fn reject_return(ctx: Context<RejectReturn>) -> Result<()> {
+    let return_request = &ctx.accounts.return_request;
+    emit!(ReturnRejected {
+        receipt: return_request.receipt,
+        store: return_request.store,
+        buyer: return_request.buyer,
+        line: return_request.line,
+        quantity: return_request.quantity,
+        rejected_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
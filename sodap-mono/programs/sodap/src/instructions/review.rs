@@ -0,0 +1,90 @@
+use crate::error::CustomError;
+use crate::state::review::{PostReview, Review, ReviewPosted, UpdateReview};
+use crate::types::TransactionStatus;
+use anchor_lang::prelude::*;
+
+/// Rate a product bought on line `line` of the reviewer's receipt.
+/// Each receipt line can be reviewed once.
+pub fn post_review(
+    ctx: Context<PostReview>,
+    line: u8,
+    rating: u8,
+    content_uri: String,
+    content_hash: [u8; 32],
+) -> Result<()> {
+    validate_review(rating, &content_uri)?;
+
+    let receipt = &ctx.accounts.receipt;
+    require!(
+        receipt.status == TransactionStatus::Completed,
+        CustomError::ReviewNotEligible
+    );
+    require!(
+        receipt.product_uuids.get(line as usize) == Some(&ctx.accounts.product.uuid),
+        CustomError::ReviewNotEligible
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let review = &mut ctx.accounts.review;
+    review.product = ctx.accounts.product.key();
+    review.store = ctx.accounts.product.store;
+    review.reviewer = ctx.accounts.reviewer.key();
+    review.receipt = receipt.key();
+    review.line = line;
+    review.rating = rating;
+    review.content_uri = content_uri;
+    review.content_hash = content_hash;
+    review.created_at = current_time;
+    review.updated_at = current_time;
+
+    let stats = &mut ctx.accounts.stats;
+    stats.product = review.product;
+    stats.add(rating)?;
+
+    emit!(ReviewPosted {
+        review: review.key(),
+        product: review.product,
+        store: review.store,
+        reviewer: review.reviewer,
+        rating,
+        content_uri: review.content_uri.clone(),
+        content_hash,
+        review_count: stats.review_count,
+        rating_sum: stats.rating_sum,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Change the rating or text of an existing review
+pub fn update_review(
+    ctx: Context<UpdateReview>,
+    rating: u8,
+    content_uri: String,
+    content_hash: [u8; 32],
+) -> Result<()> {
+    validate_review(rating, &content_uri)?;
+
+    let review = &mut ctx.accounts.review;
+    ctx.accounts.stats.replace(review.rating, rating)?;
+    review.rating = rating;
+    review.content_uri = content_uri;
+    review.content_hash = content_hash;
+    review.updated_at = Clock::get()?.unix_timestamp;
+
+    msg!("Review {} updated", review.key());
+    Ok(())
+}
+
+fn validate_review(rating: u8, content_uri: &str) -> Result<()> {
+    require!(
+        (Review::MIN_RATING..=Review::MAX_RATING).contains(&rating),
+        CustomError::InvalidRating
+    );
+    require!(
+        content_uri.len() <= Review::MAX_URI_LEN,
+        CustomError::InvalidParameters
+    );
+    Ok(())
+}
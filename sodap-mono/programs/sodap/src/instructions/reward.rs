@@ -0,0 +1,187 @@
+use crate::error::CustomError;
+use crate::state::reward::{
+    ClaimReward, CreateReward, Reward, RewardClaimed, RewardCreated, UpdateReward,
+};
+use crate::types::{LoyaltyPointType, LoyaltyTransactionType, RewardKind};
+use anchor_lang::prelude::*;
+
+/// Add a reward to a store's loyalty catalog
+pub fn create_reward(
+    ctx: Context<CreateReward>,
+    reward_id: [u8; 16],
+    name: String,
+    kind: RewardKind,
+    point_cost: u64,
+    stock: u64,
+    per_user_limit: u16,
+) -> Result<()> {
+    require!(
+        name.len() <= Reward::MAX_NAME_LEN,
+        CustomError::InvalidParameters
+    );
+    require!(point_cost > 0, CustomError::InvalidParameters);
+
+    let reward = &mut ctx.accounts.reward;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    reward.loyalty_program = ctx.accounts.loyalty_program.key();
+    reward.store = ctx.accounts.store.key();
+    reward.reward_id = reward_id;
+    reward.name = name;
+    reward.kind = kind;
+    reward.point_cost = point_cost;
+    reward.stock = stock;
+    reward.claimed = 0;
+    reward.per_user_limit = per_user_limit;
+    reward.is_active = true;
+    reward.created_at = current_time;
+
+    emit!(RewardCreated {
+        reward: reward.key(),
+        store: reward.store,
+        kind,
+        point_cost,
+        stock,
+        created_at: current_time,
+    });
+
+    msg!("Reward {} created for store {}", reward.name, reward.store);
+    Ok(())
+}
+
+/// Update a reward's cost, stock, limits or availability
+pub fn update_reward(
+    ctx: Context<UpdateReward>,
+    point_cost: Option<u64>,
+    stock: Option<u64>,
+    per_user_limit: Option<u16>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let reward = &mut ctx.accounts.reward;
+
+    if let Some(cost) = point_cost {
+        require!(cost > 0, CustomError::InvalidParameters);
+        reward.point_cost = cost;
+    }
+    if let Some(stock) = stock {
+        reward.stock = stock;
+    }
+    if let Some(limit) = per_user_limit {
+        reward.per_user_limit = limit;
+    }
+    if let Some(active) = is_active {
+        reward.is_active = active;
+    }
+
+    msg!("Reward {} updated", reward.name);
+    Ok(())
+}
+
+/// Spend loyalty points on a catalog reward
+pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+    let reward = &mut ctx.accounts.reward;
+    let loyalty_account = &mut ctx.accounts.loyalty_account;
+    let reward_claims = &mut ctx.accounts.reward_claims;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        ctx.accounts.loyalty_program.is_active,
+        CustomError::LoyaltyProgramInactive
+    );
+    require!(reward.is_active, CustomError::RewardInactive);
+    require!(reward.stock > 0, CustomError::RewardOutOfStock);
+    require!(
+        reward.per_user_limit == 0 || reward_claims.claims < reward.per_user_limit,
+        CustomError::RewardLimitReached
+    );
+
+    // Debit points
+    loyalty_account.redeem_points(reward.point_cost)?;
+
+    // Fulfil the reward
+    let voucher_key = match reward.kind {
+        RewardKind::FreeProduct { product } => {
+            require!(ctx.accounts.voucher.is_none(), CustomError::InvalidReward);
+            let product_account = ctx
+                .accounts
+                .product
+                .as_mut()
+                .ok_or(CustomError::InvalidReward)?;
+            require!(product_account.key() == product, CustomError::InvalidReward);
+            require!(product_account.is_active, CustomError::ProductNotFound);
+            product_account.stock = product_account
+                .stock
+                .checked_sub(1)
+                .ok_or(CustomError::OutOfStock)?;
+            None
+        }
+        RewardKind::Voucher { value } | RewardKind::ShippingCredit { value } => {
+            require!(ctx.accounts.product.is_none(), CustomError::InvalidReward);
+            let voucher = ctx
+                .accounts
+                .voucher
+                .as_mut()
+                .ok_or(CustomError::InvalidReward)?;
+            voucher.store = reward.store;
+            voucher.owner = ctx.accounts.user.key();
+            voucher.reward = reward.key();
+            voucher.value = value;
+            voucher.is_redeemed = false;
+            voucher.issued_at = current_time;
+            voucher.redeemed_at = 0;
+            voucher.redeemed_receipt = None;
+            Some(voucher.key())
+        }
+    };
+
+    // Track per-user claims
+    if reward_claims.user == Pubkey::default() {
+        reward_claims.reward = reward.key();
+        reward_claims.user = ctx.accounts.user.key();
+    }
+    reward_claims.claims = reward_claims
+        .claims
+        .checked_add(1)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    reward.stock -= 1;
+    reward.claimed = reward
+        .claimed
+        .checked_add(1)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    // Create transaction record
+    let transaction_record = &mut ctx.accounts.transaction_record;
+    transaction_record.loyalty_account = loyalty_account.key();
+    transaction_record.sequence = loyalty_account.next_transaction_sequence()?;
+    transaction_record.payer = ctx.accounts.user.key();
+    transaction_record.user = ctx.accounts.user.key();
+    transaction_record.store = ctx.accounts.store.key();
+    transaction_record.transaction_type = LoyaltyTransactionType::Redeemed;
+    transaction_record.point_type = LoyaltyPointType::Purchase;
+    transaction_record.points = reward.point_cost;
+    transaction_record.purchase_amount = None;
+    transaction_record.description = format!("Claimed reward {}", reward.name);
+    transaction_record.timestamp = current_time;
+    transaction_record.related_user = None;
+    transaction_record.receipt = None;
+
+    emit!(RewardClaimed {
+        reward: reward.key(),
+        user: ctx.accounts.user.key(),
+        store: reward.store,
+        kind: reward.kind,
+        points_spent: reward.point_cost,
+        voucher: voucher_key,
+        remaining_stock: reward.stock,
+        timestamp: current_time,
+    });
+
+    msg!(
+        "User {} claimed reward {} for {} points",
+        ctx.accounts.user.key(),
+        reward.name,
+        reward.point_cost
+    );
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use crate::error::CustomError;
+use crate::instructions::treasury::collect_platform_fee;
+use crate::state::settlement::{OrderSettled, SetHoldPeriod, SettleOrder};
+use crate::types::TransactionStatus;
+use anchor_lang::prelude::*;
+
+/// Set how long unconfirmed orders wait before anyone can release them.
+/// Applies to orders placed from now on, and can't be shorter than the
+/// window buyers have to open a dispute.
+pub fn set_hold_period(ctx: Context<SetHoldPeriod>, hold_period: i64) -> Result<()> {
+    require!(hold_period > 0, CustomError::InvalidParameters);
+    require!(
+        hold_period >= ctx.accounts.dispute_pool.dispute_window,
+        CustomError::HoldPeriodTooShort
+    );
+    ctx.accounts.escrow_account.hold_period = hold_period;
+    msg!("Settlement hold period set to {} seconds", hold_period);
+    Ok(())
+}
+
+/// Buyer confirms the order arrived, releasing its payment to the store
+pub fn confirm_receipt(ctx: Context<SettleOrder>) -> Result<()> {
+    require_keys_eq!(
+        ctx.accounts.caller.key(),
+        ctx.accounts.receipt.buyer,
+        CustomError::Unauthorized
+    );
+    settle_order(ctx, true)
+}
+
+/// Release an order's payment to the store once the hold period it was
+/// bought under has passed without a dispute. Anyone can crank this.
+pub fn auto_release_order(ctx: Context<SettleOrder>) -> Result<()> {
+    require!(
+        Clock::get()?.unix_timestamp >= ctx.accounts.receipt.release_after,
+        CustomError::HoldPeriodActive
+    );
+    settle_order(ctx, false)
+}
+
+fn settle_order(ctx: Context<SettleOrder>, confirmed_by_buyer: bool) -> Result<()> {
+    let receipt = &ctx.accounts.receipt;
+    require!(!receipt.settled, CustomError::OrderAlreadySettled);
+    require!(!receipt.disputed, CustomError::OrderDisputed);
+    require!(!receipt.awaiting_delivery, CustomError::DeliveryPending);
+    require!(
+        !matches!(
+            receipt.status,
+            TransactionStatus::Pending | TransactionStatus::Failed
+        ),
+        CustomError::OrderNotSettleable
+    );
+    let (amount, platform_fee) = receipt.settlement();
+    let store_amount = amount - platform_fee;
+
+    let store_key = ctx.accounts.store.key();
+    collect_platform_fee(
+        ctx.accounts.escrow_account.to_account_info(),
+        &mut ctx.accounts.treasury,
+        ctx.accounts.system_program.to_account_info(),
+        store_key,
+        amount,
+        platform_fee,
+    )?;
+    // The escrow is a program account, so its lamports are moved directly
+    if store_amount > 0 {
+        ctx.accounts.escrow_account.sub_lamports(store_amount)?;
+        ctx.accounts.store_owner.add_lamports(store_amount)?;
+    }
+
+    let escrow = &mut ctx.accounts.escrow_account;
+    escrow.unsettled = escrow.unsettled.saturating_sub(amount);
+    escrow.balance = escrow
+        .balance
+        .checked_sub(amount)
+        .ok_or(CustomError::InsufficientFunds)?;
+
+    let store = &mut ctx.accounts.store;
+    store.revenue = store
+        .revenue
+        .checked_add(store_amount)
+        .ok_or(CustomError::ArithmeticError)?;
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.record_payout(amount, platform_fee)?;
+    store_stats.close_order()?;
+
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.settled = true;
+
+    emit!(OrderSettled {
+        receipt: receipt.key(),
+        store: store.key(),
+        buyer: receipt.buyer,
+        amount,
+        platform_fee,
+        confirmed_by_buyer,
+        settled_by: ctx.accounts.caller.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
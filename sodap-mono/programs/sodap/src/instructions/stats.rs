@@ -0,0 +1,87 @@
+use crate::error::CustomError;
+use crate::state::product::Purchase;
+use crate::state::stats::{CheckEscrowInvariant, EscrowInvariantChecked, SetTopSellerTracking};
+use anchor_lang::prelude::*;
+
+/// Check that a store's escrow matches its books. Remaining accounts must be
+/// every open (unsettled) order of the store. Fails when:
+/// - the outstanding total of those orders differs from the escrow earmarked for orders
+/// - the escrow balance is below that earmarked amount
+/// - the store stats disagree with the escrow balance
+pub fn check_escrow_invariant<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CheckEscrowInvariant<'info>>,
+) -> Result<()> {
+    let store = ctx.accounts.store.key();
+    let escrow = &ctx.accounts.escrow_account;
+    let stats = &ctx.accounts.store_stats;
+    require!(
+        ctx.remaining_accounts.len() as u64 == stats.open_orders,
+        CustomError::EscrowInvariantViolated
+    );
+
+    let mut seen = Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut open_order_total = 0u64;
+    for receipt_info in ctx.remaining_accounts {
+        require!(
+            !seen.contains(receipt_info.key),
+            CustomError::EscrowInvariantViolated
+        );
+        seen.push(*receipt_info.key);
+
+        let receipt = Account::<Purchase>::try_from(receipt_info)?;
+        require!(
+            receipt.store == store && !receipt.settled,
+            CustomError::EscrowInvariantViolated
+        );
+        open_order_total = open_order_total
+            .checked_add(receipt.total_paid.saturating_sub(receipt.refunded_amount))
+            .ok_or(CustomError::ArithmeticError)?;
+    }
+
+    let order_funds = escrow
+        .held_for_delivery
+        .checked_add(escrow.frozen)
+        .and_then(|funds| funds.checked_add(escrow.unsettled))
+        .ok_or(CustomError::ArithmeticError)?;
+    msg!(
+        "Escrow balance {}, books {}, open orders {} owed {}, earmarked {}",
+        escrow.balance,
+        stats.escrow_held,
+        stats.open_orders,
+        open_order_total,
+        order_funds
+    );
+    require!(
+        open_order_total == order_funds
+            && escrow.balance >= order_funds
+            && stats.escrow_held == escrow.balance,
+        CustomError::EscrowInvariantViolated
+    );
+
+    emit!(EscrowInvariantChecked {
+        store,
+        escrow_balance: escrow.balance,
+        escrow_held: stats.escrow_held,
+        open_orders: stats.open_orders,
+        open_order_total,
+        order_funds,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Turn per-product unit counts in the store's daily sales buckets on or off
+pub fn set_top_seller_tracking(ctx: Context<SetTopSellerTracking>, enabled: bool) -> Result<()> {
+    let store_key = ctx.accounts.store.key();
+    let store_stats = &mut ctx.accounts.store_stats;
+    store_stats.ensure_initialized(store_key, ctx.accounts.escrow_account.balance);
+    store_stats.track_top_sellers = enabled;
+
+    msg!(
+        "Top seller tracking for store {} set to {}",
+        store_key,
+        enabled
+    );
+    Ok(())
+}
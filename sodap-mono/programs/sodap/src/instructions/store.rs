@@ -100,31 +100,6 @@ pub fn remove_admin(
     Ok(())
 }
 
-pub fn release_escrow(ctx: Context<ReleaseEscrow>, amount: u64) -> Result<()> {
-    let cpi_ctx = CpiContext::new(
-        ctx.accounts.system_program.to_account_info(),
-        anchor_lang::system_program::Transfer {
-            from: ctx.accounts.escrow.to_account_info(),
-            to: ctx.accounts.owner.to_account_info(),
-        },
-    );
-    anchor_lang::system_program::transfer(cpi_ctx, amount)?;
-    ctx.accounts.store.revenue = ctx.accounts.store.revenue.checked_add(amount).unwrap();
-    Ok(())
-}
-
-// 3) refund_escrow: send from escrow PDA to buyer
-pub fn refund_escrow(ctx: Context<RefundEscrow>, amount: u64) -> Result<()> {
-    let cpi_ctx = CpiContext::new(
-        ctx.accounts.system_program.to_account_info(),
-        anchor_lang::system_program::Transfer {
-            from: ctx.accounts.escrow.to_account_info(),
-            to: ctx.accounts.buyer.to_account_info(),
-        },
-    );
-    anchor_lang::system_program::transfer(cpi_ctx, amount)?;
-    Ok(())
-}
 #[derive(Accounts)]
 #[instruction()]
 pub struct RegisterStore<'info> {
@@ -154,44 +129,6 @@ pub struct RegisterStore<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct ReleaseEscrow<'info> {
-    #[account(mut, has_one = owner)]
-    pub store: Account<'info, Store>,
-
-    #[account(
-        mut,
-        seeds = [b"escrow", store.key().as_ref()],
-        bump = store.escrow_bump,
-        seeds::program = system_program.key()
-    )]
-    /// CHECK: escrow vault PDA
-    pub escrow: AccountInfo<'info>,
-
-    #[account(mut, address = store.owner)]
-    pub owner: Signer<'info>,
-
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct RefundEscrow<'info> {
-    #[account(mut)]
-    pub store: Account<'info, Store>,
-
-    #[account(
-        mut,
-        seeds = [b"escrow", store.key().as_ref()],
-        bump = store.escrow_bump,
-    )]
-    pub escrow: AccountInfo<'info>,
-
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-
-    pub system_program: Program<'info, System>,
-}
-
 // Re-export contexts from state
 pub use crate::state::store::{AddAdmin, RemoveAdmin, UpdateStore};
 // pub use self::register_store;
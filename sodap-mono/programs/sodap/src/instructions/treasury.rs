@@ -0,0 +1,110 @@
+use crate::error::CustomError;
+use crate::state::treasury::{
+    ConfigurePlatformFee, FeeConfig, PlatformFeeCollected, PlatformFeeConfigured, Treasury,
+    TreasuryWithdrawn, WithdrawTreasury,
+};
+use crate::types::StoreFeeTier;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+/// Set the marketplace fee and the stores charged a different rate
+pub fn configure_platform_fee(
+    ctx: Context<ConfigurePlatformFee>,
+    fee_bps: u16,
+    store_tiers: Vec<StoreFeeTier>,
+) -> Result<()> {
+    require!(
+        fee_bps <= FeeConfig::MAX_FEE_BPS,
+        CustomError::InvalidParameters
+    );
+    require!(
+        store_tiers.len() <= FeeConfig::MAX_STORE_TIERS
+            && store_tiers
+                .iter()
+                .all(|tier| tier.fee_bps <= FeeConfig::MAX_FEE_BPS),
+        CustomError::InvalidParameters
+    );
+
+    let fee_config = &mut ctx.accounts.fee_config;
+    fee_config.fee_bps = fee_bps;
+    fee_config.store_tiers = store_tiers;
+
+    emit!(PlatformFeeConfigured {
+        fee_bps,
+        store_tiers: fee_config.store_tiers.clone(),
+        configured_by: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
+
+/// Move collected fees out of the treasury
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    require!(amount > 0, CustomError::InvalidParameters);
+    require!(
+        ctx.accounts.treasury.balance >= amount,
+        CustomError::InsufficientFunds
+    );
+
+    // The treasury is a program account, so its lamports are moved directly
+    ctx.accounts.treasury.sub_lamports(amount)?;
+    ctx.accounts.recipient.add_lamports(amount)?;
+
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.balance -= amount;
+    treasury.total_withdrawn = treasury
+        .total_withdrawn
+        .checked_add(amount)
+        .ok_or(CustomError::ArithmeticError)?;
+
+    emit!(TreasuryWithdrawn {
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        withdrawn_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Send the platform's cut of a payment from `from` to the treasury.
+/// Program accounts such as the escrow are debited directly, wallets pay
+/// through the system program and must sign.
+pub(crate) fn collect_platform_fee<'info>(
+    from: AccountInfo<'info>,
+    treasury: &mut Account<'info, Treasury>,
+    system_program: AccountInfo<'info>,
+    store: Pubkey,
+    amount: u64,
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+
+    if from.owner == &crate::ID {
+        from.sub_lamports(fee)?;
+        treasury.add_lamports(fee)?;
+    } else {
+        system_program::transfer(
+            CpiContext::new(
+                system_program,
+                Transfer {
+                    from,
+                    to: treasury.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
+    treasury.collect(fee)?;
+
+    emit!(PlatformFeeCollected {
+        store,
+        amount,
+        fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
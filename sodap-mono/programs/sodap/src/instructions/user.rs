@@ -1,10 +1,13 @@
 // User profile-related instructions, events, and accounts will be placed here.
 
 use crate::error::CustomError;
+use crate::instructions::product::purchase_cart;
+use crate::state::product::{BarcodeIndex, PurchaseCart};
+pub use crate::state::user::CreateOrUpdateUserProfile;
 use crate::state::user::UserProfileUpdated;
-pub use crate::state::user::{CreateOrUpdateUserProfile, ScanAndPurchase};
 use crate::types::*;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
 
 pub fn create_or_update_user_profile(
     ctx: Context<CreateOrUpdateUserProfile>,
@@ -45,22 +48,57 @@ pub fn create_or_update_user_profile(
     Ok(())
 }
 
-pub fn scan_and_purchase(
-    ctx: Context<ScanAndPurchase>,
-    product_uuids: Vec<[u8; 16]>,
+/// Check out a cart of scanned barcodes through the regular purchase flow.
+/// Remaining accounts are those of `purchase_cart`, followed by one barcode
+/// index per scanned code.
+pub fn scan_and_purchase<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PurchaseCart<'info>>,
+    order_id: u64,
+    barcodes: Vec<String>,
     quantities: Vec<u64>,
-    _store_id: Pubkey,
+    total_amount_paid: u64,
+    gas_fee: u64,
+    coupon_code: Option<String>,
 ) -> Result<()> {
     require!(
-        product_uuids.len() == quantities.len(),
+        !barcodes.is_empty() && barcodes.len() == quantities.len(),
         CustomError::InvalidCart
     );
-    let user_profile = &mut ctx.accounts.user_profile;
-    user_profile.total_purchases += 1;
-    emit!(UserProfileUpdated {
-        wallet_address: ctx.accounts.authority.key(),
-        user_id: user_profile.user_id.clone(),
-        updated_at: Clock::get()?.unix_timestamp,
-    });
-    Ok(())
+    let remaining_accounts: &'info [AccountInfo<'info>] = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() > barcodes.len(),
+        CustomError::BarcodeIndexMismatch
+    );
+
+    // Resolve each scanned code to the product it was registered for
+    let store = ctx.accounts.store.key();
+    let index_accounts = &remaining_accounts[remaining_accounts.len() - barcodes.len()..];
+    let product_uuids = barcodes
+        .iter()
+        .zip(index_accounts)
+        .map(|(barcode, index_info)| {
+            require!(!index_info.data_is_empty(), CustomError::BarcodeNotFound);
+            let index = Account::<BarcodeIndex>::try_from(index_info)?;
+            require!(
+                index.store == store
+                    && index.gtin_hash == hash(barcode.trim().as_bytes()).to_bytes(),
+                CustomError::BarcodeNotFound
+            );
+            Ok(index.product_uuid)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    purchase_cart(
+        ctx,
+        order_id,
+        CartOrder {
+            product_uuids,
+            variant_ids: Vec::new(),
+            quantities,
+            total_amount_paid,
+            gas_fee,
+            status: TransactionStatus::Completed,
+            coupon_code,
+        },
+    )
 }
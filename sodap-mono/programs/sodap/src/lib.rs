@@ -8,9 +8,9 @@ declare_id!("G2B8xnzSUP9fhJ5dWDGYWWmUbRA6WjZ3hySyp5hFbyTb");
 // Module declarations without re-exports
 mod error;
 mod instructions;
-mod state;
+pub mod state;
 pub mod types;
-mod utils;
+pub mod utils;
 
 // Re-export instruction modules
 use instructions::*;
@@ -24,13 +24,6 @@ use error::*;
 // Re-export types module
 use types::*;
 
-// Define ProductAttribute type since it's not found in types.rs
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct ProductAttribute {
-    pub name: String,
-    pub value: String,
-}
-
 // Custom error types for validation
 #[error_code]
 pub enum CustomError {
@@ -54,30 +47,6 @@ pub enum CustomError {
 
 // Use Store and Escrow structs from state module
 use state::store::Store;
-use state::Escrow;
-
-// Define Purchase struct for storing purchase records
-#[derive(Debug)]
-#[account]
-pub struct Purchase {
-    pub product_ids: Vec<Pubkey>,
-    pub quantities: Vec<u64>,
-    pub total_paid: u64,
-    pub gas_fee: u64,
-    pub store: Pubkey,
-    pub buyer: Pubkey,
-    pub timestamp: i64,
-}
-
-impl Purchase {
-    pub const MAX_PRODUCTS: usize = 10;
-
-    pub fn validate_products(&self) -> anchor_lang::Result<()> {
-        anchor_lang::require!(self.product_ids.len() <= Self::MAX_PRODUCTS, CustomError::TooManyProducts);
-        anchor_lang::require!(self.product_ids.len() == self.quantities.len(), CustomError::InvalidPurchase);
-        Ok(())
-    }
-}
 
 // Declare a struct here to avoid using one from a module
 #[derive(Accounts)]
@@ -110,27 +79,6 @@ pub struct CreateOrUpdateUserProfileAccounts<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct ScanAndPurchaseAccounts<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct RegisterProductAccounts<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct UpdateProductAccounts<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
-
 #[derive(Accounts)]
 pub struct DeactivateProductAccounts<'info> {
     #[account(mut)]
@@ -138,59 +86,6 @@ pub struct DeactivateProductAccounts<'info> {
     pub system_program: Program<'info, System>,
 }
 
-// Add a new struct for emitting purchase events
-#[event]
-pub struct PurchaseCompleted {
-    pub store: Pubkey,
-    pub buyer: Pubkey,
-    pub total_amount: u64,
-    pub timestamp: i64,
-}
-
-#[derive(Accounts)]
-pub struct PurchaseCartAccounts<'info> {
-    // Store information
-    #[account(mut)]
-    pub store: Account<'info, Store>,
-
-    // Receipt/Purchase record
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + // discriminator
-            (4 + Purchase::MAX_PRODUCTS * 32) + // product_ids Vec<Pubkey>
-            (4 + Purchase::MAX_PRODUCTS * 8) + // quantities Vec<u64>
-            8 + // total_paid
-            8 + // gas_fee
-            32 + // store
-            32 + // buyer
-            8 // timestamp
-    )]
-    pub receipt: Account<'info, Purchase>,
-
-    // The buyer who is paying for the cart
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-
-    // The store owner who will receive payment
-    #[account(mut)]
-    /// CHECK: This is not dangerous because we're only using it for payment
-    pub store_owner: AccountInfo<'info>,
-
-    // The escrow account that holds funds during the purchase
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        space = 8 + 32 + 8,
-        seeds = [b"escrow", store.key().as_ref()],
-        bump
-    )]
-    pub escrow_account: Account<'info, Escrow>,
-
-    // Required programs
-    pub system_program: Program<'info, System>,
-}
-
 #[derive(Accounts)]
 pub struct AddPlatformAdminAccounts<'info> {
     #[account(mut)]
@@ -222,48 +117,6 @@ pub struct RemoveStoreAdminAccounts<'info> {
     pub system_program: Program<'info, System>,
 }
 
-
-#[derive(Accounts)]
-pub struct ReleaseEscrowAccounts<'info> {
-    #[account(mut)]
-    pub store: Account<'info, Store>,
-    #[account(mut)]
-    /// CHECK: This is safe because we're only using it for payment
-    pub store_owner: AccountInfo<'info>,
-    #[account(mut)]
-    pub escrow_account: Account<'info, Escrow>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct RefundEscrowAccounts<'info> {
-    // Store information
-    #[account(mut)]
-    pub store: Account<'info, Store>,
-
-    // Store owner authorizing the refund
-    #[account(
-        constraint = store_owner.key() == store.owner @ CustomError::Unauthorized
-    )]
-    pub store_owner: Signer<'info>,
-
-    // The buyer who receives the refund
-    #[account(mut)]
-    /// CHECK: This is not dangerous because we're only sending funds to this account
-    pub buyer: AccountInfo<'info>,
-
-    // The escrow account that holds funds
-    #[account(
-        mut,
-        seeds = [b"escrow", store.key().as_ref()],
-        bump,
-        constraint = escrow_account.store == store.key() @ CustomError::Unauthorized
-    )]
-    pub escrow_account: Account<'info, Escrow>,
-
-    pub system_program: Program<'info, System>,
-}
-
 #[program]
 pub mod sodap {
     use super::*;
@@ -353,77 +206,32 @@ pub mod sodap {
         Ok(())
     }
 
-    pub fn scan_and_purchase(
-        ctx: Context<ScanAndPurchaseAccounts>,
-        product_ids: Vec<Pubkey>,
+    pub fn scan_and_purchase<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseCart<'info>>,
+        order_id: u64,
+        barcodes: Vec<String>,
         quantities: Vec<u64>,
-        user_id: Pubkey,
+        total_amount_paid: u64,
+        gas_fee: u64,
+        coupon_code: Option<String>,
     ) -> Result<()> {
-        // Simplified implementation
-        msg!("Scanning and purchasing products");
-        msg!("User ID: {:?}", user_id);
-        msg!("Product IDs: {:?}", product_ids);
-        msg!("Quantities: {:?}", quantities);
-        Ok(())
+        instructions::user::scan_and_purchase(ctx, order_id, barcodes, quantities, total_amount_paid, gas_fee, coupon_code)
     }
 
     // Product operations
     pub fn register_product(
-        ctx: Context<RegisterProductAccounts>,
-        product_id: Pubkey,
-        store_id: Pubkey,
-        name: String,
-        description: String,
-        image_uri: String,
-        price: u64,
-        inventory: Option<u64>,
-        attributes: Vec<ProductAttribute>,
+        ctx: Context<RegisterProduct>,
+        registration: ProductRegistration,
     ) -> Result<()> {
-        // Simplified implementation
-        msg!("Registering product: {:?}", product_id);
-        msg!("Store ID: {:?}", store_id);
-        msg!("Name: {}", name);
-        msg!("Description: {}", description);
-        msg!("Image URI: {}", image_uri);
-        msg!("Price: {}", price);
-        if let Some(inventory) = inventory {
-            msg!("Inventory: {}", inventory);
-        }
-        msg!("Attributes: {:?}", attributes);
-        Ok(())
+        instructions::product::register_product(ctx, registration)
     }
 
     pub fn update_product(
-        ctx: Context<UpdateProductAccounts>,
-        product_id: Pubkey,
-        name: Option<String>,
-        description: Option<String>,
-        image_uri: Option<String>,
-        price: Option<u64>,
-        inventory: Option<u64>,
-        attributes: Option<Vec<ProductAttribute>>,
+        ctx: Context<UpdateProduct>,
+        product_uuid: [u8; 16],
+        changes: ProductChanges,
     ) -> Result<()> {
-        // Simplified implementation
-        msg!("Updating product: {:?}", product_id);
-        if let Some(name) = name {
-            msg!("New name: {}", name);
-        }
-        if let Some(description) = description {
-            msg!("New description: {}", description);
-        }
-        if let Some(image_uri) = image_uri {
-            msg!("New image URI: {}", image_uri);
-        }
-        if let Some(price) = price {
-            msg!("New price: {}", price);
-        }
-        if let Some(inventory) = inventory {
-            msg!("New inventory: {}", inventory);
-        }
-        if let Some(attributes) = attributes {
-            msg!("New attributes: {:?}", attributes);
-        }
-        Ok(())
+        instructions::product::update_product(ctx, product_uuid, changes)
     }
 
     pub fn deactivate_product(
@@ -435,124 +243,15 @@ pub mod sodap {
         Ok(())
     }
 
-    pub fn purchase_cart(
-        ctx: Context<PurchaseCartAccounts>,
-        product_ids: Vec<Pubkey>,
-        quantities: Vec<u64>,
-        total_amount_paid: u64,
-        payment_method: PaymentMethod,
-        bnpl_term: Option<BnplTerm>,
-        loyalty_points_to_use: Option<u64>,
+    /// Full-payment checkout. The old `payment_method`, `bnpl_term` and
+    /// `loyalty_points_to_use` arguments are gone: BNPL is opened with
+    /// `create_bnpl_loan`, and points are spent on a `claim_reward` voucher.
+    pub fn purchase_cart<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseCart<'info>>,
+        order_id: u64,
+        order: CartOrder,
     ) -> Result<()> {
-        // Validate cart data
-        require!(
-            product_ids.len() == quantities.len() && !product_ids.is_empty(),
-            CustomError::InvalidCart
-        );
-
-        // Calculate total price (in real implementation, you would validate product prices)
-        // For simplicity, we're assuming total_amount_paid is correct
-        let base_total_price = total_amount_paid;
-        
-        // Handle loyalty points redemption if specified
-        let loyalty_discount = if let Some(points) = loyalty_points_to_use {
-            // Note: In a full implementation, we would validate loyalty points here
-            // For now, assume 100 points = 1 dollar (1_000_000_000 lamports)
-            (points * 1_000_000_000) / 100
-        } else {
-            0
-        };
-        
-        let total_price = base_total_price.saturating_sub(loyalty_discount);
-        let actual_payment_needed = total_price;
-
-        // Handle payment based on method
-        match payment_method {
-            PaymentMethod::FullPayment => {
-                // Traditional full payment - transfer to escrow
-                let cpi_context = CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.buyer.to_account_info(),
-                        to: ctx.accounts.escrow_account.to_account_info(),
-                    },
-                );
-                anchor_lang::system_program::transfer(cpi_context, actual_payment_needed)?;
-            },
-            PaymentMethod::BNPL => {
-                // BNPL payment - only transfer downpayment to escrow
-                // The BNPL loan will be created separately
-                require!(bnpl_term.is_some(), CustomError::InvalidCart);
-                
-                // Calculate minimum downpayment (20% of total)
-                let min_downpayment = (actual_payment_needed * 2000) / 10000; // 20%
-                require!(
-                    total_amount_paid >= min_downpayment,
-                    CustomError::InvalidCart
-                );
-                
-                // Transfer downpayment to escrow
-                let cpi_context = CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.buyer.to_account_info(),
-                        to: ctx.accounts.escrow_account.to_account_info(),
-                    },
-                );
-                anchor_lang::system_program::transfer(cpi_context, total_amount_paid)?;
-                
-                msg!("BNPL purchase initiated - Downpayment: {}, Total: {}, Term: {:?}", 
-                     total_amount_paid, total_price, bnpl_term.unwrap());
-            }
-        }
-
-        // Get current escrow balance before updating
-        let escrow_balance = ctx.accounts.escrow_account.balance;
-
-        // Update escrow account
-        ctx.accounts.escrow_account.store = ctx.accounts.store.key();
-        let actual_deposit = match payment_method {
-            PaymentMethod::FullPayment => actual_payment_needed,
-            PaymentMethod::BNPL => total_amount_paid, // Only downpayment deposited
-        };
-        ctx.accounts.escrow_account.balance = escrow_balance
-            .checked_add(actual_deposit)
-            .ok_or(CustomError::ArithmeticError)?;
-
-        // Update receipt
-        let receipt = &mut ctx.accounts.receipt;
-        receipt.product_ids = product_ids.clone();
-        receipt.quantities = quantities.clone();
-        receipt.total_paid = match payment_method {
-            PaymentMethod::FullPayment => base_total_price, // Record full original price
-            PaymentMethod::BNPL => base_total_price, // Record full price, even if only downpayment made
-        };
-        receipt.gas_fee = 0; // For simplicity
-        receipt.store = ctx.accounts.store.key();
-        receipt.buyer = ctx.accounts.buyer.key();
-        receipt.timestamp = Clock::get()?.unix_timestamp;
-
-
-        // Emit purchase event
-        emit!(PurchaseCompleted {
-            store: ctx.accounts.store.key(),
-            buyer: ctx.accounts.buyer.key(),
-            total_amount: total_price,
-            timestamp: receipt.timestamp,
-        });
-
-        // Log purchase
-        msg!("Purchase completed - Original total: {}, After loyalty discount: {}, Actual paid: {}", 
-             base_total_price, loyalty_discount, actual_payment_needed);
-        msg!(
-            "Funds held in escrow: {}",
-            ctx.accounts.escrow_account.balance
-        );
-        if let Some(points) = loyalty_points_to_use {
-            msg!("Loyalty points used: {}, Discount value: {} lamports", points, loyalty_discount);
-        }
-
-        Ok(())
+        instructions::product::purchase_cart(ctx, order_id, order)
     }
 
     // Admin operations
@@ -628,70 +327,6 @@ pub mod sodap {
     }
 
 
-    // Function to release funds from escrow to store owner
-    pub fn release_escrow(ctx: Context<ReleaseEscrowAccounts>, amount: u64) -> Result<()> {
-        // Check if escrow has enough balance
-        require!(
-            ctx.accounts.escrow_account.balance >= amount,
-            CustomError::InsufficientEscrowBalance
-        );
-
-        // Transfer from escrow to store owner
-        let cpi_accounts = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.escrow_account.to_account_info(),
-            to: ctx.accounts.store_owner.to_account_info(),
-        };
-
-        let cpi_program = ctx.accounts.system_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-
-        anchor_lang::system_program::transfer(cpi_ctx, amount)?;
-
-        // Update escrow balance
-        ctx.accounts.escrow_account.balance = ctx.accounts.escrow_account.balance.checked_sub(amount).unwrap();
-
-        // Log the release
-        msg!("Released {} lamports from escrow to store owner", amount);
-        msg!(
-            "Remaining escrow balance: {}",
-            ctx.accounts.escrow_account.balance
-        );
-
-        Ok(())
-    }
-
-    // Function to refund funds from escrow to buyer
-    pub fn refund_from_escrow(ctx: Context<RefundEscrowAccounts>, amount: u64) -> Result<()> {
-        // Check if escrow has enough balance
-        require!(
-            ctx.accounts.escrow_account.balance >= amount,
-            CustomError::InsufficientEscrowBalance
-        );
-
-        // Transfer from escrow to buyer (refund)
-        let cpi_accounts = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.escrow_account.to_account_info(),
-            to: ctx.accounts.buyer.to_account_info(),
-        };
-
-        let cpi_program = ctx.accounts.system_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-
-        anchor_lang::system_program::transfer(cpi_ctx, amount)?;
-
-        // Update escrow balance
-        ctx.accounts.escrow_account.balance = ctx.accounts.escrow_account.balance.checked_sub(amount).unwrap();
-
-        // Log the refund
-        msg!("Refunded {} lamports from escrow to buyer", amount);
-        msg!(
-            "Remaining escrow balance: {}",
-            ctx.accounts.escrow_account.balance
-        );
-
-        Ok(())
-    }
-
     // BNPL-related instructions
     pub fn initialize_credit_score(ctx: Context<InitializeCreditScore>) -> Result<()> {
         instructions::bnpl::initialize_credit_score(ctx)
@@ -740,17 +375,13 @@ pub mod sodap {
     pub fn initialize_loyalty_account(
         ctx: Context<InitializeLoyaltyAccount>,
         referral_code: Option<[u8; 8]>,
-        referred_by: Option<Pubkey>,
+        referrer_code: Option<[u8; 8]>,
     ) -> Result<()> {
-        instructions::loyalty::initialize_loyalty_account(ctx, referral_code, referred_by)
+        instructions::loyalty::initialize_loyalty_account(ctx, referral_code, referrer_code)
     }
 
-    pub fn earn_loyalty_points(
-        ctx: Context<EarnLoyaltyPoints>,
-        purchase_amount: u64,
-        point_type: LoyaltyPointType,
-    ) -> Result<()> {
-        instructions::loyalty::earn_loyalty_points(ctx, purchase_amount, point_type)
+    pub fn earn_loyalty_points(ctx: Context<EarnLoyaltyPoints>, purchase_amount: u64) -> Result<()> {
+        instructions::loyalty::earn_loyalty_points(ctx, purchase_amount)
     }
 
     pub fn redeem_loyalty_points(
@@ -763,24 +394,9 @@ pub mod sodap {
 
     pub fn update_loyalty_program(
         ctx: Context<UpdateLoyaltyProgram>,
-        points_per_dollar: Option<u64>,
-        redemption_rate: Option<u64>,
-        welcome_bonus: Option<u64>,
-        referral_bonus: Option<u64>,
-        min_redemption: Option<u64>,
-        max_redemption_percent: Option<u16>,
-        is_active: Option<bool>,
+        changes: LoyaltyProgramChanges,
     ) -> Result<()> {
-        instructions::loyalty::update_loyalty_program(
-            ctx,
-            points_per_dollar,
-            redemption_rate,
-            welcome_bonus,
-            referral_bonus,
-            min_redemption,
-            max_redemption_percent,
-            is_active,
-        )
+        instructions::loyalty::update_loyalty_program(ctx, changes)
     }
 
     pub fn gift_loyalty_points(
@@ -791,25 +407,398 @@ pub mod sodap {
         instructions::loyalty::gift_loyalty_points(ctx, points_to_gift, message)
     }
 
-    pub fn award_referral_bonus(
-        ctx: Context<EarnLoyaltyPoints>,
-        referred_user: Pubkey,
-    ) -> Result<()> {
-        instructions::loyalty::award_referral_bonus(ctx, referred_user)
+    pub fn award_referral_bonus(ctx: Context<AwardReferralBonus>) -> Result<()> {
+        instructions::loyalty::award_referral_bonus(ctx)
     }
 
     pub fn deduct_loyalty_points(
-        ctx: Context<EarnLoyaltyPoints>,
+        ctx: Context<DeductLoyaltyPoints>,
         refund_amount: u64,
-        point_type: LoyaltyPointType,
     ) -> Result<()> {
-        instructions::loyalty::deduct_loyalty_points(ctx, refund_amount, point_type)
+        instructions::loyalty::deduct_loyalty_points(ctx, refund_amount)
+    }
+
+    pub fn close_loyalty_transaction(ctx: Context<CloseLoyaltyTransaction>) -> Result<()> {
+        instructions::loyalty::close_loyalty_transaction(ctx)
+    }
+
+    // Reward catalog instructions
+    pub fn create_reward(
+        ctx: Context<CreateReward>,
+        reward_id: [u8; 16],
+        name: String,
+        kind: RewardKind,
+        point_cost: u64,
+        stock: u64,
+        per_user_limit: u16,
+    ) -> Result<()> {
+        instructions::reward::create_reward(ctx, reward_id, name, kind, point_cost, stock, per_user_limit)
+    }
+
+    pub fn update_reward(
+        ctx: Context<UpdateReward>,
+        point_cost: Option<u64>,
+        stock: Option<u64>,
+        per_user_limit: Option<u16>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::reward::update_reward(ctx, point_cost, stock, per_user_limit, is_active)
+    }
+
+    pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+        instructions::reward::claim_reward(ctx)
+    }
+
+    // Scheduled sale price instructions
+    pub fn set_price_schedule(
+        ctx: Context<UpdateProduct>,
+        product_uuid: [u8; 16],
+        schedule: Vec<ScheduledPrice>,
+    ) -> Result<()> {
+        instructions::product::set_price_schedule(ctx, product_uuid, schedule)
+    }
+
+    pub fn sync_sale_price(ctx: Context<SyncSalePrice>) -> Result<()> {
+        instructions::product::sync_sale_price(ctx)
+    }
+
+    // Coupon instructions
+    pub fn create_coupon(
+        ctx: Context<CreateCoupon>,
+        code_hash: [u8; 32],
+        terms: CouponTerms,
+    ) -> Result<()> {
+        instructions::coupon::create_coupon(ctx, code_hash, terms)
+    }
+
+    pub fn update_coupon(
+        ctx: Context<UpdateCoupon>,
+        max_uses: Option<u64>,
+        per_user_limit: Option<u16>,
+        expires_at: Option<i64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::coupon::update_coupon(ctx, max_uses, per_user_limit, expires_at, is_active)
+    }
+
+    // Purchase limit instructions
+    pub fn set_purchase_limits(
+        ctx: Context<UpdateProduct>,
+        product_uuid: [u8; 16],
+        limits: PurchaseLimits,
+    ) -> Result<()> {
+        instructions::product::set_purchase_limits(ctx, product_uuid, limits)
+    }
+
+    pub fn open_purchase_counter(
+        ctx: Context<OpenPurchaseCounter>,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::product::open_purchase_counter(ctx, proof)
+    }
+
+    // Batch catalog instructions
+    pub fn register_products<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterProducts<'info>>,
+        items: Vec<ProductRegistration>,
+    ) -> Result<Vec<BatchItemResult>> {
+        instructions::catalog::register_products(ctx, items)
+    }
+
+    pub fn update_products<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateProducts<'info>>,
+        updates: Vec<ProductUpdate>,
+    ) -> Result<Vec<BatchItemResult>> {
+        instructions::catalog::update_products(ctx, updates)
+    }
+
+    // Stock alert instructions
+    pub fn set_reorder_threshold(
+        ctx: Context<UpdateProduct>,
+        product_uuid: [u8; 16],
+        threshold: u64,
+    ) -> Result<()> {
+        instructions::product::set_reorder_threshold(ctx, product_uuid, threshold)
+    }
+
+    pub fn restock_product(
+        ctx: Context<RestockProduct>,
+        supplier: String,
+        quantity: u64,
+        cost: u64,
+    ) -> Result<()> {
+        instructions::product::restock_product(ctx, supplier, quantity, cost)
+    }
+
+    // Product review instructions
+    pub fn post_review(
+        ctx: Context<PostReview>,
+        line: u8,
+        rating: u8,
+        content_uri: String,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::review::post_review(ctx, line, rating, content_uri, content_hash)
+    }
+
+    pub fn update_review(
+        ctx: Context<UpdateReview>,
+        rating: u8,
+        content_uri: String,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::review::update_review(ctx, rating, content_uri, content_hash)
+    }
+
+    // Digital delivery instructions
+    pub fn set_digital_delivery(
+        ctx: Context<UpdateProduct>,
+        product_uuid: [u8; 16],
+        is_digital: bool,
+    ) -> Result<()> {
+        instructions::product::set_digital_delivery(ctx, product_uuid, is_digital)
+    }
+
+    pub fn record_delivery(
+        ctx: Context<RecordDelivery>,
+        kind: DeliveryKind,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        instructions::delivery::record_delivery(ctx, kind, payload)
+    }
+
+    // Product catalog structure instructions
+    pub fn set_product_attributes(
+        ctx: Context<UpdateProduct>,
+        product_uuid: [u8; 16],
+        attributes: Vec<ProductAttribute>,
+    ) -> Result<()> {
+        instructions::product::set_product_attributes(ctx, product_uuid, attributes)
+    }
+
+    pub fn create_category(
+        ctx: Context<CreateCategory>,
+        category_id: u32,
+        name: String,
+    ) -> Result<()> {
+        instructions::category::create_category(ctx, category_id, name)
+    }
+
+    pub fn set_product_category(ctx: Context<SetProductCategory>) -> Result<()> {
+        instructions::category::set_product_category(ctx)
+    }
+
+    // Return instructions
+    pub fn request_return(
+        ctx: Context<RequestReturn>,
+        line: u8,
+        quantity: u64,
+        reason: ReturnReason,
+    ) -> Result<()> {
+        instructions::returns::request_return(ctx, line, quantity, reason)
+    }
+
+    pub fn approve_return(ctx: Context<ApproveReturn>) -> Result<()> {
+        instructions::returns::approve_return(ctx)
+    }
+
+    pub fn reject_return(ctx: Context<RejectReturn>) -> Result<()> {
+        instructions::returns::reject_return(ctx)
+    }
+
+    // Dispute instructions
+    pub fn configure_dispute_pool(
+        ctx: Context<ConfigureDisputePool>,
+        dispute_window: i64,
+        arbitration_fee: u64,
+    ) -> Result<()> {
+        instructions::dispute::configure_dispute_pool(ctx, dispute_window, arbitration_fee)
+    }
+
+    pub fn fund_dispute_pool(ctx: Context<FundDisputePool>, amount: u64) -> Result<()> {
+        instructions::dispute::fund_dispute_pool(ctx, amount)
+    }
+
+    pub fn open_dispute(ctx: Context<OpenDispute>, evidence_uri: String) -> Result<()> {
+        instructions::dispute::open_dispute(ctx, evidence_uri)
+    }
+
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, buyer_amount: u64) -> Result<()> {
+        instructions::dispute::resolve_dispute(ctx, buyer_amount)
+    }
+
+    // Order settlement instructions
+    pub fn set_hold_period(ctx: Context<SetHoldPeriod>, hold_period: i64) -> Result<()> {
+        instructions::settlement::set_hold_period(ctx, hold_period)
+    }
+
+    pub fn confirm_receipt(ctx: Context<SettleOrder>) -> Result<()> {
+        instructions::settlement::confirm_receipt(ctx)
+    }
+
+    pub fn auto_release_order(ctx: Context<SettleOrder>) -> Result<()> {
+        instructions::settlement::auto_release_order(ctx)
+    }
+
+    // Platform treasury instructions
+    pub fn configure_platform_fee(
+        ctx: Context<ConfigurePlatformFee>,
+        fee_bps: u16,
+        store_tiers: Vec<StoreFeeTier>,
+    ) -> Result<()> {
+        instructions::treasury::configure_platform_fee(ctx, fee_bps, store_tiers)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        instructions::treasury::withdraw_treasury(ctx, amount)
+    }
+
+    // Store accounting instructions
+    pub fn set_top_seller_tracking(ctx: Context<SetTopSellerTracking>, enabled: bool) -> Result<()> {
+        instructions::stats::set_top_seller_tracking(ctx, enabled)
+    }
+
+    pub fn check_escrow_invariant<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CheckEscrowInvariant<'info>>,
+    ) -> Result<()> {
+        instructions::stats::check_escrow_invariant(ctx)
+    }
+
+    // Product archive instructions
+    pub fn close_product(ctx: Context<CloseProduct>, product_uuid: [u8; 16]) -> Result<()> {
+        instructions::product::close_product(ctx, product_uuid)
+    }
+
+    // Product bundle instructions
+    pub fn create_bundle(
+        ctx: Context<CreateBundle>,
+        bundle_id: [u8; 16],
+        name: String,
+        components: Vec<BundleComponent>,
+        price: u64,
+    ) -> Result<()> {
+        instructions::bundle::create_bundle(ctx, bundle_id, name, components, price)
+    }
+
+    pub fn update_bundle(
+        ctx: Context<UpdateBundle>,
+        price: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::bundle::update_bundle(ctx, price, is_active)
+    }
+
+    pub fn purchase_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseBundle<'info>>,
+        order_id: u64,
+        quantity: u64,
+        total_amount_paid: u64,
+        gas_fee: u64,
+    ) -> Result<()> {
+        instructions::bundle::purchase_bundle(ctx, order_id, quantity, total_amount_paid, gas_fee)
+    }
+
+    // Product variant instructions
+    pub fn create_product_variant(
+        ctx: Context<CreateProductVariant>,
+        variant_id: [u8; 16],
+        attributes: Vec<ProductAttribute>,
+        price_override: Option<u64>,
+        stock: u64,
+    ) -> Result<()> {
+        instructions::product::create_product_variant(ctx, variant_id, attributes, price_override, stock)
+    }
+
+    pub fn update_product_variant(
+        ctx: Context<UpdateProductVariant>,
+        new_attributes: Option<Vec<ProductAttribute>>,
+        new_price_override: Option<u64>,
+        new_stock: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::product::update_product_variant(ctx, new_attributes, new_price_override, new_stock, is_active)
+    }
+
+    // Inventory reservation instructions
+    pub fn reserve_items<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReserveItems<'info>>,
+        reservation_id: [u8; 16],
+        product_uuids: Vec<[u8; 16]>,
+        variant_ids: Vec<Option<[u8; 16]>>,
+        quantities: Vec<u64>,
+        duration: Option<i64>,
+    ) -> Result<()> {
+        instructions::reservation::reserve_items(ctx, reservation_id, product_uuids, variant_ids, quantities, duration)
+    }
+
+    pub fn release_reservation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseReservation<'info>>,
+    ) -> Result<()> {
+        instructions::reservation::release_reservation(ctx)
+    }
+
+    // Achievement badge instructions
+    pub fn create_achievement(
+        ctx: Context<CreateAchievement>,
+        achievement_id: [u8; 16],
+        name: String,
+        uri: String,
+        criterion: AchievementCriterion,
+    ) -> Result<()> {
+        instructions::achievement::create_achievement(ctx, achievement_id, name, uri, criterion)
+    }
+
+    pub fn update_achievement(
+        ctx: Context<UpdateAchievement>,
+        uri: Option<String>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::achievement::update_achievement(ctx, uri, is_active)
+    }
+
+    pub fn claim_badge(ctx: Context<ClaimBadge>) -> Result<()> {
+        instructions::achievement::claim_badge(ctx)
+    }
+
+    // Account migration instructions, for accounts created before their layouts grew
+    pub fn migrate_escrow(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migration::migrate_escrow(ctx)
+    }
+
+    pub fn migrate_product(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migration::migrate_product(ctx)
+    }
+
+    pub fn migrate_purchase(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migration::migrate_purchase(ctx)
+    }
+
+    pub fn migrate_bnpl_loan(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migration::migrate_bnpl_loan(ctx)
     }
 }
 
 
+/// Platform accounts every checkout needs. The fee starts at 0 bps until a
+/// platform admin runs `configure_platform_fee`.
 #[derive(Accounts)]
 pub struct Initialize<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = FeeConfig::LEN,
+        seeds = [b"fee_config"],
+        bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = Treasury::LEN,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
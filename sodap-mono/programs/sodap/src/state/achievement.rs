@@ -0,0 +1,158 @@
+use super::bnpl::BnplCreditScore;
+use super::loyalty::LoyaltyAccount;
+use super::store::Store;
+use crate::error::CustomError;
+use crate::types::AchievementCriterion;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+
+#[account]
+pub struct Achievement {
+    pub store: Pubkey,                   // Store that defined the achievement
+    pub achievement_id: [u8; 16],        // Unique achievement identifier
+    pub name: String,                    // Display name
+    pub uri: String,                     // Badge metadata URI
+    pub criterion: AchievementCriterion, // What the user has to reach
+    pub is_active: bool,                 // Whether badges can be claimed
+    pub badges_issued: u64,              // Badges minted so far
+    pub created_at: i64,                 // Creation timestamp
+    pub bump: u8,                        // PDA bump, signs as badge mint authority
+}
+
+impl Achievement {
+    pub const MAX_NAME_LEN: usize = 50;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const LEN: usize =
+        8 + 32 + 16 + (4 + Self::MAX_NAME_LEN) + (4 + Self::MAX_URI_LEN) + (1 + 8) + 1 + 8 + 8 + 1;
+}
+
+/// Marks a claimed badge so each user gets at most one per achievement
+#[account]
+pub struct Badge {
+    pub achievement: Pubkey, // Achievement the badge proves
+    pub user: Pubkey,        // Badge holder
+    pub mint: Pubkey,        // Non-transferable badge mint
+    pub claimed_at: i64,     // Claim timestamp
+}
+
+impl Badge {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8;
+}
+
+#[derive(Accounts)]
+#[instruction(achievement_id: [u8; 16])]
+pub struct CreateAchievement<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Achievement::LEN,
+        seeds = [b"achievement", store.key().as_ref(), achievement_id.as_ref()],
+        bump
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAchievement<'info> {
+    #[account(
+        mut,
+        seeds = [b"achievement", store.key().as_ref(), achievement.achievement_id.as_ref()],
+        bump = achievement.bump,
+        has_one = store
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBadge<'info> {
+    #[account(
+        mut,
+        seeds = [b"achievement", store.key().as_ref(), achievement.achievement_id.as_ref()],
+        bump = achievement.bump,
+        has_one = store
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        init,
+        payer = user,
+        space = Badge::LEN,
+        seeds = [b"badge", achievement.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub badge: Account<'info, Badge>,
+
+    /// CHECK: Created in the handler as a non-transferable Token-2022 mint with metadata
+    #[account(
+        mut,
+        seeds = [b"badge_mint", achievement.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+
+    /// CHECK: User's associated token account for the badge mint, created by the associated token program
+    #[account(mut)]
+    pub badge_token_account: UncheckedAccount<'info>,
+
+    /// Proves purchase count, loyalty point, tier and referral achievements
+    /// at this store
+    #[account(
+        seeds = [b"loyalty_account", store.key().as_ref(), user.key().as_ref()],
+        bump,
+        has_one = user,
+        has_one = store
+    )]
+    pub loyalty_account: Option<Account<'info, LoyaltyAccount>>,
+
+    /// Proves BNPL repayment achievements
+    #[account(
+        seeds = [b"credit_score", user.key().as_ref()],
+        bump,
+        constraint = credit_score.user == user.key() @ CustomError::Unauthorized
+    )]
+    pub credit_score: Option<Account<'info, BnplCreditScore>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct AchievementCreated {
+    pub achievement: Pubkey,
+    pub store: Pubkey,
+    pub criterion: AchievementCriterion,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct BadgeClaimed {
+    pub achievement: Pubkey,
+    pub store: Pubkey,
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub claimed_at: i64,
+}
@@ -1,4 +1,6 @@
+use super::stats::StoreStats;
 use super::store::Store;
+use super::treasury::{FeeConfig, Treasury};
 use crate::error::CustomError;
 use crate::types::{BnplLoanStatus, BnplTerm};
 use anchor_lang::prelude::*;
@@ -22,11 +24,12 @@ pub struct BnplLoan {
     pub late_fee: u64,            // Late fee amount
     pub grace_period_days: u8,    // Grace period in days
     pub purchase_receipt: Pubkey, // Reference to purchase receipt
+    pub late_payments: u8,        // Installments paid after their due date
 }
 
 impl BnplLoan {
     pub const LEN: usize =
-        8 + 16 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 1 + 1 + 8 + 8 + 1 + 1 + 8 + 1 + 32;
+        8 + 16 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 1 + 1 + 8 + 8 + 1 + 1 + 8 + 1 + 32 + 1;
 
     /// Calculate monthly installment amount
     pub fn calculate_installment(&self) -> Result<u64> {
@@ -82,10 +85,11 @@ pub struct BnplCreditScore {
     pub late_payments: u16,       // Number of late payments
     pub defaults: u16,            // Number of defaults
     pub last_updated: i64,        // Last update timestamp
+    pub on_time_loans: u16,       // Loans repaid without a late installment
 }
 
 impl BnplCreditScore {
-    pub const LEN: usize = 8 + 32 + 2 + 2 + 2 + 2 + 2 + 8;
+    pub const LEN: usize = 8 + 32 + 2 + 2 + 2 + 2 + 2 + 8 + 2;
 
     /// Calculate credit score based on payment history
     pub fn calculate_score(&mut self) {
@@ -128,6 +132,8 @@ pub struct CreateBnplLoan<'info> {
 
     #[account(mut)]
     pub store: Account<'info, Store>,
+    #[account(mut, seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
 
     #[account(mut)]
     pub borrower: Signer<'info>,
@@ -164,6 +170,8 @@ pub struct MakeBnplPayment<'info> {
 
     #[account(mut)]
     pub store: Account<'info, Store>,
+    #[account(mut, seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
 
     #[account(mut)]
     pub borrower: Signer<'info>,
@@ -182,6 +190,12 @@ pub struct MakeBnplPayment<'info> {
     )]
     pub store_owner: AccountInfo<'info>,
 
+    #[account(seeds = [b"fee_config"], bump)]
+    pub fee_config: Account<'info, FeeConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -214,6 +228,8 @@ pub struct LiquidateBnplLoan<'info> {
 
     #[account(mut)]
     pub store: Account<'info, Store>,
+    #[account(mut, seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
 
     /// CHECK: Liquidator can be anyone who wants to pay off the debt
     #[account(mut)]
@@ -251,6 +267,7 @@ pub struct BnplPaymentMade {
     pub remaining_balance: u64,
     pub payment_date: i64,
     pub was_late: bool,
+    pub platform_fee: u64, // Part of amount_paid sent to the treasury
 }
 
 /// Event emitted when a BNPL loan is completed
@@ -0,0 +1,141 @@
+use super::daily::DailySales;
+use super::loyalty::LoyaltyAccount;
+use super::product::Purchase;
+use super::stats::StoreStats;
+use super::store::Store;
+use super::treasury::FeeConfig;
+use crate::error::CustomError;
+use crate::state::Escrow;
+use crate::types::BundleComponent;
+use anchor_lang::prelude::*;
+
+/// Several products sold together at one price
+#[account]
+pub struct Bundle {
+    pub store: Pubkey,                    // Store selling the bundle
+    pub bundle_id: [u8; 16],              // Unique bundle identifier
+    pub name: String,                     // Display name, e.g. "Breakfast basket"
+    pub components: Vec<BundleComponent>, // Products and quantities in one bundle
+    pub price: u64,                       // Bundle price in lamports
+    pub is_active: bool,                  // Whether the bundle can be bought
+    pub created_at: i64,                  // Creation timestamp
+}
+
+impl Bundle {
+    pub const MAX_NAME_LEN: usize = 50;
+    pub const MAX_COMPONENTS: usize = 8;
+    pub const LEN: usize =
+        8 + 32 + 16 + (4 + Self::MAX_NAME_LEN) + (4 + Self::MAX_COMPONENTS * (16 + 8)) + 8 + 1 + 8;
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: [u8; 16])]
+pub struct CreateBundle<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Bundle::LEN,
+        seeds = [b"bundle", store.key().as_ref(), bundle_id.as_ref()],
+        bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBundle<'info> {
+    #[account(
+        mut,
+        seeds = [b"bundle", store.key().as_ref(), bundle.bundle_id.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PurchaseBundle<'info> {
+    #[account(mut)]
+    pub store: Account<'info, Store>,
+    #[account(
+        seeds = [b"bundle", store.key().as_ref(), bundle.bundle_id.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        init,
+        payer = buyer,
+        space = Purchase::LEN,
+        seeds = [
+            b"purchase",
+            store.key().as_ref(),
+            buyer.key().as_ref(),
+            &order_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub receipt: Account<'info, Purchase>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    /// Store sales totals
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = StoreStats::LEN,
+        seeds = [b"store_stats", store.key().as_ref()],
+        bump
+    )]
+    pub store_stats: Account<'info, StoreStats>,
+    /// Today's sales bucket
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = DailySales::LEN,
+        seeds = [b"daily", store.key().as_ref(), &DailySales::current_day()?.to_le_bytes()],
+        bump
+    )]
+    pub daily_sales: Account<'info, DailySales>,
+    /// Platform fee settings
+    #[account(seeds = [b"fee_config"], bump)]
+    pub fee_config: Account<'info, FeeConfig>,
+    /// Buyer's loyalty account at the store, whose purchase count is bumped on checkout
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub loyalty_account: Option<Account<'info, LoyaltyAccount>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct BundleCreated {
+    pub bundle: Pubkey,
+    pub store: Pubkey,
+    pub components: Vec<BundleComponent>,
+    pub price: u64,
+}
@@ -0,0 +1,34 @@
+use super::store::Store;
+use crate::error::CustomError;
+use crate::types::BatchItemResult;
+use anchor_lang::prelude::*;
+
+/// Most products a single batch instruction handles
+pub const MAX_BATCH_ITEMS: usize = 20;
+
+#[derive(Accounts)]
+pub struct RegisterProducts<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProducts<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct ProductBatchProcessed {
+    pub store: Pubkey,
+    pub results: Vec<BatchItemResult>,
+    pub timestamp: i64,
+}
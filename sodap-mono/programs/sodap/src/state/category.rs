@@ -0,0 +1,116 @@
+use super::admin::PlatformAdmins;
+use super::product::Product;
+use super::store::Store;
+use anchor_lang::prelude::*;
+
+/// A node in a store's or the platform's category tree
+#[account]
+pub struct Category {
+    pub owner: Pubkey,    // Store owning the tree, default key for the platform tree
+    pub category_id: u32, // Identifier within the tree
+    pub parent: Option<Pubkey>, // Parent category, none for a root
+    pub depth: u8,        // 0 for roots
+    pub name: String,     // Display name, e.g. "Dairy"
+    pub created_at: i64,  // Creation timestamp
+}
+
+impl Category {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_DEPTH: u8 = 4;
+    pub const LEN: usize = 8 + 32 + 4 + (1 + 32) + 1 + (4 + Self::MAX_NAME_LEN) + 8;
+}
+
+/// The products a store lists in one category
+#[account]
+pub struct CategoryIndex {
+    pub store: Pubkey,                // Store listing the products
+    pub category: Pubkey,             // Category account
+    pub product_uuids: Vec<[u8; 16]>, // Products in the category
+}
+
+impl CategoryIndex {
+    pub const MAX_PRODUCTS: usize = 100;
+    pub const LEN: usize = 8 + 32 + 32 + (4 + Self::MAX_PRODUCTS * 16);
+}
+
+#[derive(Accounts)]
+#[instruction(category_id: u32)]
+pub struct CreateCategory<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Category::LEN,
+        seeds = [
+            b"category",
+            store.as_ref().map(|store| store.key()).unwrap_or_default().as_ref(),
+            category_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub category: Account<'info, Category>,
+    /// Store owning the category; platform categories have none
+    pub store: Option<Account<'info, Store>>,
+    /// Required for platform categories
+    #[account(seeds = [b"platform_admins"], bump)]
+    pub platform_admins: Option<Account<'info, PlatformAdmins>>,
+    /// Parent category in the same tree
+    pub parent: Option<Account<'info, Category>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetProductCategory<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        seeds = [b"product", store.key().as_ref(), product.uuid.as_ref()],
+        bump,
+        has_one = store,
+        has_one = authority
+    )]
+    pub product: Account<'info, Product>,
+    /// New category, or none to take the product out of its category
+    pub category: Option<Account<'info, Category>>,
+    /// Index of the new category
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = CategoryIndex::LEN,
+        seeds = [
+            b"category_index",
+            store.key().as_ref(),
+            category.as_ref().map(|category| category.key()).unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub category_index: Option<Account<'info, CategoryIndex>>,
+    /// Index of the product's current category
+    #[account(
+        mut,
+        seeds = [b"category_index", store.key().as_ref(), old_category_index.category.as_ref()],
+        bump
+    )]
+    pub old_category_index: Option<Account<'info, CategoryIndex>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct CategoryCreated {
+    pub category: Pubkey,
+    pub owner: Pubkey,
+    pub category_id: u32,
+    pub parent: Option<Pubkey>,
+    pub name: String,
+}
+
+#[event]
+pub struct ProductCategorized {
+    pub product: Pubkey,
+    pub store: Pubkey,
+    pub old_category: Option<Pubkey>,
+    pub new_category: Option<Pubkey>,
+}
@@ -0,0 +1,251 @@
+use super::store::Store;
+use crate::error::CustomError;
+use crate::types::CouponDiscount;
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct Coupon {
+    pub store: Pubkey,                    // Store that issued the coupon
+    pub code_hash: [u8; 32],              // sha256 of the promo code
+    pub discount: CouponDiscount,         // Percent or fixed discount
+    pub min_cart: u64,                    // Minimum cart total in lamports (0 = none)
+    pub eligible_products: Vec<[u8; 16]>, // Discounted products (empty = whole cart)
+    pub max_uses: u64,                    // Global usage cap (0 = unlimited)
+    pub uses: u64,                        // Times redeemed so far
+    pub per_user_limit: u16,              // Max uses per buyer (0 = unlimited)
+    pub expires_at: i64,                  // Expiry timestamp (0 = never)
+    pub is_active: bool,                  // Whether the coupon can be used
+    pub created_at: i64,                  // Creation timestamp
+}
+
+impl Coupon {
+    pub const MAX_ELIGIBLE_PRODUCTS: usize = 10;
+    pub const LEN: usize =
+        8 + 32 + 32 + (1 + 8) + 8 + (4 + Self::MAX_ELIGIBLE_PRODUCTS * 16) + 8 + 8 + 2 + 8 + 1 + 8;
+
+    pub fn is_eligible(&self, product_uuid: &[u8; 16]) -> bool {
+        self.eligible_products.is_empty() || self.eligible_products.contains(product_uuid)
+    }
+
+    /// Discount on the eligible part of a cart
+    pub fn discount_for(&self, eligible_subtotal: u64) -> u64 {
+        match self.discount {
+            CouponDiscount::Percent { bps } => {
+                (eligible_subtotal as u128 * bps as u128 / 10_000) as u64
+            }
+            CouponDiscount::Fixed { amount } => amount.min(eligible_subtotal),
+        }
+    }
+}
+
+/// What a cart costs after its discounts
+#[derive(Debug, PartialEq)]
+pub struct CartDiscounts {
+    pub coupon_discount: u64,
+    pub voucher_discount: u64,
+    pub amount_due: u64,
+}
+
+impl CartDiscounts {
+    /// Discount a cart priced at its current sale prices. The coupon applies to
+    /// the eligible lines first, then the voucher to what is left.
+    pub fn apply(
+        cart_total: u64,
+        eligible_subtotal: u64,
+        coupon: Option<&Coupon>,
+        voucher_value: u64,
+    ) -> Result<Self> {
+        let coupon_discount = match coupon {
+            Some(coupon) => {
+                require!(
+                    cart_total >= coupon.min_cart,
+                    CustomError::CouponMinCartNotMet
+                );
+                require!(eligible_subtotal > 0, CustomError::InvalidCoupon);
+                coupon.discount_for(eligible_subtotal)
+            }
+            None => 0,
+        };
+        let after_coupon = cart_total - coupon_discount;
+        let voucher_discount = voucher_value.min(after_coupon);
+        Ok(Self {
+            coupon_discount,
+            voucher_discount,
+            amount_due: after_coupon - voucher_discount,
+        })
+    }
+}
+
+/// Tracks how often a buyer has used a coupon
+#[account]
+pub struct CouponUsage {
+    pub coupon: Pubkey, // Coupon being used
+    pub user: Pubkey,   // Buyer
+    pub uses: u16,      // Times this buyer used the coupon
+}
+
+impl CouponUsage {
+    pub const LEN: usize = 8 + 32 + 32 + 2;
+}
+
+#[derive(Accounts)]
+#[instruction(code_hash: [u8; 32])]
+pub struct CreateCoupon<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Coupon::LEN,
+        seeds = [b"coupon", store.key().as_ref(), code_hash.as_ref()],
+        bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCoupon<'info> {
+    #[account(
+        mut,
+        seeds = [b"coupon", store.key().as_ref(), coupon.code_hash.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub coupon: Account<'info, Coupon>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct CouponCreated {
+    pub coupon: Pubkey,
+    pub store: Pubkey,
+    pub discount: CouponDiscount,
+    pub max_uses: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct CouponRedeemed {
+    pub coupon: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub receipt: Pubkey,
+    pub discount: u64,
+    pub uses: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::product::Product;
+    use crate::types::{PurchaseLimits, ScheduledPrice, StablePrice, TokenizedType};
+
+    fn product_on_sale(price: u64, sale_price: u64) -> Product {
+        Product {
+            uuid: [1; 16],
+            price,
+            stable_pricing: StablePrice {
+                usdc_price: 0,
+                sol_price: price,
+                last_updated: 0,
+                is_fixed: true,
+            },
+            stock: 10,
+            tokenized_type: TokenizedType::None,
+            is_active: true,
+            metadata_uri: String::new(),
+            store: Pubkey::default(),
+            authority: Pubkey::default(),
+            price_schedule: vec![ScheduledPrice {
+                start_time: 100,
+                end_time: 200,
+                usdc_price: 0,
+                sol_price: sale_price,
+            }],
+            active_sale_start: None,
+            purchase_limits: PurchaseLimits::default(),
+            gtin_hash: None,
+            open_reservations: 0,
+            reorder_threshold: 0,
+            is_digital: false,
+            attributes: Vec::new(),
+            category: None,
+        }
+    }
+
+    fn coupon(discount: CouponDiscount, min_cart: u64) -> Coupon {
+        Coupon {
+            store: Pubkey::default(),
+            code_hash: [0; 32],
+            discount,
+            min_cart,
+            eligible_products: Vec::new(),
+            max_uses: 0,
+            uses: 0,
+            per_user_limit: 0,
+            expires_at: 0,
+            is_active: true,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_coupon_applies_to_the_sale_price() {
+        let product = product_on_sale(1_000, 800);
+        let ten_percent = coupon(CouponDiscount::Percent { bps: 1_000 }, 0);
+
+        let during_sale = product.unit_price(150) * 2;
+        let discounts = CartDiscounts::apply(during_sale, during_sale, Some(&ten_percent), 0);
+        assert_eq!(
+            discounts.unwrap(),
+            CartDiscounts {
+                coupon_discount: 160,
+                voucher_discount: 0,
+                amount_due: 1_440,
+            }
+        );
+
+        let after_sale = product.unit_price(200) * 2;
+        let discounts = CartDiscounts::apply(after_sale, after_sale, Some(&ten_percent), 0);
+        assert_eq!(discounts.unwrap().amount_due, 1_800);
+    }
+
+    #[test]
+    fn test_voucher_applies_after_the_coupon() {
+        let fixed = coupon(CouponDiscount::Fixed { amount: 300 }, 0);
+
+        let discounts = CartDiscounts::apply(1_000, 400, Some(&fixed), 500).unwrap();
+        assert_eq!(discounts.coupon_discount, 300);
+        assert_eq!(discounts.voucher_discount, 500);
+        assert_eq!(discounts.amount_due, 200);
+
+        let discounts = CartDiscounts::apply(1_000, 400, Some(&fixed), 5_000).unwrap();
+        assert_eq!(discounts.voucher_discount, 700);
+        assert_eq!(discounts.amount_due, 0);
+    }
+
+    #[test]
+    fn test_minimum_cart_uses_the_sale_price() {
+        let product = product_on_sale(1_000, 800);
+        let needs_900 = coupon(CouponDiscount::Fixed { amount: 100 }, 900);
+        let during_sale = product.unit_price(150);
+
+        assert!(CartDiscounts::apply(during_sale, during_sale, Some(&needs_900), 0).is_err());
+        assert!(CartDiscounts::apply(during_sale * 2, during_sale, Some(&needs_900), 0).is_ok());
+    }
+}
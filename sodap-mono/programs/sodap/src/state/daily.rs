@@ -0,0 +1,176 @@
+use crate::error::CustomError;
+use crate::types::{Currency, CurrencyAmounts, ProductUnits};
+use anchor_lang::prelude::*;
+
+/// One store's sales for one UTC day, so dashboards can chart them without
+/// an indexer
+#[account]
+pub struct DailySales {
+    pub store: Pubkey,                   // Store the bucket belongs to
+    pub day_index: u64,                  // Days since the Unix epoch
+    pub order_count: u64,                // Orders placed
+    pub revenue: CurrencyAmounts,        // Paid at checkout, by currency
+    pub units_sold: u64,                 // Units across all order lines
+    pub refund_count: u64,               // Refunds paid out
+    pub refunds: CurrencyAmounts,        // Refunded to buyers, by currency
+    pub units_returned: u64,             // Units taken back through returns
+    pub new_loyalty_members: u64,        // Loyalty accounts opened
+    pub top_products: Vec<ProductUnits>, // Best sellers, kept when the store opts in
+    pub last_updated: i64,               // Last change timestamp
+}
+
+impl DailySales {
+    pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+    pub const MAX_TOP_PRODUCTS: usize = 10;
+    pub const LEN: usize = 8
+        + 32
+        + 8
+        + 8
+        + CurrencyAmounts::LEN
+        + 8
+        + 8
+        + CurrencyAmounts::LEN
+        + 8
+        + 8
+        + (4 + Self::MAX_TOP_PRODUCTS * ProductUnits::LEN)
+        + 8;
+
+    pub fn day_index(timestamp: i64) -> u64 {
+        timestamp.div_euclid(Self::SECONDS_PER_DAY) as u64
+    }
+
+    /// Day index of the current block time, used in the bucket's seeds
+    pub fn current_day() -> Result<u64> {
+        Ok(Self::day_index(Clock::get()?.unix_timestamp))
+    }
+
+    pub fn ensure_initialized(&mut self, store: Pubkey, timestamp: i64) {
+        if self.store == Pubkey::default() {
+            self.store = store;
+            self.day_index = Self::day_index(timestamp);
+        }
+    }
+
+    /// An order was paid. Lines with no uuid only count towards the units.
+    pub fn record_order(
+        &mut self,
+        currency: Currency,
+        amount: u64,
+        product_uuids: &[[u8; 16]],
+        quantities: &[u64],
+        track_products: bool,
+    ) -> Result<()> {
+        self.order_count = add(self.order_count, 1)?;
+        self.revenue.add(currency, amount)?;
+        for quantity in quantities {
+            self.units_sold = add(self.units_sold, *quantity)?;
+        }
+        if track_products {
+            for (product_uuid, quantity) in product_uuids.iter().zip(quantities) {
+                self.count_product(*product_uuid, *quantity);
+            }
+        }
+        self.touch()
+    }
+
+    pub fn record_refund(&mut self, currency: Currency, amount: u64, units: u64) -> Result<()> {
+        self.refund_count = add(self.refund_count, 1)?;
+        self.refunds.add(currency, amount)?;
+        self.units_returned = add(self.units_returned, units)?;
+        self.touch()
+    }
+
+    pub fn record_loyalty_member(&mut self) -> Result<()> {
+        self.new_loyalty_members = add(self.new_loyalty_members, 1)?;
+        self.touch()
+    }
+
+    /// Add units to a product's count. Once the list is full a new product
+    /// only takes the slowest seller's place if it sold more in this order,
+    /// so counts of products that drop out and return are approximate.
+    fn count_product(&mut self, product_uuid: [u8; 16], units: u64) {
+        if let Some(entry) = self
+            .top_products
+            .iter_mut()
+            .find(|entry| entry.product_uuid == product_uuid)
+        {
+            entry.units = entry.units.saturating_add(units);
+            return;
+        }
+
+        let entry = ProductUnits {
+            product_uuid,
+            units,
+        };
+        if self.top_products.len() < Self::MAX_TOP_PRODUCTS {
+            self.top_products.push(entry);
+        } else if let Some(slowest) = self.top_products.iter_mut().min_by_key(|entry| entry.units) {
+            if units > slowest.units {
+                *slowest = entry;
+            }
+        }
+    }
+
+    fn touch(&mut self) -> Result<()> {
+        self.last_updated = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+}
+
+fn add(total: u64, amount: u64) -> Result<u64> {
+    total
+        .checked_add(amount)
+        .ok_or_else(|| CustomError::ArithmeticError.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket() -> DailySales {
+        DailySales {
+            store: Pubkey::default(),
+            day_index: 0,
+            order_count: 0,
+            revenue: CurrencyAmounts::default(),
+            units_sold: 0,
+            refund_count: 0,
+            refunds: CurrencyAmounts::default(),
+            units_returned: 0,
+            new_loyalty_members: 0,
+            top_products: Vec::new(),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_day_index() {
+        assert_eq!(DailySales::day_index(0), 0);
+        assert_eq!(DailySales::day_index(86_399), 0);
+        assert_eq!(DailySales::day_index(86_400), 1);
+    }
+
+    #[test]
+    fn test_top_products_keep_best_sellers() {
+        let mut daily = bucket();
+        for i in 0..DailySales::MAX_TOP_PRODUCTS as u8 {
+            daily.count_product([i; 16], 5);
+        }
+        daily.count_product([0; 16], 2);
+        assert_eq!(daily.top_products[0].units, 7);
+
+        // A small sale doesn't push out a listed product, a big one does
+        daily.count_product([100; 16], 3);
+        assert!(daily
+            .top_products
+            .iter()
+            .all(|entry| entry.product_uuid != [100; 16]));
+        daily.count_product([101; 16], 9);
+        assert_eq!(daily.top_products.len(), DailySales::MAX_TOP_PRODUCTS);
+        assert!(daily
+            .top_products
+            .iter()
+            .any(|entry| entry.product_uuid == [101; 16]));
+        assert_eq!(daily.top_products[0].units, 7);
+    }
+}
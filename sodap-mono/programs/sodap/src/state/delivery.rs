@@ -0,0 +1,67 @@
+use super::product::Purchase;
+use super::store::Store;
+use crate::error::CustomError;
+use crate::state::Escrow;
+use crate::types::DeliveryKind;
+use anchor_lang::prelude::*;
+
+/// Proof that the digital goods of a receipt were handed to the buyer.
+/// The payload is public, so it must only be readable by the buyer.
+#[account]
+pub struct Delivery {
+    pub receipt: Pubkey,      // Receipt being fulfilled
+    pub store: Pubkey,        // Store that sold the goods
+    pub buyer: Pubkey,        // Buyer the payload is meant for
+    pub kind: DeliveryKind,   // Encrypted content key or signed download token
+    pub payload: Vec<u8>,     // Key encrypted to the buyer, or the token itself
+    pub fulfilled_by: Pubkey, // Fulfillment signer who recorded the delivery
+    pub delivered_at: i64,    // Delivery timestamp
+}
+
+impl Delivery {
+    pub const MAX_PAYLOAD_LEN: usize = 512;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 1 + (4 + Self::MAX_PAYLOAD_LEN) + 32 + 8;
+}
+
+#[derive(Accounts)]
+pub struct RecordDelivery<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        has_one = store,
+        constraint = receipt.awaiting_delivery @ CustomError::DeliveryNotRequired,
+        constraint = !receipt.disputed @ CustomError::OrderDisputed
+    )]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        init,
+        payer = fulfiller,
+        space = Delivery::LEN,
+        seeds = [b"delivery", receipt.key().as_ref()],
+        bump
+    )]
+    pub delivery: Account<'info, Delivery>,
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(
+        mut,
+        constraint = store.can_fulfill(&fulfiller.key()) @ CustomError::Unauthorized
+    )]
+    pub fulfiller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct DigitalGoodsDelivered {
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub kind: DeliveryKind,
+    pub fulfilled_by: Pubkey,
+    pub released: u64, // Escrow no longer held for this receipt
+    pub timestamp: i64,
+}
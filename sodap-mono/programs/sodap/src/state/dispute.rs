@@ -0,0 +1,224 @@
+use super::admin::PlatformAdmins;
+use super::daily::DailySales;
+use super::product::Purchase;
+use super::stats::StoreStats;
+use super::store::Store;
+use crate::error::CustomError;
+use crate::state::Escrow;
+use crate::types::DisputeStatus;
+use anchor_lang::prelude::*;
+
+/// Platform-wide dispute settings and the pool arbitration fees are paid from
+#[account]
+pub struct DisputePool {
+    pub dispute_window: i64,  // Seconds after purchase a buyer can open a dispute
+    pub arbitration_fee: u64, // Lamports paid to the arbitrating admin per dispute
+    pub balance: u64,         // Lamports available for fees
+}
+
+impl DisputePool {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+}
+
+/// A buyer's dispute over an order, freezing its escrow until arbitrated
+#[account]
+pub struct Dispute {
+    pub receipt: Pubkey,             // Disputed order
+    pub store: Pubkey,               // Store that sold it
+    pub buyer: Pubkey,               // Buyer who opened the dispute
+    pub evidence_uri: String,        // Off-chain evidence from the buyer
+    pub amount: u64,                 // Escrow frozen for the dispute
+    pub status: DisputeStatus,       // Open until an admin resolves it
+    pub opened_at: i64,              // Opening timestamp
+    pub buyer_amount: u64,           // Awarded to the buyer
+    pub store_amount: u64,           // Left to the store
+    pub resolved_by: Option<Pubkey>, // Arbitrating platform admin
+    pub resolved_at: i64,            // Resolution timestamp
+}
+
+impl Dispute {
+    pub const MAX_EVIDENCE_URI_LEN: usize = 200;
+    pub const LEN: usize =
+        8 + 32 + 32 + 32 + (4 + Self::MAX_EVIDENCE_URI_LEN) + 8 + 1 + 8 + 8 + 8 + (1 + 32) + 8;
+}
+
+#[derive(Accounts)]
+pub struct ConfigureDisputePool<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = DisputePool::LEN,
+        seeds = [b"dispute_pool"],
+        bump
+    )]
+    pub dispute_pool: Account<'info, DisputePool>,
+    #[account(seeds = [b"platform_admins"], bump)]
+    pub platform_admins: Account<'info, PlatformAdmins>,
+    #[account(
+        mut,
+        constraint = platform_admins.admins.contains(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundDisputePool<'info> {
+    #[account(mut, seeds = [b"dispute_pool"], bump)]
+    pub dispute_pool: Account<'info, DisputePool>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        has_one = store,
+        constraint = receipt.buyer == buyer.key() @ CustomError::Unauthorized,
+        constraint = !receipt.disputed @ CustomError::OrderDisputed,
+        constraint = !receipt.settled @ CustomError::OrderAlreadySettled
+    )]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        init,
+        payer = buyer,
+        space = Dispute::LEN,
+        seeds = [b"dispute", receipt.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(seeds = [b"dispute_pool"], bump)]
+    pub dispute_pool: Account<'info, DisputePool>,
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    pub store: Account<'info, Store>,
+    #[account(mut, has_one = store)]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        mut,
+        seeds = [b"dispute", receipt.key().as_ref()],
+        bump,
+        has_one = receipt,
+        has_one = buyer
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut, seeds = [b"dispute_pool"], bump)]
+    pub dispute_pool: Account<'info, DisputePool>,
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(mut, seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
+    /// Today's sales bucket
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = DailySales::LEN,
+        seeds = [b"daily", store.key().as_ref(), &DailySales::current_day()?.to_le_bytes()],
+        bump
+    )]
+    pub daily_sales: Account<'info, DailySales>,
+    /// CHECK: receives the buyer's share; must be the disputing buyer
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(seeds = [b"platform_admins"], bump)]
+    pub platform_admins: Account<'info, PlatformAdmins>,
+    /// Platform admin arbitrating the dispute, paid the arbitration fee
+    #[account(
+        mut,
+        constraint = platform_admins.admins.contains(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub dispute: Pubkey,
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub evidence_uri: String,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub dispute: Pubkey,
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub buyer_amount: u64,
+    pub store_amount: u64,
+    pub arbitration_fee: u64,
+    pub resolved_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escrow(held_for_delivery: u64, unsettled: u64) -> Escrow {
+        Escrow {
+            store: Pubkey::default(),
+            balance: held_for_delivery + unsettled,
+            held_for_delivery,
+            frozen: 0,
+            unsettled,
+            hold_period: 0,
+        }
+    }
+
+    #[test]
+    fn test_freeze_takes_from_the_orders_own_hold() {
+        let mut digital = escrow(300, 700);
+        assert_eq!(digital.freeze_order(300, true).unwrap(), 300);
+        assert_eq!(
+            (digital.held_for_delivery, digital.unsettled, digital.frozen),
+            (0, 700, 300)
+        );
+
+        let mut physical = escrow(300, 700);
+        assert_eq!(physical.freeze_order(400, false).unwrap(), 400);
+        assert_eq!(
+            (
+                physical.held_for_delivery,
+                physical.unsettled,
+                physical.frozen
+            ),
+            (300, 300, 400)
+        );
+        assert_eq!(physical.releasable(), 0);
+    }
+
+    #[test]
+    fn test_resolution_pays_the_buyer_and_leaves_the_rest_releasable() {
+        let mut escrow = escrow(0, 1_000);
+        let frozen = escrow.freeze_order(1_000, false).unwrap();
+
+        escrow.resolve_frozen(frozen, 250).unwrap();
+
+        assert_eq!(escrow.frozen, 0);
+        assert_eq!(escrow.balance, 750);
+        assert_eq!(escrow.releasable(), 750);
+    }
+}
@@ -1,6 +1,8 @@
+use super::daily::DailySales;
+use super::product::Purchase;
 use super::store::Store;
 use crate::error::CustomError;
-use crate::types::{LoyaltyPointType, LoyaltyTier, LoyaltyTransactionType};
+use crate::types::{LoyaltyPointType, LoyaltyTier, LoyaltyTransactionType, NegativeBalancePolicy};
 use anchor_lang::prelude::*;
 
 #[account]
@@ -18,10 +20,43 @@ pub struct LoyaltyAccount {
     pub referral_code: [u8; 8],      // User's referral code
     pub referred_by: Option<Pubkey>, // Who referred this user
     pub total_referrals: u16,        // Number of successful referrals
+    pub referral_rewarded: bool,     // Whether this user's referral has been rewarded
+    pub transaction_count: u64,      // Sequence number of the next transaction record
+    pub points_debt: u64,            // Clawed-back points still owed from future earnings
+    pub purchase_count: u64,         // Orders placed at the store since enrolling
 }
 
 impl LoyaltyAccount {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + (1 + 32) + 2;
+    pub const LEN: usize =
+        8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + (1 + 32) + 2 + 1 + 8 + 8 + 8;
+
+    /// Count an order placed at the store
+    pub fn record_purchase(&mut self) -> Result<()> {
+        self.purchase_count = self
+            .purchase_count
+            .checked_add(1)
+            .ok_or(CustomError::ArithmeticError)?;
+        Ok(())
+    }
+
+    /// Claim the next transaction sequence number
+    pub fn next_transaction_sequence(&mut self) -> Result<u64> {
+        let sequence = self.transaction_count;
+        self.transaction_count = self
+            .transaction_count
+            .checked_add(1)
+            .ok_or(CustomError::ArithmeticError)?;
+        Ok(sequence)
+    }
+
+    /// Resolve the referral code for a user, deriving it from the wallet if none is given
+    pub fn resolve_referral_code(referral_code: Option<[u8; 8]>, user: &Pubkey) -> [u8; 8] {
+        referral_code.unwrap_or_else(|| {
+            let mut code = [0u8; 8];
+            code.copy_from_slice(&user.to_bytes()[0..8]);
+            code
+        })
+    }
 
     /// Calculate and update user's loyalty tier
     pub fn update_tier(&mut self) {
@@ -64,15 +99,43 @@ impl LoyaltyAccount {
             .checked_add(points)
             .ok_or(CustomError::ArithmeticError)?;
 
+        // Outstanding clawback debt is repaid before points become spendable
+        let repaid = points.min(self.points_debt);
+        self.points_debt -= repaid;
+
         self.available_points = self
             .available_points
-            .checked_add(points)
+            .checked_add(points - repaid)
             .ok_or(CustomError::ArithmeticError)?;
 
         self.update_tier();
         Ok(())
     }
 
+    /// Claw back previously earned points, returning the shortfall that could not be taken
+    pub fn reverse_points(&mut self, points: u64, policy: NegativeBalancePolicy) -> Result<u64> {
+        let shortfall = points.saturating_sub(self.available_points);
+
+        match policy {
+            NegativeBalancePolicy::Reject => {
+                require!(shortfall == 0, CustomError::InsufficientLoyaltyPoints);
+            }
+            NegativeBalancePolicy::CarryDebt => {
+                self.points_debt = self
+                    .points_debt
+                    .checked_add(shortfall)
+                    .ok_or(CustomError::ArithmeticError)?;
+            }
+            NegativeBalancePolicy::ClampToZero => {}
+        }
+
+        self.available_points -= points - shortfall;
+        self.total_points = self.total_points.saturating_sub(points);
+
+        self.update_tier();
+        Ok(shortfall)
+    }
+
     /// Redeem points from the account
     pub fn redeem_points(&mut self, points: u64) -> Result<()> {
         require!(
@@ -108,10 +171,11 @@ pub struct LoyaltyProgram {
     pub tier_multiplier_enabled: bool, // Whether tier multipliers are enabled
     pub created_at: i64,               // Program creation timestamp
     pub updated_at: i64,               // Last update timestamp
+    pub negative_balance_policy: NegativeBalancePolicy, // How clawbacks handle spent points
 }
 
 impl LoyaltyProgram {
-    pub const LEN: usize = 8 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 2 + 4 + 1 + 8 + 8;
+    pub const LEN: usize = 8 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 2 + 4 + 1 + 8 + 8 + 1;
 
     /// Calculate points earned for a purchase amount
     pub fn calculate_points_earned(&self, amount_spent: u64, tier: LoyaltyTier) -> u64 {
@@ -142,8 +206,23 @@ impl LoyaltyProgram {
     }
 }
 
+#[account]
+pub struct ReferralCode {
+    pub store: Pubkey,     // Store the code is registered in
+    pub code: [u8; 8],     // The referral code itself
+    pub referrer: Pubkey,  // Wallet that owns the code
+    pub created_at: i64,   // Registration timestamp
+}
+
+impl ReferralCode {
+    pub const LEN: usize = 8 + 32 + 8 + 32 + 8;
+}
+
 #[account]
 pub struct LoyaltyTransaction {
+    pub loyalty_account: Pubkey,                  // Loyalty account this record belongs to
+    pub sequence: u64,                            // Position in the account's history
+    pub payer: Pubkey,                            // Who paid rent for this record
     pub user: Pubkey,                             // User involved in transaction
     pub store: Pubkey,                            // Store where transaction occurred
     pub transaction_type: LoyaltyTransactionType, // Type of transaction
@@ -153,10 +232,20 @@ pub struct LoyaltyTransaction {
     pub description: String,                      // Transaction description
     pub timestamp: i64,                           // Transaction timestamp
     pub related_user: Option<Pubkey>,             // Related user (for gifts/referrals)
+    pub receipt: Option<Pubkey>,                  // Purchase receipt the points relate to
+    pub points_reversed: u64,                     // Points clawed back from this record so far
 }
 
 impl LoyaltyTransaction {
-    pub const LEN: usize = 8 + 32 + 32 + 1 + 1 + 8 + (1 + 8) + (4 + 100) + 8 + (1 + 32);
+    pub const LEN: usize =
+        8 + 32 + 8 + 32 + 32 + 32 + 1 + 1 + 8 + (1 + 8) + (4 + 100) + 8 + (1 + 32) + (1 + 32) + 8;
+
+    /// Earned points a refund of the receipt could still claw back
+    pub fn is_reversible(&self) -> bool {
+        self.transaction_type == LoyaltyTransactionType::Earned
+            && self.receipt.is_some()
+            && self.points_reversed < self.points
+    }
 }
 
 #[derive(Accounts)]
@@ -183,6 +272,7 @@ pub struct InitializeLoyaltyProgram<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(referral_code: Option<[u8; 8]>)]
 pub struct InitializeLoyaltyAccount<'info> {
     #[account(
         init,
@@ -193,6 +283,26 @@ pub struct InitializeLoyaltyAccount<'info> {
     )]
     pub loyalty_account: Account<'info, LoyaltyAccount>,
 
+    /// Registry entry for the user's own code; `init` fails if the code is taken
+    #[account(
+        init,
+        payer = user,
+        space = ReferralCode::LEN,
+        seeds = [
+            b"referral_code",
+            store.key().as_ref(),
+            LoyaltyAccount::resolve_referral_code(referral_code, &user.key()).as_ref()
+        ],
+        bump
+    )]
+    pub referral_code_registry: Account<'info, ReferralCode>,
+
+    /// Registry entry of the referrer's code, required when a referrer code is given
+    #[account(
+        constraint = referrer_code_registry.store == store.key() @ CustomError::InvalidReferralCode
+    )]
+    pub referrer_code_registry: Option<Account<'info, ReferralCode>>,
+
     #[account(mut)]
     pub store: Account<'info, Store>,
 
@@ -202,6 +312,16 @@ pub struct InitializeLoyaltyAccount<'info> {
     )]
     pub loyalty_program: Account<'info, LoyaltyProgram>,
 
+    /// Today's sales bucket
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = DailySales::LEN,
+        seeds = [b"daily", store.key().as_ref(), &DailySales::current_day()?.to_le_bytes()],
+        bump
+    )]
+    pub daily_sales: Account<'info, DailySales>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
@@ -231,16 +351,157 @@ pub struct EarnLoyaltyPoints<'info> {
     #[account(
         init,
         payer = user,
-        space = LoyaltyTransaction::LEN
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            loyalty_account.key().as_ref(),
+            &loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
     )]
     pub transaction_record: Account<'info, LoyaltyTransaction>,
 
+    /// Receipt the points are earned for, so they can be clawed back on refund
+    #[account(
+        mut,
+        constraint = purchase_receipt.buyer == user.key() @ CustomError::LoyaltyReceiptMismatch,
+        constraint = purchase_receipt.store == store.key() @ CustomError::LoyaltyReceiptMismatch,
+        constraint = !purchase_receipt.loyalty_earned @ CustomError::LoyaltyPointsAlreadyEarned
+    )]
+    pub purchase_receipt: Account<'info, Purchase>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct DeductLoyaltyPoints<'info> {
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), loyalty_account.user.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub loyalty_account: Account<'info, LoyaltyAccount>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        seeds = [b"loyalty_program", store.key().as_ref()],
+        bump
+    )]
+    pub loyalty_program: Account<'info, LoyaltyProgram>,
+
+    /// Receipt being refunded
+    #[account(
+        constraint = receipt.buyer == loyalty_account.user @ CustomError::LoyaltyReceiptMismatch,
+        constraint = receipt.store == store.key() @ CustomError::LoyaltyReceiptMismatch
+    )]
+    pub receipt: Account<'info, Purchase>,
+
+    /// The record of the points originally earned for the receipt
+    #[account(
+        mut,
+        has_one = loyalty_account,
+        constraint = earned_transaction.receipt == Some(receipt.key()) @ CustomError::LoyaltyReceiptMismatch,
+        constraint = earned_transaction.transaction_type == LoyaltyTransactionType::Earned
+            @ CustomError::LoyaltyReceiptMismatch
+    )]
+    pub earned_transaction: Account<'info, LoyaltyTransaction>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            loyalty_account.key().as_ref(),
+            &loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_record: Account<'info, LoyaltyTransaction>,
+
+    /// Store owner or manager issuing the refund
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AwardReferralBonus<'info> {
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), referrer_loyalty_account.user.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub referrer_loyalty_account: Account<'info, LoyaltyAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), referee_loyalty_account.user.as_ref()],
+        bump,
+        has_one = store,
+        constraint = referee_loyalty_account.referred_by == Some(referrer_loyalty_account.user)
+            @ CustomError::InvalidReferralCode
+    )]
+    pub referee_loyalty_account: Account<'info, LoyaltyAccount>,
+
+    /// Receipt of the referee's qualifying purchase at this store
+    #[account(
+        constraint = referee_receipt.buyer == referee_loyalty_account.user @ CustomError::ReferralNotQualified,
+        constraint = referee_receipt.store == store.key() @ CustomError::ReferralNotQualified
+    )]
+    pub referee_receipt: Account<'info, Purchase>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        seeds = [b"loyalty_program", store.key().as_ref()],
+        bump
+    )]
+    pub loyalty_program: Account<'info, LoyaltyProgram>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            referrer_loyalty_account.key().as_ref(),
+            &referrer_loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub referrer_transaction: Account<'info, LoyaltyTransaction>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            referee_loyalty_account.key().as_ref(),
+            &referee_loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub referee_transaction: Account<'info, LoyaltyTransaction>,
+
+    /// Anyone may crank the reward once the referee qualifies
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct RedeemLoyaltyPoints<'info> {
     #[account(
@@ -264,7 +525,13 @@ pub struct RedeemLoyaltyPoints<'info> {
     #[account(
         init,
         payer = user,
-        space = LoyaltyTransaction::LEN
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            loyalty_account.key().as_ref(),
+            &loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
     )]
     pub transaction_record: Account<'info, LoyaltyTransaction>,
 
@@ -324,14 +591,26 @@ pub struct GiftLoyaltyPoints<'info> {
     #[account(
         init,
         payer = sender,
-        space = LoyaltyTransaction::LEN
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            sender_loyalty_account.key().as_ref(),
+            &sender_loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
     )]
     pub sender_transaction: Account<'info, LoyaltyTransaction>,
 
     #[account(
         init,
         payer = sender,
-        space = LoyaltyTransaction::LEN
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            recipient_loyalty_account.key().as_ref(),
+            &recipient_loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
     )]
     pub recipient_transaction: Account<'info, LoyaltyTransaction>,
 
@@ -344,6 +623,37 @@ pub struct GiftLoyaltyPoints<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CloseLoyaltyTransaction<'info> {
+    #[account(
+        seeds = [b"loyalty_account", loyalty_account.store.as_ref(), user.key().as_ref()],
+        bump,
+        has_one = user
+    )]
+    pub loyalty_account: Account<'info, LoyaltyAccount>,
+
+    #[account(
+        mut,
+        close = rent_recipient,
+        seeds = [
+            b"loyalty_transaction",
+            loyalty_account.key().as_ref(),
+            &transaction_record.sequence.to_le_bytes()
+        ],
+        bump,
+        has_one = loyalty_account,
+        constraint = transaction_record.payer == rent_recipient.key() @ CustomError::Unauthorized,
+        constraint = !transaction_record.is_reversible() @ CustomError::LoyaltyRecordInUse
+    )]
+    pub transaction_record: Account<'info, LoyaltyTransaction>,
+
+    /// CHECK: Receives the reclaimed rent; must be the record's original payer
+    #[account(mut)]
+    pub rent_recipient: AccountInfo<'info>,
+
+    pub user: Signer<'info>,
+}
+
 /// Event emitted when loyalty points are earned
 #[event]
 pub struct LoyaltyPointsEarned {
@@ -368,6 +678,21 @@ pub struct LoyaltyPointsRedeemed {
     pub timestamp: i64,
 }
 
+/// Event emitted when loyalty points are clawed back for a refund
+#[event]
+pub struct LoyaltyPointsReversed {
+    pub user: Pubkey,
+    pub store: Pubkey,
+    pub receipt: Pubkey,
+    pub points_reversed: u64,
+    pub shortfall: u64,
+    pub refund_amount: u64,
+    pub remaining_points: u64,
+    pub new_total: u64,
+    pub new_tier: LoyaltyTier,
+    pub timestamp: i64,
+}
+
 /// Event emitted when loyalty points are gifted
 #[event]
 pub struct LoyaltyPointsGifted {
@@ -378,6 +703,17 @@ pub struct LoyaltyPointsGifted {
     pub timestamp: i64,
 }
 
+/// Event emitted when a referral is rewarded
+#[event]
+pub struct ReferralRewarded {
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+    pub store: Pubkey,
+    pub referrer_points: u64,
+    pub referee_points: u64,
+    pub timestamp: i64,
+}
+
 /// Event emitted when user's loyalty tier changes
 #[event]
 pub struct LoyaltyTierChanged {
@@ -397,3 +733,75 @@ pub struct LoyaltyProgramUpdated {
     pub redemption_rate: u64,
     pub updated_at: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_with(available_points: u64, total_points: u64) -> LoyaltyAccount {
+        LoyaltyAccount {
+            user: Pubkey::default(),
+            store: Pubkey::default(),
+            total_points,
+            available_points,
+            redeemed_points: total_points - available_points,
+            expired_points: 0,
+            tier: LoyaltyTier::Bronze,
+            tier_progress: 0,
+            last_purchase_date: 0,
+            created_at: 0,
+            referral_code: [0u8; 8],
+            referred_by: None,
+            total_referrals: 0,
+            referral_rewarded: false,
+            transaction_count: 0,
+            points_debt: 0,
+            purchase_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_reverse_points_with_enough_balance() {
+        let mut account = account_with(300, 1200);
+
+        let shortfall = account
+            .reverse_points(200, NegativeBalancePolicy::Reject)
+            .unwrap();
+
+        assert_eq!(shortfall, 0);
+        assert_eq!(account.available_points, 100);
+        assert_eq!(account.total_points, 1000);
+        assert_eq!(account.tier, LoyaltyTier::Silver);
+    }
+
+    #[test]
+    fn test_reverse_points_policies_on_spent_points() {
+        // 100 points left to spend, 250 to claw back
+        let mut clamped = account_with(100, 500);
+        assert_eq!(
+            clamped
+                .reverse_points(250, NegativeBalancePolicy::ClampToZero)
+                .unwrap(),
+            150
+        );
+        assert_eq!(clamped.available_points, 0);
+        assert_eq!(clamped.points_debt, 0);
+
+        let mut rejected = account_with(100, 500);
+        assert!(rejected
+            .reverse_points(250, NegativeBalancePolicy::Reject)
+            .is_err());
+
+        let mut indebted = account_with(100, 500);
+        indebted
+            .reverse_points(250, NegativeBalancePolicy::CarryDebt)
+            .unwrap();
+        assert_eq!(indebted.available_points, 0);
+        assert_eq!(indebted.points_debt, 150);
+
+        // Future earnings pay the debt off first
+        indebted.add_points(200, LoyaltyPointType::Purchase).unwrap();
+        assert_eq!(indebted.points_debt, 0);
+        assert_eq!(indebted.available_points, 50);
+    }
+}
@@ -0,0 +1,143 @@
+use super::product::Purchase;
+use crate::types::{AnomalyFlag, TransactionStatus};
+use crate::utils::pricing::PriceConverter;
+use anchor_lang::prelude::*;
+
+/// Purchase receipt as written before variants, returns and settlement
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LegacyPurchase {
+    pub product_uuids: Vec<[u8; 16]>,
+    pub quantities: Vec<u64>,
+    pub total_paid: u64,
+    pub gas_fee: u64,
+    pub status: TransactionStatus,
+    pub anomaly: AnomalyFlag,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub ts: i64,
+}
+
+impl LegacyPurchase {
+    /// The receipt in the current layout. Its payment was escrowed before
+    /// orders were settled one by one, so it counts as settled and stays
+    /// with the store's withdrawable balance. Loyalty points were not tied
+    /// to receipts then, so it can't earn them again.
+    pub fn into_purchase(self) -> Result<Purchase> {
+        let lines = self.product_uuids.len();
+        Ok(Purchase {
+            variant_ids: vec![None; lines],
+            line_amounts: PriceConverter::split_by_weight(self.total_paid, &self.quantities)?,
+            returned_quantities: vec![0; self.quantities.len()],
+            product_uuids: self.product_uuids,
+            quantities: self.quantities,
+            total_paid: self.total_paid,
+            gas_fee: self.gas_fee,
+            status: self.status,
+            anomaly: self.anomaly,
+            store: self.store,
+            buyer: self.buyer,
+            ts: self.ts,
+            token_mints: vec![None; lines],
+            coupon: None,
+            coupon_discount: 0,
+            bundle: None,
+            awaiting_delivery: false,
+            refunded_amount: 0,
+            disputed: false,
+            settled: true,
+            platform_fee: 0,
+            loyalty_earned: true,
+            order_id: 0,
+            release_after: self.ts,
+        })
+    }
+}
+
+/// A program account still in the layout it was created with, grown to the
+/// current one at the payer's expense
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: the handler checks the discriminator and the old length
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub old_len: u64,
+    pub new_len: u64,
+    pub migrated_by: Pubkey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::product::Product;
+    use crate::types::{StablePrice, TokenizedType};
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn test_zero_extended_legacy_product_reads_in_current_layout() {
+        // A product as first written, with the longest metadata URI
+        let store = Pubkey::new_unique();
+        let legacy = (
+            [7u8; 16],
+            500u64,
+            StablePrice {
+                usdc_price: 5_000_000,
+                sol_price: 50_000_000,
+                last_updated: 1_700_000_000,
+                is_fixed: true,
+            },
+            12u64,
+            TokenizedType::None,
+            true,
+            "u".repeat(Product::MAX_METADATA_URI_LEN),
+            store,
+            Pubkey::new_unique(),
+        );
+        let mut data = Product::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+        data.resize(Product::LEN, 0);
+
+        let product = Product::try_deserialize(&mut data.as_slice()).unwrap();
+
+        assert_eq!(product.stock, 12);
+        assert_eq!(product.store, store);
+        assert!(product.price_schedule.is_empty() && product.attributes.is_empty());
+        assert_eq!(product.gtin_hash, None);
+        assert_eq!(product.category, None);
+    }
+
+    #[test]
+    fn test_legacy_purchase_splits_payment_by_quantity() {
+        let legacy = LegacyPurchase {
+            product_uuids: vec![[1; 16], [2; 16]],
+            quantities: vec![1, 2],
+            total_paid: 1_000,
+            gas_fee: 5,
+            status: TransactionStatus::Completed,
+            anomaly: AnomalyFlag::None,
+            store: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            ts: 1_700_000_000,
+        };
+
+        let data = legacy.try_to_vec().unwrap();
+        let purchase = LegacyPurchase::deserialize(&mut data.as_slice())
+            .unwrap()
+            .into_purchase()
+            .unwrap();
+
+        assert_eq!(purchase.line_amounts, vec![333, 667]);
+        assert_eq!(purchase.variant_ids, vec![None, None]);
+        assert_eq!(purchase.returned_quantities, vec![0, 0]);
+        assert_eq!(purchase.net_paid(), 1_000);
+        assert!(purchase.settled && purchase.loyalty_earned);
+        assert!(purchase.try_to_vec().unwrap().len() <= Purchase::LEN - 8);
+    }
+}
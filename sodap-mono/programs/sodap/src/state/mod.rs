@@ -2,60 +2,112 @@ use crate::error::CustomError;
 use anchor_lang::prelude::*;
 
 // Submodules for on-chain accounts and context structs
+pub mod achievement;
 pub mod admin;
 pub mod bnpl;
+pub mod bundle;
+pub mod catalog;
+pub mod category;
+pub mod coupon;
+pub mod daily;
+pub mod delivery;
+pub mod dispute;
 pub mod loyalty;
+pub mod migration;
 pub mod product;
+pub mod reservation;
+pub mod returns;
+pub mod review;
+pub mod reward;
+pub mod settlement;
+pub mod stats;
 pub mod store;
+pub mod treasury;
 pub mod user;
 
 // Re-export all relevant structs and context types
+pub use achievement::*;
 pub use admin::*;
 pub use bnpl::*;
+pub use bundle::*;
+pub use catalog::*;
+pub use category::*;
+pub use coupon::*;
+pub use daily::*;
+pub use delivery::*;
+pub use dispute::*;
 pub use loyalty::*;
+pub use migration::*;
 pub use product::PurchaseCart;
 pub use product::*;
+pub use reservation::*;
+pub use returns::*;
+pub use review::*;
+pub use reward::*;
+pub use settlement::*;
+pub use stats::*;
 pub use store::Store;
 pub use store::*;
+pub use treasury::*;
 pub use user::*;
 
-#[derive(Accounts)]
-pub struct ReleaseEscrow<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", store.key().as_ref()],
-        bump
-    )]
-    pub escrow_account: Account<'info, Escrow>,
-    pub store: Account<'info, Store>,
-    #[account(
-        mut,
-        constraint = store_owner.key() == store.owner @ CustomError::Unauthorized
-    )]
-    pub store_owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct RefundEscrow<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", store.key().as_ref()],
-        bump
-    )]
-    pub escrow_account: Account<'info, Escrow>,
-    pub store: Account<'info, Store>,
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    #[account(
-        constraint = store_owner.key() == store.owner @ CustomError::Unauthorized
-    )]
-    pub store_owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
-
 #[account]
 pub struct Escrow {
     pub store: Pubkey,
     pub balance: u64,
+    pub held_for_delivery: u64, // Paid for digital goods not yet delivered
+    pub frozen: u64,            // Locked by open disputes
+    pub unsettled: u64,         // Paid for orders not yet settled to the store
+    pub hold_period: i64,       // Seconds before unconfirmed orders settle, 0 for the default
+}
+
+impl Escrow {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8;
+    pub const DEFAULT_HOLD_PERIOD: i64 = 7 * 24 * 60 * 60;
+
+    /// Balance the store can withdraw
+    pub fn releasable(&self) -> u64 {
+        self.balance
+            .saturating_sub(self.held_for_delivery)
+            .saturating_sub(self.frozen)
+            .saturating_sub(self.unsettled)
+    }
+
+    /// When an order bought at `purchased_at` settles without confirmation
+    pub fn settles_at(&self, purchased_at: i64) -> i64 {
+        let hold_period = if self.hold_period > 0 {
+            self.hold_period
+        } else {
+            Self::DEFAULT_HOLD_PERIOD
+        };
+        purchased_at.saturating_add(hold_period)
+    }
+
+    /// Freeze what is left of a disputed order, taking it from the funds
+    /// held for delivery or awaiting settlement. Returns the frozen amount.
+    pub fn freeze_order(&mut self, outstanding: u64, awaiting_delivery: bool) -> Result<u64> {
+        let source = if awaiting_delivery {
+            &mut self.held_for_delivery
+        } else {
+            &mut self.unsettled
+        };
+        let amount = outstanding.min(*source);
+        *source -= amount;
+        self.frozen = self
+            .frozen
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticError)?;
+        Ok(amount)
+    }
+
+    /// Unfreeze a resolved dispute's `amount`, of which `buyer_amount` leaves
+    /// the escrow for the buyer and the rest stays for the store
+    pub fn resolve_frozen(&mut self, amount: u64, buyer_amount: u64) -> Result<()> {
+        self.frozen = self.frozen.saturating_sub(amount);
+        self.balance = self
+            .balance
+            .checked_sub(buyer_amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        Ok(())
+    }
 }
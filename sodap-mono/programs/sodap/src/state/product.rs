@@ -1,8 +1,22 @@
+use super::category::CategoryIndex;
+use super::coupon::{Coupon, CouponUsage};
+use super::daily::DailySales;
+use super::loyalty::LoyaltyAccount;
+use super::reservation::Reservation;
+use super::reward::Voucher;
+use super::stats::StoreStats;
 use super::store::Store;
+use super::treasury::FeeConfig;
+use super::user::UserProfile;
 use crate::error::CustomError;
 use crate::state::Escrow;
-use crate::types::{AnomalyFlag, StablePrice, TokenizedType, TransactionStatus};
+use crate::types::{
+    AnomalyFlag, ProductAttribute, ProductRegistration, PurchaseLimits, ScheduledPrice,
+    StablePrice, TokenizedType, TransactionStatus,
+};
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
 
 #[account]
 pub struct Product {
@@ -15,17 +29,110 @@ pub struct Product {
     pub metadata_uri: String,
     pub store: Pubkey,
     pub authority: Pubkey,
+    pub price_schedule: Vec<ScheduledPrice>, // Upcoming and running sales, sorted by start
+    pub active_sale_start: Option<i64>,      // Start of the sale last announced as running
+    pub purchase_limits: PurchaseLimits,     // Per-order/per-wallet caps and allowlist gating
+    pub gtin_hash: Option<[u8; 32]>,         // sha256 of the product's barcode, if indexed
+    pub open_reservations: u32,              // Reservation lines holding this product's stock
+    pub reorder_threshold: u64,              // Alert when stock falls below this (0 = never)
+    pub is_digital: bool,                    // Delivered as a download, escrow waits for delivery
+    pub attributes: Vec<ProductAttribute>,   // Typed attributes, e.g. weight = 500
+    pub category: Option<Pubkey>,            // Category from the store's or the platform's tree
 }
 
 impl Product {
+    pub const MAX_SCHEDULED_PRICES: usize = 5;
+    pub const MAX_METADATA_URI_LEN: usize = 200;
+    pub const MAX_ATTRIBUTES: usize = 8;
     // Calculate: discriminator + uuid + price + stable_pricing + stock + tokenized_type + is_active + metadata_uri + store + authority
+    //   + price_schedule + active_sale_start + purchase_limits + gtin_hash + open_reservations
+    //   + reorder_threshold + is_digital + attributes + category
     // StablePrice: usdc_price(8) + sol_price(8) + last_updated(8) + is_fixed(1) = 25 bytes
-    pub const LEN: usize = 8 + 16 + 8 + 25 + 8 + 1 + 1 + (4 + 200) + 32 + 32;
+    // ScheduledPrice: start_time(8) + end_time(8) + usdc_price(8) + sol_price(8) = 32 bytes
+    // PurchaseLimits: max_per_order(8) + max_per_wallet(8) + sale_start(8) + allowlist_root(33) = 57 bytes
+    pub const LEN: usize = 8
+        + 16
+        + 8
+        + 25
+        + 8
+        + 1
+        + 1
+        + (4 + Self::MAX_METADATA_URI_LEN)
+        + 32
+        + 32
+        + (4 + Self::MAX_SCHEDULED_PRICES * 32)
+        + (1 + 8)
+        + 57
+        + (1 + 32)
+        + 4
+        + 8
+        + 1
+        + (4 + Self::MAX_ATTRIBUTES * ProductAttribute::LEN)
+        + (1 + 32);
+
+    /// Scheduled sale running at `now`, if any
+    pub fn active_sale(&self, now: i64) -> Option<&ScheduledPrice> {
+        self.price_schedule
+            .iter()
+            .find(|sale| sale.start_time <= now && now < sale.end_time)
+    }
+
+    /// Lamport unit price charged at checkout, taking scheduled sales into account
+    pub fn unit_price(&self, now: i64) -> u64 {
+        self.active_sale(now)
+            .map(|sale| sale.sol_price)
+            .unwrap_or(self.price)
+    }
+}
+
+/// Units of a limited product a wallet has bought, and its allowlist status
+#[account]
+pub struct PurchaseCounter {
+    pub product: Pubkey,   // Limited product
+    pub buyer: Pubkey,     // Wallet being tracked
+    pub purchased: u64,    // Units bought so far
+    pub allowlisted: bool, // Whether the wallet proved allowlist membership
+}
+
+impl PurchaseCounter {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+/// A size/color SKU of a product with its own stock and optional price
+#[account]
+pub struct ProductVariant {
+    pub product: Pubkey,                   // Parent product
+    pub store: Pubkey,                     // Store the product belongs to
+    pub variant_id: [u8; 16],              // Unique variant identifier
+    pub attributes: Vec<ProductAttribute>, // e.g. size = M, color = Red
+    pub price_override: Option<u64>,       // Replaces the product price when set
+    pub stock: u64,                        // Tracked apart from Product.stock
+    pub is_active: bool,
+    pub authority: Pubkey,
+}
+
+impl ProductVariant {
+    pub const MAX_ATTRIBUTES: usize = 5;
+    pub const LEN: usize = 8
+        + 32
+        + 32
+        + 16
+        + (4 + Self::MAX_ATTRIBUTES * ProductAttribute::LEN)
+        + (1 + 8)
+        + 8
+        + 1
+        + 32;
+
+    /// Unit price of the variant given its parent product
+    pub fn unit_price(&self, product: &Product, now: i64) -> u64 {
+        self.price_override.unwrap_or(product.unit_price(now))
+    }
 }
 
 #[account]
 pub struct Purchase {
     pub product_uuids: Vec<[u8; 16]>,
+    pub variant_ids: Vec<Option<[u8; 16]>>,
     pub quantities: Vec<u64>,
     pub total_paid: u64,
     pub gas_fee: u64,
@@ -34,14 +141,97 @@ pub struct Purchase {
     pub store: Pubkey,
     pub buyer: Pubkey,
     pub ts: i64,
+    pub token_mints: Vec<Option<Pubkey>>, // Ownership token minted per line, if tokenized
+    pub coupon: Option<Pubkey>,           // Coupon applied at checkout
+    pub coupon_discount: u64,             // Lamports taken off by the coupon
+    pub bundle: Option<Pubkey>,           // Bundle bought, lines then list its components
+    pub awaiting_delivery: bool,          // Holds digital goods not yet delivered
+    pub line_amounts: Vec<u64>,           // Share of total_paid per line
+    pub returned_quantities: Vec<u64>,    // Units returned per line
+    pub refunded_amount: u64,             // Lamports refunded through returns
+    pub disputed: bool,                   // Escrow frozen by an open dispute
+    pub settled: bool,                    // Payment released to the store
+    pub platform_fee: u64,                // Marketplace cut taken when the order settles
+    pub loyalty_earned: bool,             // Loyalty points were earned for this receipt
+    pub order_id: u64,                    // Buyer-chosen order number in the receipt's seeds
+    pub release_after: i64,               // When the order settles without buyer confirmation
 }
 
 impl Purchase {
-    pub const LEN: usize = 8 + (4 + 10 * 16) + (4 + 10 * 8) + 8 + 8 + 1 + 1 + 32 + 32 + 8;
+    pub const LEN: usize = 8
+        + (4 + 10 * 16)
+        + (4 + 10 * 17)
+        + (4 + 10 * 8)
+        + 8
+        + 8
+        + 1
+        + 1
+        + 32
+        + 32
+        + 8
+        + (4 + 10 * 33)
+        + (1 + 32)
+        + 8
+        + (1 + 32)
+        + 1
+        + (4 + 10 * 8)
+        + (4 + 10 * 8)
+        + 8
+        + 1
+        + 1
+        + 8
+        + 1
+        + 8
+        + 8;
+
+    /// What the buyer has paid and not had refunded
+    pub fn net_paid(&self) -> u64 {
+        self.total_paid.saturating_sub(self.refunded_amount)
+    }
+
+    /// Units of a line that can still be returned
+    pub fn returnable(&self, line: usize) -> u64 {
+        let returned = self.returned_quantities.get(line).copied().unwrap_or(0);
+        self.quantities[line].saturating_sub(returned)
+    }
+
+    /// Refund for returning `quantity` more units of a line. Pro-rated on the
+    /// units returned so far, so returning a whole line piece by piece adds up
+    /// to exactly what was paid for it.
+    pub fn refund_for(&self, line: usize, quantity: u64) -> u64 {
+        let line_amount = self.line_amounts[line] as u128;
+        let line_quantity = self.quantities[line] as u128;
+        let returned = self.returned_quantities[line] as u128;
+        (line_amount * (returned + quantity as u128) / line_quantity
+            - line_amount * returned / line_quantity) as u64
+    }
+
+    /// What settling the order pays out of escrow, and the platform's part of
+    /// it. Refunded orders pay the fee only on what the store keeps.
+    pub fn settlement(&self) -> (u64, u64) {
+        let amount = self.net_paid();
+        let platform_fee = (self.platform_fee as u128 * amount as u128)
+            .checked_div(self.total_paid as u128)
+            .unwrap_or(0) as u64;
+        (amount, platform_fee)
+    }
+}
+
+/// Maps the sha256 of a GTIN barcode to a product, so scanners can look it up
+#[account]
+pub struct BarcodeIndex {
+    pub store: Pubkey,          // Store selling the product
+    pub gtin_hash: [u8; 32],    // sha256 of the GTIN digits
+    pub product: Pubkey,        // Product account
+    pub product_uuid: [u8; 16], // Product identifier used at checkout
+}
+
+impl BarcodeIndex {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 16;
 }
 
 #[derive(Accounts)]
-#[instruction(product_uuid: [u8; 16])]
+#[instruction(registration: ProductRegistration)]
 pub struct RegisterProduct<'info> {
     #[account(mut)]
     pub store: Account<'info, Store>,
@@ -49,11 +239,17 @@ pub struct RegisterProduct<'info> {
         init,
         payer = authority,
         space = Product::LEN,
-        seeds = [b"product", store.key().as_ref(), product_uuid.as_ref()],
+        seeds = [b"product", store.key().as_ref(), registration.product_uuid.as_ref()],
         bump
     )]
     pub product: Account<'info, Product>,
+    /// CHECK: Barcode index PDA, created by the handler when a GTIN is given
     #[account(mut)]
+    pub barcode_index: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -71,7 +267,19 @@ pub struct UpdateProduct<'info> {
         has_one = authority
     )]
     pub product: Account<'info, Product>,
+    #[account(mut)]
     pub authority: Signer<'info>,
+    /// CHECK: New barcode index PDA, created by the handler when the GTIN changes
+    #[account(mut)]
+    pub barcode_index: Option<UncheckedAccount<'info>>,
+    /// Index of the product's current barcode, closed when the GTIN changes
+    #[account(
+        mut,
+        has_one = store @ CustomError::BarcodeIndexMismatch,
+        constraint = old_barcode_index.product == product.key() @ CustomError::BarcodeIndexMismatch
+    )]
+    pub old_barcode_index: Option<Account<'info, BarcodeIndex>>,
+    pub system_program: Option<Program<'info, System>>,
 }
 
 #[derive(Accounts)]
@@ -91,6 +299,134 @@ pub struct DeactivateProduct<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(product_uuid: [u8; 16])]
+pub struct CloseProduct<'info> {
+    /// Receives the reclaimed rent
+    #[account(mut)]
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        seeds = [b"product", store.key().as_ref(), product_uuid.as_ref()],
+        bump,
+        has_one = store,
+        has_one = authority,
+        close = store
+    )]
+    pub product: Account<'info, Product>,
+    #[account(
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    /// Index of the product's barcode, closed along with the product
+    #[account(
+        mut,
+        has_one = store @ CustomError::BarcodeIndexMismatch,
+        constraint = barcode_index.product == product.key() @ CustomError::BarcodeIndexMismatch,
+        close = store
+    )]
+    pub barcode_index: Option<Account<'info, BarcodeIndex>>,
+    /// Index of the product's category, which the product is removed from
+    #[account(
+        mut,
+        seeds = [b"category_index", store.key().as_ref(), category_index.category.as_ref()],
+        bump
+    )]
+    pub category_index: Option<Account<'info, CategoryIndex>>,
+    pub authority: Signer<'info>,
+}
+
+/// Longest supplier name recorded on a restock
+pub const MAX_SUPPLIER_LEN: usize = 50;
+
+#[derive(Accounts)]
+pub struct RestockProduct<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        seeds = [b"product", store.key().as_ref(), product.uuid.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub product: Account<'info, Product>,
+    /// Restock a variant's stock instead of the product's
+    #[account(
+        mut,
+        constraint = variant.product == product.key() @ CustomError::VariantNotFound
+    )]
+    pub variant: Option<Account<'info, ProductVariant>>,
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPurchaseCounter<'info> {
+    #[account(
+        seeds = [b"product", product.store.as_ref(), product.uuid.as_ref()],
+        bump
+    )]
+    pub product: Account<'info, Product>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = PurchaseCounter::LEN,
+        seeds = [b"purchase_counter", product.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub counter: Account<'info, PurchaseCounter>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSalePrice<'info> {
+    #[account(
+        mut,
+        seeds = [b"product", product.store.as_ref(), product.uuid.as_ref()],
+        bump
+    )]
+    pub product: Account<'info, Product>,
+}
+
+#[derive(Accounts)]
+#[instruction(variant_id: [u8; 16])]
+pub struct CreateProductVariant<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        has_one = store,
+        has_one = authority
+    )]
+    pub product: Account<'info, Product>,
+    #[account(
+        init,
+        payer = authority,
+        space = ProductVariant::LEN,
+        seeds = [b"variant", product.key().as_ref(), variant_id.as_ref()],
+        bump
+    )]
+    pub variant: Account<'info, ProductVariant>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProductVariant<'info> {
+    #[account(
+        mut,
+        seeds = [b"variant", variant.product.as_ref(), variant.variant_id.as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub variant: Account<'info, ProductVariant>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
 pub struct PurchaseCart<'info> {
     #[account(mut)]
     pub store: Account<'info, Store>,
@@ -98,7 +434,12 @@ pub struct PurchaseCart<'info> {
         init,
         payer = buyer,
         space = Purchase::LEN,
-        seeds = [b"purchase", store.key().as_ref(), buyer.key().as_ref()],
+        seeds = [
+            b"purchase",
+            store.key().as_ref(),
+            buyer.key().as_ref(),
+            &order_id.to_le_bytes()
+        ],
         bump
     )]
     pub receipt: Account<'info, Purchase>,
@@ -119,6 +460,75 @@ pub struct PurchaseCart<'info> {
         bump
     )]
     pub escrow_account: Account<'info, Escrow>,
+    /// Store sales totals
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = StoreStats::LEN,
+        seeds = [b"store_stats", store.key().as_ref()],
+        bump
+    )]
+    pub store_stats: Account<'info, StoreStats>,
+    /// Today's sales bucket
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = DailySales::LEN,
+        seeds = [b"daily", store.key().as_ref(), &DailySales::current_day()?.to_le_bytes()],
+        bump
+    )]
+    pub daily_sales: Account<'info, DailySales>,
+    /// Platform fee settings
+    #[account(seeds = [b"fee_config"], bump)]
+    pub fee_config: Account<'info, FeeConfig>,
+    /// Optional reward voucher applied as a discount
+    #[account(
+        mut,
+        constraint = voucher.owner == buyer.key() @ CustomError::InvalidVoucher,
+        constraint = voucher.store == store.key() @ CustomError::InvalidVoucher
+    )]
+    pub voucher: Option<Account<'info, Voucher>>,
+    /// Optional promo code coupon issued by the store
+    #[account(mut, has_one = store @ CustomError::InvalidCoupon)]
+    pub coupon: Option<Account<'info, Coupon>>,
+    /// Per-buyer usage counter for the coupon
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = CouponUsage::LEN,
+        seeds = [
+            b"coupon_usage",
+            coupon.as_ref().map(|coupon| coupon.key()).unwrap_or_default().as_ref(),
+            buyer.key().as_ref()
+        ],
+        bump
+    )]
+    pub coupon_usage: Option<Account<'info, CouponUsage>>,
+    /// Optional reservation holding the cart's stock, consumed by this checkout
+    #[account(
+        mut,
+        has_one = buyer @ CustomError::ReservationMismatch,
+        has_one = store @ CustomError::ReservationMismatch,
+        close = buyer
+    )]
+    pub reservation: Option<Account<'info, Reservation>>,
+    /// Buyer's profile, whose purchase count is bumped on checkout
+    #[account(
+        mut,
+        seeds = [b"user_profile", buyer.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+    /// Buyer's loyalty account at the store, whose purchase count is bumped on checkout
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub loyalty_account: Option<Account<'info, LoyaltyAccount>>,
+    /// Required when the cart contains tokenized products
+    pub token_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
     pub system_program: Program<'info, System>,
 }
 /// off‑chain log
@@ -127,8 +537,179 @@ pub struct CartPurchased {
     pub store_id: Pubkey,
     pub buyer_id: Pubkey,
     pub product_uuids: Vec<[u8; 16]>,
+    pub variant_ids: Vec<Option<[u8; 16]>>,
     pub quantities: Vec<u64>,
     pub total_paid: u64,
     pub gas_fee: u64,
     pub timestamp: i64,
+    pub bundle: Option<Pubkey>,
+    pub platform_fee: u64, // Marketplace cut of total_paid
+}
+
+#[event]
+pub struct ProductTokenMinted {
+    pub receipt: Pubkey,
+    pub product_uuid: [u8; 16],
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub tokenized_type: TokenizedType,
+}
+
+/// Stock fell below the product's reorder threshold
+#[event]
+pub struct LowStock {
+    pub product: Pubkey,
+    pub store: Pubkey,
+    pub variant_id: Option<[u8; 16]>,
+    pub stock: u64,
+    pub threshold: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OutOfStock {
+    pub product: Pubkey,
+    pub store: Pubkey,
+    pub variant_id: Option<[u8; 16]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProductRestocked {
+    pub product: Pubkey,
+    pub store: Pubkey,
+    pub variant_id: Option<[u8; 16]>,
+    pub supplier: String,
+    pub quantity: u64,
+    pub cost: u64,
+    pub stock: u64,
+    pub restocked_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Last state of a closed product, so historic receipts can still be resolved
+#[event]
+pub struct ProductArchived {
+    pub product: Pubkey,
+    pub store: Pubkey,
+    pub uuid: [u8; 16],
+    pub metadata_uri: String,
+    pub price: u64,
+    pub usdc_price: u64,
+    pub sol_price: u64,
+    pub tokenized_type: TokenizedType,
+    pub closed_at: i64,
+}
+
+#[event]
+pub struct SaleStarted {
+    pub product: Pubkey,
+    pub product_uuid: [u8; 16],
+    pub start_time: i64,
+    pub end_time: i64,
+    pub usdc_price: u64,
+    pub sol_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SaleEnded {
+    pub product: Pubkey,
+    pub product_uuid: [u8; 16],
+    pub start_time: i64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(line_amounts: Vec<u64>, quantities: Vec<u64>) -> Purchase {
+        let lines = quantities.len();
+        Purchase {
+            product_uuids: vec![[0; 16]; lines],
+            variant_ids: vec![None; lines],
+            total_paid: line_amounts.iter().sum(),
+            line_amounts,
+            returned_quantities: vec![0; lines],
+            quantities,
+            gas_fee: 0,
+            status: TransactionStatus::Completed,
+            anomaly: AnomalyFlag::None,
+            store: Pubkey::default(),
+            buyer: Pubkey::default(),
+            ts: 0,
+            token_mints: vec![None; lines],
+            coupon: None,
+            coupon_discount: 0,
+            bundle: None,
+            awaiting_delivery: false,
+            refunded_amount: 0,
+            disputed: false,
+            settled: false,
+            platform_fee: 0,
+            loyalty_earned: false,
+            order_id: 0,
+            release_after: 0,
+        }
+    }
+
+    fn return_units(receipt: &mut Purchase, line: usize, quantity: u64) -> u64 {
+        let refund = receipt.refund_for(line, quantity);
+        receipt.returned_quantities[line] += quantity;
+        receipt.refunded_amount += refund;
+        refund
+    }
+
+    #[test]
+    fn test_refunds_add_up_to_the_line_amount() {
+        let mut purchase = receipt(vec![1_000, 500], vec![3, 1]);
+
+        assert_eq!(return_units(&mut purchase, 0, 1), 333);
+        assert_eq!(return_units(&mut purchase, 0, 1), 333);
+        assert_eq!(return_units(&mut purchase, 0, 1), 334);
+        assert_eq!(purchase.returnable(0), 0);
+        assert_eq!(purchase.net_paid(), 500);
+    }
+
+    #[test]
+    fn test_refund_is_the_same_however_the_line_is_split() {
+        let mut whole = receipt(vec![1_000], vec![3]);
+        let mut pieces = receipt(vec![1_000], vec![3]);
+
+        assert_eq!(return_units(&mut whole, 0, 2), 666);
+        assert_eq!(return_units(&mut pieces, 0, 1) + return_units(&mut pieces, 0, 1), 666);
+        assert_eq!(whole.refund_for(0, 1), pieces.refund_for(0, 1));
+    }
+
+    #[test]
+    fn test_settlement_charges_the_fee_on_what_the_store_keeps() {
+        let mut purchase = receipt(vec![600, 400], vec![1, 1]);
+        purchase.platform_fee = 50;
+        assert_eq!(purchase.settlement(), (1_000, 50));
+
+        return_units(&mut purchase, 1, 1);
+        assert_eq!(purchase.settlement(), (600, 30));
+
+        return_units(&mut purchase, 0, 1);
+        assert_eq!(purchase.settlement(), (0, 0));
+    }
+
+    #[test]
+    fn test_hold_period_sets_the_release_time() {
+        let mut escrow = Escrow {
+            store: Pubkey::default(),
+            balance: 0,
+            held_for_delivery: 0,
+            frozen: 0,
+            unsettled: 0,
+            hold_period: 0,
+        };
+        assert_eq!(escrow.settles_at(1_000), 1_000 + Escrow::DEFAULT_HOLD_PERIOD);
+
+        escrow.hold_period = 3_600;
+        assert_eq!(escrow.settles_at(1_000), 4_600);
+        assert_eq!(escrow.settles_at(i64::MAX), i64::MAX);
+    }
 }
@@ -0,0 +1,98 @@
+use super::store::Store;
+use anchor_lang::prelude::*;
+
+/// Stock held for a buyer until checkout or expiry
+#[account]
+pub struct Reservation {
+    pub store: Pubkey,                      // Store the items belong to
+    pub buyer: Pubkey,                      // Buyer holding the items
+    pub reservation_id: [u8; 16],           // Unique reservation identifier
+    pub product_uuids: Vec<[u8; 16]>,       // Reserved products, in cart order
+    pub variant_ids: Vec<Option<[u8; 16]>>, // Reserved variant per line, if any
+    pub quantities: Vec<u64>,               // Reserved quantity per line
+    pub created_at: i64,                    // Reservation timestamp
+    pub expires_at: i64,                    // Anyone can release the stock after this
+}
+
+impl Reservation {
+    pub const MAX_ITEMS: usize = 10;
+    pub const LEN: usize = 8
+        + 32
+        + 32
+        + 16
+        + (4 + Self::MAX_ITEMS * 16)
+        + (4 + Self::MAX_ITEMS * 17)
+        + (4 + Self::MAX_ITEMS * 8)
+        + 8
+        + 8;
+
+    pub fn is_expired(&self, current_time: i64) -> bool {
+        current_time >= self.expires_at
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(reservation_id: [u8; 16])]
+pub struct ReserveItems<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        init,
+        payer = buyer,
+        space = Reservation::LEN,
+        seeds = [
+            b"reservation",
+            store.key().as_ref(),
+            buyer.key().as_ref(),
+            reservation_id.as_ref()
+        ],
+        bump
+    )]
+    pub reservation: Account<'info, Reservation>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseReservation<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        seeds = [
+            b"reservation",
+            store.key().as_ref(),
+            buyer.key().as_ref(),
+            reservation.reservation_id.as_ref()
+        ],
+        bump,
+        has_one = store,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub reservation: Account<'info, Reservation>,
+    /// CHECK: Receives the reservation rent, matched by has_one above
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    /// The buyer, or anyone once the reservation has expired
+    pub caller: Signer<'info>,
+}
+
+#[event]
+pub struct ItemsReserved {
+    pub reservation: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub product_uuids: Vec<[u8; 16]>,
+    pub quantities: Vec<u64>,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ReservationReleased {
+    pub reservation: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub released_by: Pubkey,
+    pub expired: bool,
+    pub timestamp: i64,
+}
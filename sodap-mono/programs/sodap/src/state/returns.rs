@@ -0,0 +1,201 @@
+use super::daily::DailySales;
+use super::loyalty::{LoyaltyAccount, LoyaltyProgram, LoyaltyTransaction};
+use super::product::{Product, ProductVariant, Purchase};
+use super::stats::StoreStats;
+use super::store::Store;
+use crate::error::CustomError;
+use crate::state::Escrow;
+use crate::types::{LoyaltyTransactionType, ReturnReason, TransactionStatus};
+use anchor_lang::prelude::*;
+
+/// A buyer's pending request to return units of one receipt line
+#[account]
+pub struct ReturnRequest {
+    pub receipt: Pubkey,      // Receipt the units were bought on
+    pub store: Pubkey,        // Store that sold them
+    pub buyer: Pubkey,        // Buyer asking for the return
+    pub line: u8,             // Receipt line being returned
+    pub quantity: u64,        // Units to return
+    pub reason: ReturnReason, // Why the units are coming back
+    pub requested_at: i64,    // Request timestamp
+}
+
+impl ReturnRequest {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 1 + 8 + 1 + 8;
+}
+
+#[derive(Accounts)]
+#[instruction(line: u8)]
+pub struct RequestReturn<'info> {
+    #[account(
+        constraint = receipt.buyer == buyer.key() @ CustomError::Unauthorized
+    )]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        init,
+        payer = buyer,
+        space = ReturnRequest::LEN,
+        seeds = [b"return_request", receipt.key().as_ref(), &[line]],
+        bump
+    )]
+    pub return_request: Account<'info, ReturnRequest>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveReturn<'info> {
+    pub store: Account<'info, Store>,
+    #[account(mut, has_one = store)]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        mut,
+        seeds = [b"return_request", receipt.key().as_ref(), &[return_request.line]],
+        bump,
+        has_one = receipt,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub return_request: Account<'info, ReturnRequest>,
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(mut, seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
+    /// Today's sales bucket
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = DailySales::LEN,
+        seeds = [b"daily", store.key().as_ref(), &DailySales::current_day()?.to_le_bytes()],
+        bump
+    )]
+    pub daily_sales: Account<'info, DailySales>,
+    /// Product of the returned line, restocked on approval
+    #[account(
+        mut,
+        seeds = [b"product", store.key().as_ref(), product.uuid.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub product: Account<'info, Product>,
+    /// Variant of the returned line, if it has one
+    #[account(mut, constraint = variant.product == product.key() @ CustomError::VariantNotFound)]
+    pub variant: Option<Account<'info, ProductVariant>>,
+    /// CHECK: receives the refund and the request rent; must be the receipt's buyer
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+
+    // Loyalty accounts, needed when the buyer earned points for the receipt
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), receipt.buyer.as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub loyalty_account: Option<Account<'info, LoyaltyAccount>>,
+    #[account(
+        seeds = [b"loyalty_program", store.key().as_ref()],
+        bump
+    )]
+    pub loyalty_program: Option<Account<'info, LoyaltyProgram>>,
+    /// The record of the points originally earned for the receipt
+    #[account(
+        mut,
+        constraint = earned_transaction.receipt == Some(receipt.key()) @ CustomError::LoyaltyReceiptMismatch,
+        constraint = earned_transaction.transaction_type == LoyaltyTransactionType::Earned
+            @ CustomError::LoyaltyReceiptMismatch
+    )]
+    pub earned_transaction: Option<Account<'info, LoyaltyTransaction>>,
+    #[account(
+        init,
+        payer = authority,
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            loyalty_account.as_ref().map(|account| account.key()).unwrap_or_default().as_ref(),
+            &loyalty_account
+                .as_ref()
+                .map(|account| account.transaction_count)
+                .unwrap_or_default()
+                .to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_record: Option<Account<'info, LoyaltyTransaction>>,
+
+    /// Store owner or manager deciding the return
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectReturn<'info> {
+    pub store: Account<'info, Store>,
+    #[account(has_one = store)]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        mut,
+        seeds = [b"return_request", receipt.key().as_ref(), &[return_request.line]],
+        bump,
+        has_one = receipt,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub return_request: Account<'info, ReturnRequest>,
+    /// CHECK: receives the request rent; must be the receipt's buyer
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    /// Store owner or manager deciding the return
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct ReturnRequested {
+    pub return_request: Pubkey,
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub line: u8,
+    pub product_uuid: [u8; 16],
+    pub quantity: u64,
+    pub reason: ReturnReason,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReturnApproved {
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub line: u8,
+    pub product_uuid: [u8; 16],
+    pub quantity: u64,
+    pub refund_amount: u64,
+    pub points_reversed: u64,
+    pub status: TransactionStatus,
+    pub approved_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReturnRejected {
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub line: u8,
+    pub quantity: u64,
+    pub rejected_by: Pubkey,
+    pub timestamp: i64,
+}
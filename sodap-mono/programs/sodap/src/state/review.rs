@@ -0,0 +1,129 @@
+use super::product::{Product, Purchase};
+use crate::error::CustomError;
+use anchor_lang::prelude::*;
+
+/// A buyer's rating of a product from one line of their receipt
+#[account]
+pub struct Review {
+    pub product: Pubkey,        // Reviewed product
+    pub store: Pubkey,          // Store the product belongs to
+    pub reviewer: Pubkey,       // Buyer who wrote the review
+    pub receipt: Pubkey,        // Purchase receipt proving the buy
+    pub line: u8,               // Receipt line holding the product
+    pub rating: u8,             // 1 to 5 stars
+    pub content_uri: String,    // Off-chain review text
+    pub content_hash: [u8; 32], // sha256 of the review text
+    pub created_at: i64,        // Posting timestamp
+    pub updated_at: i64,        // Last edit timestamp
+}
+
+impl Review {
+    pub const MIN_RATING: u8 = 1;
+    pub const MAX_RATING: u8 = 5;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 1 + 1 + (4 + Self::MAX_URI_LEN) + 32 + 8 + 8;
+}
+
+/// Running rating totals of a product, so storefronts can show averages
+#[account]
+pub struct ProductRatingStats {
+    pub product: Pubkey,         // Rated product
+    pub review_count: u64,       // Reviews posted
+    pub rating_sum: u64,         // Sum of all ratings
+    pub rating_counts: [u64; 5], // Reviews per star rating, 1 to 5
+}
+
+impl ProductRatingStats {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 5 * 8;
+
+    pub fn add(&mut self, rating: u8) -> Result<()> {
+        self.review_count = self
+            .review_count
+            .checked_add(1)
+            .ok_or(CustomError::ArithmeticError)?;
+        self.rating_sum = self
+            .rating_sum
+            .checked_add(rating as u64)
+            .ok_or(CustomError::ArithmeticError)?;
+        self.rating_counts[rating as usize - 1] += 1;
+        Ok(())
+    }
+
+    /// Swap a review's old rating for its new one
+    pub fn replace(&mut self, old_rating: u8, new_rating: u8) -> Result<()> {
+        self.rating_sum = self
+            .rating_sum
+            .checked_sub(old_rating as u64)
+            .and_then(|sum| sum.checked_add(new_rating as u64))
+            .ok_or(CustomError::ArithmeticError)?;
+        self.rating_counts[old_rating as usize - 1] -= 1;
+        self.rating_counts[new_rating as usize - 1] += 1;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(line: u8)]
+pub struct PostReview<'info> {
+    #[account(
+        seeds = [b"product", product.store.as_ref(), product.uuid.as_ref()],
+        bump
+    )]
+    pub product: Account<'info, Product>,
+    #[account(
+        constraint = receipt.buyer == reviewer.key() @ CustomError::ReviewNotEligible,
+        constraint = receipt.store == product.store @ CustomError::ReviewNotEligible
+    )]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        init,
+        payer = reviewer,
+        space = Review::LEN,
+        seeds = [b"review", receipt.key().as_ref(), &[line]],
+        bump
+    )]
+    pub review: Account<'info, Review>,
+    #[account(
+        init_if_needed,
+        payer = reviewer,
+        space = ProductRatingStats::LEN,
+        seeds = [b"rating_stats", product.key().as_ref()],
+        bump
+    )]
+    pub stats: Account<'info, ProductRatingStats>,
+    #[account(mut)]
+    pub reviewer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReview<'info> {
+    #[account(
+        mut,
+        seeds = [b"review", review.receipt.as_ref(), &[review.line]],
+        bump,
+        has_one = reviewer
+    )]
+    pub review: Account<'info, Review>,
+    #[account(
+        mut,
+        seeds = [b"rating_stats", review.product.as_ref()],
+        bump
+    )]
+    pub stats: Account<'info, ProductRatingStats>,
+    pub reviewer: Signer<'info>,
+}
+
+#[event]
+pub struct ReviewPosted {
+    pub review: Pubkey,
+    pub product: Pubkey,
+    pub store: Pubkey,
+    pub reviewer: Pubkey,
+    pub rating: u8,
+    pub content_uri: String,
+    pub content_hash: [u8; 32],
+    pub review_count: u64,
+    pub rating_sum: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,219 @@
+use super::loyalty::{LoyaltyAccount, LoyaltyProgram, LoyaltyTransaction};
+use super::product::Product;
+use super::store::Store;
+use crate::error::CustomError;
+use crate::types::RewardKind;
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct Reward {
+    pub loyalty_program: Pubkey, // Loyalty program offering the reward
+    pub store: Pubkey,           // Store the reward belongs to
+    pub reward_id: [u8; 16],     // Unique reward identifier
+    pub name: String,            // Display name
+    pub kind: RewardKind,        // What the user gets
+    pub point_cost: u64,         // Points debited per claim
+    pub stock: u64,              // Claims still available
+    pub claimed: u64,            // Total claims so far
+    pub per_user_limit: u16,     // Max claims per user (0 = unlimited)
+    pub is_active: bool,         // Whether the reward can be claimed
+    pub created_at: i64,         // Creation timestamp
+}
+
+impl Reward {
+    pub const MAX_NAME_LEN: usize = 50;
+    pub const LEN: usize =
+        8 + 32 + 32 + 16 + (4 + Self::MAX_NAME_LEN) + (1 + 32) + 8 + 8 + 8 + 2 + 1 + 8;
+}
+
+#[account]
+pub struct RewardClaims {
+    pub reward: Pubkey, // Reward being claimed
+    pub user: Pubkey,   // Claiming user
+    pub claims: u16,    // Number of claims made by this user
+}
+
+impl RewardClaims {
+    pub const LEN: usize = 8 + 32 + 32 + 2;
+}
+
+#[account]
+pub struct Voucher {
+    pub store: Pubkey,                    // Store the voucher is redeemable at
+    pub owner: Pubkey,                    // User who claimed it
+    pub reward: Pubkey,                   // Reward it was issued from
+    pub value: u64,                       // Discount value in lamports
+    pub is_redeemed: bool,                // Whether it has been used
+    pub issued_at: i64,                   // Issue timestamp
+    pub redeemed_at: i64,                 // Redemption timestamp (0 = unused)
+    pub redeemed_receipt: Option<Pubkey>, // Receipt of the purchase it was used on
+}
+
+impl Voucher {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1 + 8 + 8 + (1 + 32);
+}
+
+#[derive(Accounts)]
+#[instruction(reward_id: [u8; 16])]
+pub struct CreateReward<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Reward::LEN,
+        seeds = [b"reward", loyalty_program.key().as_ref(), reward_id.as_ref()],
+        bump
+    )]
+    pub reward: Account<'info, Reward>,
+
+    #[account(
+        seeds = [b"loyalty_program", store.key().as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub loyalty_program: Account<'info, LoyaltyProgram>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        mut,
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"reward", loyalty_program.key().as_ref(), reward.reward_id.as_ref()],
+        bump,
+        has_one = loyalty_program
+    )]
+    pub reward: Account<'info, Reward>,
+
+    #[account(
+        seeds = [b"loyalty_program", store.key().as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub loyalty_program: Account<'info, LoyaltyProgram>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        constraint = store.can_manage(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"reward", loyalty_program.key().as_ref(), reward.reward_id.as_ref()],
+        bump,
+        has_one = loyalty_program
+    )]
+    pub reward: Account<'info, Reward>,
+
+    #[account(
+        seeds = [b"loyalty_program", store.key().as_ref()],
+        bump,
+        has_one = store
+    )]
+    pub loyalty_program: Account<'info, LoyaltyProgram>,
+
+    pub store: Account<'info, Store>,
+
+    #[account(
+        mut,
+        seeds = [b"loyalty_account", store.key().as_ref(), user.key().as_ref()],
+        bump,
+        has_one = user,
+        has_one = store
+    )]
+    pub loyalty_account: Account<'info, LoyaltyAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = RewardClaims::LEN,
+        seeds = [b"reward_claims", reward.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub reward_claims: Account<'info, RewardClaims>,
+
+    #[account(
+        init,
+        payer = user,
+        space = LoyaltyTransaction::LEN,
+        seeds = [
+            b"loyalty_transaction",
+            loyalty_account.key().as_ref(),
+            &loyalty_account.transaction_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_record: Account<'info, LoyaltyTransaction>,
+
+    /// Issued for voucher and shipping credit rewards
+    #[account(
+        init,
+        payer = user,
+        space = Voucher::LEN,
+        seeds = [
+            b"voucher",
+            reward.key().as_ref(),
+            user.key().as_ref(),
+            &reward_claims.claims.to_le_bytes()
+        ],
+        bump
+    )]
+    pub voucher: Option<Account<'info, Voucher>>,
+
+    /// The product given away by free product rewards
+    #[account(mut, has_one = store)]
+    pub product: Option<Account<'info, Product>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Event emitted when a reward is added to a store's catalog
+#[event]
+pub struct RewardCreated {
+    pub reward: Pubkey,
+    pub store: Pubkey,
+    pub kind: RewardKind,
+    pub point_cost: u64,
+    pub stock: u64,
+    pub created_at: i64,
+}
+
+/// Event emitted when a user claims a reward
+#[event]
+pub struct RewardClaimed {
+    pub reward: Pubkey,
+    pub user: Pubkey,
+    pub store: Pubkey,
+    pub kind: RewardKind,
+    pub points_spent: u64,
+    pub voucher: Option<Pubkey>,
+    pub remaining_stock: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a voucher is applied at checkout
+#[event]
+pub struct VoucherRedeemed {
+    pub voucher: Pubkey,
+    pub owner: Pubkey,
+    pub store: Pubkey,
+    pub receipt: Pubkey,
+    pub discount: u64,
+    pub timestamp: i64,
+}
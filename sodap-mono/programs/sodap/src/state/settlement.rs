@@ -0,0 +1,63 @@
+use super::dispute::DisputePool;
+use super::product::Purchase;
+use super::stats::StoreStats;
+use super::store::Store;
+use super::treasury::Treasury;
+use crate::error::CustomError;
+use crate::state::Escrow;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetHoldPeriod<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    pub store: Account<'info, Store>,
+    #[account(seeds = [b"dispute_pool"], bump)]
+    pub dispute_pool: Account<'info, DisputePool>,
+    #[account(
+        constraint = store_owner.key() == store.owner @ CustomError::Unauthorized
+    )]
+    pub store_owner: Signer<'info>,
+}
+
+/// Accounts to settle an order, shared by buyer confirmation and the
+/// permissionless auto-release crank
+#[derive(Accounts)]
+pub struct SettleOrder<'info> {
+    #[account(mut)]
+    pub store: Account<'info, Store>,
+    #[account(mut, has_one = store)]
+    pub receipt: Account<'info, Purchase>,
+    #[account(
+        mut,
+        seeds = [b"escrow", store.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(mut, seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
+    /// CHECK: receives the settled payment; must be the store owner
+    #[account(mut, address = store.owner @ CustomError::Unauthorized)]
+    pub store_owner: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    /// The buyer when confirming receipt, anyone when cranking
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct OrderSettled {
+    pub receipt: Pubkey,
+    pub store: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub platform_fee: u64,        // Part of amount sent to the treasury
+    pub confirmed_by_buyer: bool, // False when released after the hold period
+    pub settled_by: Pubkey,
+    pub timestamp: i64,
+}
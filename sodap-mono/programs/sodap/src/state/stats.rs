@@ -0,0 +1,132 @@
+use super::store::Store;
+use crate::error::CustomError;
+use crate::state::Escrow;
+use anchor_lang::prelude::*;
+
+/// Running sales and cash flow totals of a store
+#[account]
+pub struct StoreStats {
+    pub store: Pubkey,         // Store the totals belong to
+    pub gross_sales: u64,      // Paid by buyers at checkout
+    pub refunds: u64,          // Returned to buyers from escrow
+    pub fees_paid: u64,        // Platform fees taken from the store's payouts
+    pub net_revenue: u64,      // Paid out to the store owner after fees
+    pub escrow_held: u64,      // Sale proceeds still in escrow
+    pub bnpl_receivables: u64, // BNPL principal buyers still owe
+    pub order_count: u64,      // Orders placed
+    pub open_orders: u64,      // Orders not yet settled to the store
+    pub last_updated: i64,     // Last change timestamp
+    pub track_top_sellers: bool, // Keep per-product units in the daily sales buckets
+}
+
+impl StoreStats {
+    pub const LEN: usize = 8 + 32 + 8 * 8 + 8 + 1;
+
+    /// Start tracking a store, counting whatever its escrow already holds
+    pub fn ensure_initialized(&mut self, store: Pubkey, escrow_balance: u64) {
+        if self.store == Pubkey::default() {
+            self.store = store;
+            self.escrow_held = escrow_balance;
+        }
+    }
+
+    /// An order was paid into escrow. `open` is false for orders that are
+    /// not settled individually.
+    pub fn record_sale(&mut self, amount: u64, open: bool) -> Result<()> {
+        self.gross_sales = add(self.gross_sales, amount)?;
+        self.escrow_held = add(self.escrow_held, amount)?;
+        self.order_count = add(self.order_count, 1)?;
+        if open {
+            self.open_orders = add(self.open_orders, 1)?;
+        }
+        self.touch()
+    }
+
+    /// Escrow was refunded to a buyer
+    pub fn record_refund(&mut self, amount: u64) -> Result<()> {
+        self.refunds = add(self.refunds, amount)?;
+        self.escrow_held = self.escrow_held.saturating_sub(amount);
+        self.touch()
+    }
+
+    /// Escrow was paid out, `fee` of it to the platform
+    pub fn record_payout(&mut self, amount: u64, fee: u64) -> Result<()> {
+        self.escrow_held = self.escrow_held.saturating_sub(amount);
+        self.record_direct_payment(amount, fee)
+    }
+
+    /// The store owner was paid outside escrow, `fee` of it to the platform
+    pub fn record_direct_payment(&mut self, amount: u64, fee: u64) -> Result<()> {
+        self.fees_paid = add(self.fees_paid, fee)?;
+        self.net_revenue = add(self.net_revenue, amount.saturating_sub(fee))?;
+        self.touch()
+    }
+
+    /// An order settled or was arbitrated and no longer needs escrow
+    pub fn close_order(&mut self) -> Result<()> {
+        self.open_orders = self.open_orders.saturating_sub(1);
+        self.touch()
+    }
+
+    pub fn add_receivable(&mut self, amount: u64) -> Result<()> {
+        self.bnpl_receivables = add(self.bnpl_receivables, amount)?;
+        self.touch()
+    }
+
+    pub fn settle_receivable(&mut self, amount: u64) -> Result<()> {
+        self.bnpl_receivables = self.bnpl_receivables.saturating_sub(amount);
+        self.touch()
+    }
+
+    fn touch(&mut self) -> Result<()> {
+        self.last_updated = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+}
+
+fn add(total: u64, amount: u64) -> Result<u64> {
+    total
+        .checked_add(amount)
+        .ok_or_else(|| CustomError::ArithmeticError.into())
+}
+
+#[derive(Accounts)]
+pub struct CheckEscrowInvariant<'info> {
+    pub store: Account<'info, Store>,
+    #[account(seeds = [b"escrow", store.key().as_ref()], bump)]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(seeds = [b"store_stats", store.key().as_ref()], bump)]
+    pub store_stats: Account<'info, StoreStats>,
+}
+
+#[derive(Accounts)]
+pub struct SetTopSellerTracking<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        init_if_needed,
+        payer = store_owner,
+        space = StoreStats::LEN,
+        seeds = [b"store_stats", store.key().as_ref()],
+        bump
+    )]
+    pub store_stats: Account<'info, StoreStats>,
+    #[account(seeds = [b"escrow", store.key().as_ref()], bump)]
+    pub escrow_account: Account<'info, Escrow>,
+    #[account(
+        mut,
+        constraint = store_owner.key() == store.owner @ CustomError::Unauthorized
+    )]
+    pub store_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EscrowInvariantChecked {
+    pub store: Pubkey,
+    pub escrow_balance: u64,
+    pub escrow_held: u64, // What the store stats expect the balance to be
+    pub open_orders: u64,
+    pub open_order_total: u64, // Outstanding on the open orders passed in
+    pub order_funds: u64,      // Escrow earmarked for open orders
+    pub timestamp: i64,
+}
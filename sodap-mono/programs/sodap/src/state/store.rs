@@ -49,6 +49,18 @@ impl Store {
         4 +                     // Vec length prefix
         (33 * Self::MAX_ADMIN_ROLES); // admin_roles (Pubkey + role_type)
 
+    /// Whether a key may act for the store (owner, or an Owner/Manager admin)
+    pub fn can_manage(&self, key: &Pubkey) -> bool {
+        self.owner == *key
+            || has_role(self, key, AdminRoleType::Owner)
+            || has_role(self, key, AdminRoleType::Manager)
+    }
+
+    /// Whether `key` may record digital deliveries for the store
+    pub fn can_fulfill(&self, key: &Pubkey) -> bool {
+        self.can_manage(key) || has_role(self, key, AdminRoleType::Fulfillment)
+    }
+
     pub fn validate_admin_roles(&self) -> anchor_lang::Result<()> {
         anchor_lang::require!(self.admin_roles.len() <= Self::MAX_ADMIN_ROLES, CustomError::TooManyAdmins);
         Ok(())
@@ -0,0 +1,142 @@
+use super::admin::PlatformAdmins;
+use crate::error::CustomError;
+use crate::types::StoreFeeTier;
+use anchor_lang::prelude::*;
+
+/// Marketplace fee taken from store revenue, with optional per-store rates
+#[account]
+pub struct FeeConfig {
+    pub fee_bps: u16,                   // Default fee in basis points
+    pub store_tiers: Vec<StoreFeeTier>, // Stores charged a different rate
+}
+
+impl FeeConfig {
+    pub const MAX_FEE_BPS: u16 = 1_000;
+    pub const MAX_STORE_TIERS: usize = 20;
+    pub const LEN: usize = 8 + 2 + (4 + Self::MAX_STORE_TIERS * StoreFeeTier::LEN);
+
+    /// Fee rate charged to a store
+    pub fn fee_bps_for(&self, store: &Pubkey) -> u16 {
+        self.store_tiers
+            .iter()
+            .find(|tier| tier.store == *store)
+            .map_or(self.fee_bps, |tier| tier.fee_bps)
+    }
+
+    /// Platform cut of `amount` earned by a store
+    pub fn fee_for(&self, store: &Pubkey, amount: u64) -> u64 {
+        (amount as u128 * self.fee_bps_for(store) as u128 / 10_000) as u64
+    }
+}
+
+/// Platform treasury collecting marketplace fees
+#[account]
+pub struct Treasury {
+    pub balance: u64,         // Lamports available to withdraw
+    pub total_collected: u64, // Fees collected since creation
+    pub total_withdrawn: u64, // Fees withdrawn since creation
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+
+    pub fn collect(&mut self, fee: u64) -> Result<()> {
+        self.balance = self
+            .balance
+            .checked_add(fee)
+            .ok_or(CustomError::ArithmeticError)?;
+        self.total_collected = self
+            .total_collected
+            .checked_add(fee)
+            .ok_or(CustomError::ArithmeticError)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ConfigurePlatformFee<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = FeeConfig::LEN,
+        seeds = [b"fee_config"],
+        bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Treasury::LEN,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(seeds = [b"platform_admins"], bump)]
+    pub platform_admins: Account<'info, PlatformAdmins>,
+    #[account(
+        mut,
+        constraint = platform_admins.admins.contains(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(seeds = [b"platform_admins"], bump)]
+    pub platform_admins: Account<'info, PlatformAdmins>,
+    #[account(
+        constraint = platform_admins.admins.contains(&authority.key()) @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    /// CHECK: any account chosen by the platform admin to receive the funds
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct PlatformFeeConfigured {
+    pub fee_bps: u16,
+    pub store_tiers: Vec<StoreFeeTier>,
+    pub configured_by: Pubkey,
+}
+
+#[event]
+pub struct PlatformFeeCollected {
+    pub store: Pubkey,
+    pub amount: u64, // Gross amount the fee was taken from
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub withdrawn_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_for_uses_store_tier() {
+        let tiered_store = Pubkey::new_unique();
+        let config = FeeConfig {
+            fee_bps: 250,
+            store_tiers: vec![StoreFeeTier {
+                store: tiered_store,
+                fee_bps: 100,
+            }],
+        };
+
+        assert_eq!(config.fee_for(&Pubkey::new_unique(), 1_000_000), 25_000);
+        assert_eq!(config.fee_for(&tiered_store, 1_000_000), 10_000);
+        assert_eq!(config.fee_for(&tiered_store, 0), 0);
+    }
+}
@@ -55,15 +55,3 @@ pub struct CreateOrUpdateUserProfile<'info> {
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
-
-#[derive(Accounts)]
-pub struct ScanAndPurchase<'info> {
-    #[account(
-        mut,
-        seeds = [b"user_profile", authority.key().as_ref()],
-        bump,
-        has_one = authority
-    )]
-    pub user_profile: Account<'info, UserProfile>,
-    pub authority: Signer<'info>,
-}
@@ -1,6 +1,7 @@
 // Moved from state/types.rs
 // Please add your types here, or move the content from state/types.rs
 
+use crate::error::CustomError;
 use anchor_lang::prelude::*;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
@@ -8,6 +9,7 @@ pub enum AdminRoleType {
     Owner,
     Manager,
     Viewer,
+    Fulfillment, // Can record digital deliveries
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
@@ -22,6 +24,8 @@ pub enum TransactionStatus {
     Pending,
     Completed,
     Failed,
+    PartiallyRefunded, // Some lines were returned
+    Refunded,          // Every line was returned
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
@@ -111,6 +115,60 @@ pub enum LoyaltyTransactionType {
     Expired,  // Points expired
     Gifted,   // Points gifted to another user
     Received, // Points received from another user
+    Reversed, // Points clawed back after a refund
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum NegativeBalancePolicy {
+    ClampToZero, // Forfeit whatever can no longer be clawed back
+    CarryDebt,   // Record the shortfall and offset it against future earnings
+    Reject,      // Fail the clawback if the points were already spent
+}
+
+/// Changes to a store's loyalty program settings. `None` leaves a field unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct LoyaltyProgramChanges {
+    pub points_per_dollar: Option<u64>,
+    pub redemption_rate: Option<u64>,
+    pub welcome_bonus: Option<u64>,
+    pub referral_bonus: Option<u64>,
+    pub min_redemption: Option<u64>,
+    pub max_redemption_percent: Option<u16>,
+    pub is_active: Option<bool>,
+    pub negative_balance_policy: Option<NegativeBalancePolicy>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RewardKind {
+    FreeProduct { product: Pubkey }, // One unit of a store product
+    Voucher { value: u64 },          // Lamport discount at checkout
+    ShippingCredit { value: u64 },   // Lamport credit towards delivery, applied at checkout
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum CouponDiscount {
+    Percent { bps: u16 },  // Basis points off eligible items (1000 = 10%)
+    Fixed { amount: u64 }, // Lamports off eligible items
+}
+
+/// Discount, eligibility and caps of a new coupon
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CouponTerms {
+    pub discount: CouponDiscount,
+    pub min_cart: u64,                    // Minimum cart total before discounts
+    pub eligible_products: Vec<[u8; 16]>, // Empty = every product in the store
+    pub max_uses: u64,                    // Total redemptions (0 = no limit)
+    pub per_user_limit: u16,              // Redemptions per buyer (0 = no limit)
+    pub expires_at: i64,                  // Unix timestamp (0 = never)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AchievementCriterion {
+    Purchases { count: u64 },      // Orders placed at the store reach count
+    LoyaltyPoints { points: u64 }, // Lifetime loyalty points reach points
+    LoyaltyTier { tier: LoyaltyTier }, // Loyalty tier at or above tier
+    Referrals { count: u16 },      // Referred at least count friends
+    OnTimeBnplLoans { count: u16 }, // Repaid count BNPL loans without a late installment
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
@@ -126,3 +184,190 @@ pub struct StablePrice {
     pub last_updated: i64,      // Timestamp of last price update
     pub is_fixed: bool,         // Whether prices are fixed or use oracle
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BundleComponent {
+    pub product_uuid: [u8; 16], // Product included in the bundle
+    pub quantity: u64,          // Units of it per bundle
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct PurchaseLimits {
+    pub max_per_order: u64,              // Max units in one cart (0 = no limit)
+    pub max_per_wallet: u64,             // Max units a wallet can ever buy (0 = no limit)
+    pub sale_start: i64,                 // Product can't be bought before this (0 = anytime)
+    pub allowlist_root: Option<[u8; 32]>, // Merkle root of eligible wallets
+}
+
+impl PurchaseLimits {
+    /// Whether buyers need a purchase counter for this product
+    pub fn tracks_wallets(&self) -> bool {
+        self.max_per_wallet > 0 || self.allowlist_root.is_some()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledPrice {
+    pub start_time: i64, // Sale start (inclusive)
+    pub end_time: i64,   // Sale end (exclusive)
+    pub usdc_price: u64, // Sale price in USDC (6 decimals)
+    pub sol_price: u64,  // Sale price in SOL (9 decimals), charged at checkout
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ProductAttribute {
+    pub name: String,          // Attribute name, e.g. "size"
+    pub value: AttributeValue, // Attribute value, e.g. "M"
+}
+
+impl ProductAttribute {
+    pub const MAX_NAME_LEN: usize = 20;
+    pub const MAX_TEXT_LEN: usize = 50;
+    // name + largest value (Text: variant tag + length prefix + text)
+    pub const LEN: usize = (4 + Self::MAX_NAME_LEN) + (1 + 4 + Self::MAX_TEXT_LEN);
+
+    pub fn is_valid(&self) -> bool {
+        let value_fits = match &self.value {
+            AttributeValue::Text { value } => value.len() <= Self::MAX_TEXT_LEN,
+            AttributeValue::Integer { .. } | AttributeValue::Boolean { .. } => true,
+        };
+        !self.name.is_empty() && self.name.len() <= Self::MAX_NAME_LEN && value_fits
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum AttributeValue {
+    Text { value: String },  // e.g. a color
+    Integer { value: i64 },  // e.g. a weight in grams
+    Boolean { value: bool }, // e.g. whether it's organic
+}
+
+/// The lines and payment of a checkout
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CartOrder {
+    pub product_uuids: Vec<[u8; 16]>,
+    pub variant_ids: Vec<Option<[u8; 16]>>, // Empty when no line has a variant
+    pub quantities: Vec<u64>,
+    pub total_amount_paid: u64,
+    pub gas_fee: u64,
+    pub status: TransactionStatus,
+    pub coupon_code: Option<String>, // Promo code entered by the buyer
+}
+
+/// A product to register, on its own or as part of a batch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ProductRegistration {
+    pub product_uuid: [u8; 16],
+    pub price: u64,                  // Legacy lamport price
+    pub usdc_price: u64,             // Price in USDC (6 decimals)
+    pub sol_price: u64,              // Price in SOL (9 decimals)
+    pub stock: u64,                  // Initial stock
+    pub tokenized_type: TokenizedType,
+    pub metadata_uri: String,        // Off-chain name, description and image
+    pub is_fixed_pricing: bool,      // Whether prices are fixed or use oracle
+    pub gtin_hash: Option<[u8; 32]>, // sha256 of the barcode, indexed when set
+}
+
+/// One product change in a batch update. `None` leaves a field unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct ProductUpdate {
+    pub product_uuid: [u8; 16],
+    pub stock_adjustment: i64, // Added to stock, or taken from it when negative
+    pub new_price: Option<u64>,
+    pub new_usdc_price: Option<u64>,
+    pub new_sol_price: Option<u64>,
+    pub is_active: Option<bool>,
+}
+
+/// Changes to a single product. `None` leaves a field unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct ProductChanges {
+    pub new_price: Option<u64>,
+    pub new_usdc_price: Option<u64>,
+    pub new_sol_price: Option<u64>,
+    pub new_stock: Option<u64>,
+    pub new_metadata_uri: Option<String>,
+    pub new_tokenized_type: Option<TokenizedType>,
+    pub update_price_timestamp: Option<bool>, // Stamp the prices as updated now
+    pub new_gtin_hash: Option<[u8; 32]>,      // Moves the barcode index to the new code
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BatchItemStatus {
+    Applied,
+    AlreadyExists,
+    NotFound,
+    InvalidInput,
+    InsufficientStock,
+    BarcodeInUse,
+}
+
+/// Outcome of one item of a batch instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BatchItemResult {
+    pub product_uuid: [u8; 16],
+    pub status: BatchItemStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DeliveryKind {
+    EncryptedKey,  // Content key encrypted to the buyer's public key
+    DownloadToken, // Download token signed by the store
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ReturnReason {
+    Defective,
+    WrongItem,
+    NotAsDescribed,
+    ChangedMind,
+    Other,
+}
+
+/// Fee rate charged to one store instead of the platform default
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StoreFeeTier {
+    pub store: Pubkey,
+    pub fee_bps: u16,
+}
+
+impl StoreFeeTier {
+    pub const LEN: usize = 32 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DisputeStatus {
+    Open,
+    Resolved,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct CurrencyAmounts {
+    pub sol: u64,  // Lamports
+    pub usdc: u64, // USDC base units (6 decimals)
+}
+
+impl CurrencyAmounts {
+    pub const LEN: usize = 8 + 8;
+
+    pub fn add(&mut self, currency: Currency, amount: u64) -> Result<()> {
+        let total = match currency {
+            Currency::SOL => &mut self.sol,
+            Currency::USDC => &mut self.usdc,
+        };
+        *total = total
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticError)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ProductUnits {
+    pub product_uuid: [u8; 16], // Product sold
+    pub units: u64,             // Units sold of it
+}
+
+impl ProductUnits {
+    pub const LEN: usize = 16 + 8;
+}
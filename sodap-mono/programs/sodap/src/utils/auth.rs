@@ -1,5 +1,6 @@
 // Helper functions for authentication and authorization
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 
 pub fn check_root_password(
     username: &str,
@@ -15,3 +16,53 @@ pub fn is_super_root_admin(key: &Pubkey, super_admin_pubkey: &Pubkey) -> bool {
     // Check if the key matches the super admin key
     key == super_admin_pubkey
 }
+
+/// Verify that `leaf` is part of the Merkle tree with `root`.
+/// Pairs are hashed in sorted order, so proofs don't carry left/right flags.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed = leaf;
+    for node in proof {
+        computed = if computed <= *node {
+            hashv(&[&computed, node]).to_bytes()
+        } else {
+            hashv(&[node, &computed]).to_bytes()
+        };
+    }
+    computed == *root
+}
+
+/// Allowlist leaf for a wallet
+pub fn allowlist_leaf(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[wallet.as_ref()]).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        let wallets = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves: Vec<[u8; 32]> = wallets.iter().map(allowlist_leaf).collect();
+
+        // Three leaves: hash the first pair, carry the third up unpaired
+        let pair = |a: [u8; 32], b: [u8; 32]| {
+            if a <= b {
+                hashv(&[&a, &b]).to_bytes()
+            } else {
+                hashv(&[&b, &a]).to_bytes()
+            }
+        };
+        let left = pair(leaves[0], leaves[1]);
+        let root = pair(left, leaves[2]);
+
+        assert!(verify_merkle_proof(&[leaves[1], leaves[2]], &root, leaves[0]));
+        assert!(verify_merkle_proof(&[leaves[0], leaves[2]], &root, leaves[1]));
+        assert!(verify_merkle_proof(&[left], &root, leaves[2]));
+
+        // Wrong wallet or truncated proof
+        let outsider = allowlist_leaf(&Pubkey::new_unique());
+        assert!(!verify_merkle_proof(&[leaves[1], leaves[2]], &root, outsider));
+        assert!(!verify_merkle_proof(&[leaves[1]], &root, leaves[0]));
+    }
+}
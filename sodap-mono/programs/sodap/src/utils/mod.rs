@@ -1,3 +1,4 @@
 pub mod pda;
 pub mod auth;
 pub mod pricing;
+pub mod token;
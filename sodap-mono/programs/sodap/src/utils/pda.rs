@@ -1,6 +1,7 @@
 use crate::error::CustomError;
 use crate::state::store::Store;
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 
 pub fn find_store_pda(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"store", authority.as_ref()], program_id)
@@ -18,3 +19,104 @@ pub fn verify_store_authority(store: &Account<Store>, authority: &Signer) -> Res
     );
     Ok(())
 }
+
+pub fn find_product_pda(
+    program_id: &Pubkey,
+    store: &Pubkey,
+    product_uuid: &[u8; 16],
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"product", store.as_ref(), product_uuid.as_ref()],
+        program_id,
+    )
+}
+
+pub fn find_barcode_pda(program_id: &Pubkey, store: &Pubkey, gtin_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"barcode", store.as_ref(), gtin_hash.as_ref()],
+        program_id,
+    )
+}
+
+/// Create a PDA account of `space` bytes owned by this program, paid by `payer`
+pub fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    create_signed_account(
+        account,
+        payer,
+        system_program_info,
+        signer_seeds,
+        Rent::get()?.minimum_balance(space),
+        space,
+        &crate::ID,
+    )
+}
+
+/// Create a PDA account of `space` bytes holding at least `lamports`, owned by `owner`.
+/// Like Anchor's `init`, an address that was already sent lamports is topped up,
+/// allocated and assigned instead, so a stray transfer can't block it.
+pub fn create_signed_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    lamports: u64,
+    space: usize,
+    owner: &Pubkey,
+) -> Result<()> {
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program_info.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            lamports,
+            space as u64,
+            owner,
+        );
+    }
+
+    let shortfall = lamports.saturating_sub(current_lamports);
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program_info.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program_info.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program_info.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        owner,
+    )
+}
@@ -107,6 +107,30 @@ impl PriceConverter {
         
         Ok(raw_price)
     }
+
+    /// Split an amount across lines in proportion to their weights.
+    /// Rounding leftovers go to the last line so the shares add up to `total`.
+    pub fn split_by_weight(total: u64, weights: &[u64]) -> Result<Vec<u64>> {
+        let weight_sum = weights
+            .iter()
+            .try_fold(0u128, |sum, weight| sum.checked_add(*weight as u128))
+            .ok_or(CustomError::ArithmeticError)?;
+
+        let mut shares = Vec::with_capacity(weights.len());
+        let mut allocated = 0u64;
+        for weight in weights {
+            let share = (total as u128 * *weight as u128)
+                .checked_div(weight_sum)
+                .unwrap_or(0) as u64;
+            allocated += share;
+            shares.push(share);
+        }
+        if let Some(last) = shares.last_mut() {
+            *last += total - allocated;
+        }
+
+        Ok(shares)
+    }
 }
 
 #[cfg(test)]
@@ -148,4 +172,18 @@ mod tests {
             "$50.00"
         );
     }
+
+    #[test]
+    fn test_split_by_weight() {
+        let shares = PriceConverter::split_by_weight(1_000, &[300, 300, 300]).unwrap();
+        assert_eq!(shares, vec![333, 333, 334]);
+        assert_eq!(shares.iter().sum::<u64>(), 1_000);
+
+        // A discounted cart spreads the discount over every line
+        let shares = PriceConverter::split_by_weight(900, &[600, 400]).unwrap();
+        assert_eq!(shares, vec![540, 360]);
+
+        let shares = PriceConverter::split_by_weight(0, &[0, 0]).unwrap();
+        assert_eq!(shares, vec![0, 0]);
+    }
 }
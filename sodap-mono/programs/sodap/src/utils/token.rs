@@ -0,0 +1,135 @@
+use crate::utils::pda::create_signed_account;
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+use anchor_spl::token_2022::{self, InitializeMint2};
+use anchor_spl::token_2022_extensions::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_2022_extensions::spl_token_metadata_interface::state::TokenMetadata;
+use anchor_spl::token_interface::{
+    metadata_pointer_initialize, non_transferable_mint_initialize, token_metadata_initialize,
+    MetadataPointerInitialize, NonTransferableMintInitialize, TokenMetadataInitialize,
+};
+
+pub const PRODUCT_TOKEN_NAME: &str = "SoDap Product";
+pub const PRODUCT_TOKEN_SYMBOL: &str = "SODAP";
+pub const BADGE_TOKEN_SYMBOL: &str = "BADGE";
+
+/// Accounts needed to create a token mint
+pub struct TokenMintAccounts<'a, 'info> {
+    pub payer: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+/// Create a zero-decimal Token-2022 mint whose metadata extension points at `uri`.
+/// The mint and its metadata are both controlled by `authority`.
+pub fn create_product_mint(
+    accounts: &TokenMintAccounts,
+    mint_seeds: &[&[u8]],
+    authority_seeds: &[&[u8]],
+    uri: String,
+) -> Result<()> {
+    create_metadata_mint(
+        accounts,
+        mint_seeds,
+        authority_seeds,
+        PRODUCT_TOKEN_NAME.to_string(),
+        PRODUCT_TOKEN_SYMBOL.to_string(),
+        uri,
+        false,
+    )
+}
+
+/// Create a zero-decimal Token-2022 mint carrying its own name, symbol and `uri`
+/// metadata, optionally non-transferable. The mint and its metadata are both
+/// controlled by `authority`.
+pub fn create_metadata_mint(
+    accounts: &TokenMintAccounts,
+    mint_seeds: &[&[u8]],
+    authority_seeds: &[&[u8]],
+    name: String,
+    symbol: String,
+    uri: String,
+    non_transferable: bool,
+) -> Result<()> {
+    let metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey::try_from(Some(accounts.authority.key()))?,
+        mint: accounts.mint.key(),
+        name,
+        symbol,
+        uri,
+        additional_metadata: vec![],
+    };
+
+    // The metadata extension is reallocated into the mint, so fund it up front
+    let mut extensions = vec![ExtensionType::MetadataPointer];
+    if non_transferable {
+        extensions.push(ExtensionType::NonTransferable);
+    }
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&extensions)?;
+    let lamports = Rent::get()?.minimum_balance(space + metadata.tlv_size_of()?);
+    create_signed_account(
+        accounts.mint,
+        accounts.payer,
+        accounts.system_program,
+        mint_seeds,
+        lamports,
+        space,
+        accounts.token_program.key,
+    )?;
+
+    if non_transferable {
+        non_transferable_mint_initialize(CpiContext::new(
+            accounts.token_program.clone(),
+            NonTransferableMintInitialize {
+                token_program_id: accounts.token_program.clone(),
+                mint: accounts.mint.clone(),
+            },
+        ))?;
+    }
+
+    metadata_pointer_initialize(
+        CpiContext::new(
+            accounts.token_program.clone(),
+            MetadataPointerInitialize {
+                token_program_id: accounts.token_program.clone(),
+                mint: accounts.mint.clone(),
+            },
+        ),
+        Some(accounts.authority.key()),
+        Some(accounts.mint.key()),
+    )?;
+
+    token_2022::initialize_mint2(
+        CpiContext::new(
+            accounts.token_program.clone(),
+            InitializeMint2 {
+                mint: accounts.mint.clone(),
+            },
+        ),
+        0,
+        accounts.authority.key,
+        None,
+    )?;
+
+    token_metadata_initialize(
+        CpiContext::new_with_signer(
+            accounts.token_program.clone(),
+            TokenMetadataInitialize {
+                program_id: accounts.token_program.clone(),
+                metadata: accounts.mint.clone(),
+                update_authority: accounts.authority.clone(),
+                mint_authority: accounts.authority.clone(),
+                mint: accounts.mint.clone(),
+            },
+            &[authority_seeds],
+        ),
+        metadata.name,
+        metadata.symbol,
+        metadata.uri,
+    )?;
+
+    Ok(())
+}
@@ -25,6 +25,7 @@ describe("BNPL and Loyalty Integration Tests", () => {
   let creditScore: PublicKey;
   let loyaltyProgram: PublicKey;
   let loyaltyAccount: PublicKey;
+  let purchaseReceipt: PublicKey;
 
   before(async () => {
     // Create test accounts
@@ -35,6 +36,13 @@ describe("BNPL and Loyalty Integration Tests", () => {
     await fundTestAccount(provider, storeOwner.publicKey, 5);
     await fundTestAccount(provider, customer.publicKey, 10);
 
+    // Create the platform fee config and treasury checkout pays into
+    await program.methods
+      .initialize()
+      .accounts({ payer: storeOwner.publicKey })
+      .signers([storeOwner])
+      .rpc();
+
     // Derive PDAs
     [store] = PublicKey.findProgramAddressSync(
       [Buffer.from("store"), storeOwner.publicKey.toBuffer()],
@@ -167,6 +175,15 @@ describe("BNPL and Loyalty Integration Tests", () => {
           )
           .accounts({
             loyaltyAccount: loyaltyAccount,
+            referralCodeRegistry: PublicKey.findProgramAddressSync(
+              [
+                Buffer.from("referral_code"),
+                store.toBuffer(),
+                customer.publicKey.toBuffer().subarray(0, 8),
+              ],
+              program.programId
+            )[0],
+            referrerCodeRegistry: null,
             loyaltyProgram: loyaltyProgram,
             user: customer.publicKey,
             store: store,
@@ -193,41 +210,157 @@ describe("BNPL and Loyalty Integration Tests", () => {
   });
 
   describe("Purchase with Loyalty Points Redemption", () => {
-    it("Should make a purchase with loyalty points discount", async () => {
+    it("Should make a purchase with a voucher bought with loyalty points", async () => {
       try {
-        const receipt = Keypair.generate();
-        const productIds = [Keypair.generate().publicKey];
-        const quantities = [new anchor.BN(1)];
-        const totalAmount = new anchor.BN(2 * LAMPORTS_PER_SOL); // 2 SOL
-        const loyaltyPointsToUse = new anchor.BN(100); // Use 100 points = 1 SOL discount
+        const productUuid = Array.from(crypto.getRandomValues(new Uint8Array(16)));
+        const price = new anchor.BN(2 * LAMPORTS_PER_SOL); // 2 SOL
+        const [product] = PublicKey.findProgramAddressSync(
+          [Buffer.from("product"), store.toBuffer(), Buffer.from(productUuid)],
+          program.programId
+        );
+        await program.methods
+          .registerProduct({
+            productUuid,
+            price,
+            usdcPrice: new anchor.BN(0),
+            solPrice: price,
+            stock: new anchor.BN(10),
+            tokenizedType: { none: {} },
+            metadataUri: "",
+            isFixedPricing: true,
+            gtinHash: null,
+          })
+          .accounts({
+            store: store,
+            product: product,
+            barcodeIndex: null,
+            authority: storeOwner.publicKey,
+          })
+          .signers([storeOwner])
+          .rpc();
 
+        // Points are spent on a 1 SOL voucher reward, which checkout applies
+        const rewardId = Array.from(crypto.getRandomValues(new Uint8Array(16)));
+        const [reward] = PublicKey.findProgramAddressSync(
+          [Buffer.from("reward"), loyaltyProgram.toBuffer(), Buffer.from(rewardId)],
+          program.programId
+        );
         await program.methods
-          .purchaseCart(
-            productIds,
-            quantities,
-            totalAmount,
-            { fullPayment: {} }, // PaymentMethod::FullPayment
-            null, // No BNPL term
-            loyaltyPointsToUse
+          .createReward(
+            rewardId,
+            "1 SOL off",
+            { voucher: { value: new anchor.BN(1 * LAMPORTS_PER_SOL) } },
+            new anchor.BN(100),
+            new anchor.BN(10),
+            0
           )
           .accounts({
+            reward: reward,
+            loyaltyProgram: loyaltyProgram,
+            store: store,
+            authority: storeOwner.publicKey,
+          })
+          .signers([storeOwner])
+          .rpc();
+
+        const loyaltyBefore = await program.account.loyaltyAccount.fetch(
+          loyaltyAccount
+        );
+        const [transactionRecord] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("loyalty_transaction"),
+            loyaltyAccount.toBuffer(),
+            loyaltyBefore.transactionCount.toArrayLike(Buffer, "le", 8),
+          ],
+          program.programId
+        );
+        const [voucher] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("voucher"),
+            reward.toBuffer(),
+            customer.publicKey.toBuffer(),
+            new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+          ],
+          program.programId
+        );
+        await program.methods
+          .claimReward()
+          .accounts({
+            reward: reward,
+            loyaltyProgram: loyaltyProgram,
             store: store,
-            receipt: receipt.publicKey,
+            loyaltyAccount: loyaltyAccount,
+            transactionRecord: transactionRecord,
+            voucher: voucher,
+            product: null,
+            user: customer.publicKey,
+          })
+          .signers([customer])
+          .rpc();
+
+        const orderId = new anchor.BN(1);
+        [purchaseReceipt] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("purchase"),
+            store.toBuffer(),
+            customer.publicKey.toBuffer(),
+            orderId.toArrayLike(Buffer, "le", 8),
+          ],
+          program.programId
+        );
+        const [dailySales] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("daily"),
+            store.toBuffer(),
+            new anchor.BN(Math.floor(Date.now() / 1000 / 86400)).toArrayLike(
+              Buffer,
+              "le",
+              8
+            ),
+          ],
+          program.programId
+        );
+        const escrowBefore = await program.account.escrow.fetch(escrowAccount);
+
+        await program.methods
+          .purchaseCart(orderId, {
+            productUuids: [productUuid],
+            variantIds: [],
+            quantities: [new anchor.BN(1)],
+            totalAmountPaid: new anchor.BN(1 * LAMPORTS_PER_SOL),
+            gasFee: new anchor.BN(0),
+            status: { completed: {} },
+            couponCode: null,
+          })
+          .accounts({
+            store: store,
+            receipt: purchaseReceipt,
             buyer: customer.publicKey,
             storeOwner: storeOwner.publicKey,
             escrowAccount: escrowAccount,
-            systemProgram: SystemProgram.programId,
+            dailySales: dailySales,
+            voucher: voucher,
+            coupon: null,
+            couponUsage: null,
+            reservation: null,
+            userProfile: null,
+            loyaltyAccount: loyaltyAccount,
+            tokenProgram: null,
+            associatedTokenProgram: null,
           })
-          .signers([customer, receipt])
+          .remainingAccounts([
+            { pubkey: product, isWritable: true, isSigner: false },
+          ])
+          .signers([customer])
           .rpc();
 
-        // Check that escrow received the discounted amount (2 SOL - 1 SOL discount)
+        // Check that escrow received the discounted amount (2 SOL - 1 SOL voucher)
         const escrowAccountData = await program.account.escrow.fetch(
           escrowAccount
         );
-        expect(escrowAccountData.balance.toNumber()).to.equal(
-          1 * LAMPORTS_PER_SOL
-        );
+        expect(
+          escrowAccountData.balance.sub(escrowBefore.balance).toNumber()
+        ).to.equal(1 * LAMPORTS_PER_SOL);
 
         // Check that loyalty points were deducted
         const loyaltyAccountData = await program.account.loyaltyAccount.fetch(
@@ -242,6 +375,7 @@ describe("BNPL and Loyalty Integration Tests", () => {
       }
     });
   });
+  });
 
   describe("BNPL Loan Creation and Management", () => {
     it("Should create a BNPL loan", async () => {
@@ -349,16 +483,15 @@ describe("BNPL and Loyalty Integration Tests", () => {
   describe("Loyalty Points Earning", () => {
     it("Should earn loyalty points from purchase", async () => {
       try {
-        const purchaseAmount = new anchor.BN(3 * LAMPORTS_PER_SOL); // 3 SOL purchase
+        // Points are earned on what the buyer actually paid (1 SOL after the voucher)
+        const purchaseAmount = new anchor.BN(1 * LAMPORTS_PER_SOL);
 
         await program.methods
-          .earnLoyaltyPoints(
-            purchaseAmount,
-            { purchase: {} } // LoyaltyPointType::Purchase
-          )
+          .earnLoyaltyPoints(purchaseAmount)
           .accounts({
             loyaltyAccount: loyaltyAccount,
             loyaltyProgram: loyaltyProgram,
+            purchaseReceipt: purchaseReceipt,
             user: customer.publicKey,
             store: store,
             systemProgram: SystemProgram.programId,
@@ -369,10 +502,10 @@ describe("BNPL and Loyalty Integration Tests", () => {
         const loyaltyAccountData = await program.account.loyaltyAccount.fetch(
           loyaltyAccount
         );
-        // Should have earned 300 points (3 SOL * 100 points per SOL)
-        // Plus the previous 400 points remaining = 700 total
-        expect(loyaltyAccountData.availablePoints.toNumber()).to.equal(700);
-        expect(loyaltyAccountData.totalPoints.toNumber()).to.equal(800); // 500 welcome + 300 earned
+        // Should have earned 100 points (1 SOL * 100 points per SOL)
+        // Plus the previous 400 points remaining = 500 total
+        expect(loyaltyAccountData.availablePoints.toNumber()).to.equal(500);
+        expect(loyaltyAccountData.totalPoints.toNumber()).to.equal(600); // 500 welcome + 100 earned
 
         console.log("✅ Loyalty points earned successfully");
       } catch (error) {
@@ -383,27 +516,32 @@ describe("BNPL and Loyalty Integration Tests", () => {
   });
 
   describe("Escrow Operations", () => {
-    it("Should release escrow funds to store owner", async () => {
+    it("Should pay the store owner when the buyer confirms receipt", async () => {
       try {
         const escrowAccountBefore = await program.account.escrow.fetch(
           escrowAccount
         );
-        const initialBalance = escrowAccountBefore.balance.toNumber();
-        const releaseAmount = new anchor.BN(Math.floor(initialBalance / 2)); // Release half
+        const receiptData = await program.account.purchase.fetch(
+          purchaseReceipt
+        );
+        const releaseAmount = receiptData.totalPaid.sub(
+          receiptData.refundedAmount
+        );
 
         const storeOwnerBalanceBefore = await provider.connection.getBalance(
           storeOwner.publicKey
         );
 
         await program.methods
-          .releaseEscrow(releaseAmount)
+          .confirmReceipt()
           .accounts({
             store: store,
-            storeOwner: storeOwner.publicKey,
+            receipt: purchaseReceipt,
             escrowAccount: escrowAccount,
-            systemProgram: SystemProgram.programId,
+            storeOwner: storeOwner.publicKey,
+            caller: customer.publicKey,
           })
-          .signers([storeOwner])
+          .signers([customer])
           .rpc();
 
         const escrowAccountAfter = await program.account.escrow.fetch(
@@ -414,7 +552,7 @@ describe("BNPL and Loyalty Integration Tests", () => {
         );
 
         expect(escrowAccountAfter.balance.toNumber()).to.equal(
-          initialBalance - releaseAmount.toNumber()
+          escrowAccountBefore.balance.sub(releaseAmount).toNumber()
         );
         expect(storeOwnerBalanceAfter).to.be.greaterThan(
           storeOwnerBalanceBefore
@@ -514,6 +652,15 @@ describe("BNPL and Loyalty Integration Tests", () => {
           .initializeLoyaltyAccount(null, null)
           .accounts({
             loyaltyAccount: newLoyaltyAccount,
+            referralCodeRegistry: PublicKey.findProgramAddressSync(
+              [
+                Buffer.from("referral_code"),
+                store.toBuffer(),
+                newCustomer.publicKey.toBuffer().subarray(0, 8),
+              ],
+              program.programId
+            )[0],
+            referrerCodeRegistry: null,
             loyaltyProgram: loyaltyProgram,
             user: newCustomer.publicKey,
             store: store,
@@ -30,6 +30,57 @@ describe("sodap payment", () => {
   const productName = "Test Product";
   const productDescription = "Test Product Description";
   const productImageUri = "https://example.com/product/image.png";
+  const productUuid = Array.from(Keypair.generate().publicKey.toBytes().slice(0, 16));
+
+  // Receipts are PDAs keyed by the buyer's order id
+  let nextOrderId = Date.now();
+  const findReceiptPda = (orderId: anchor.BN): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("purchase"),
+        storePda.toBuffer(),
+        buyer.publicKey.toBuffer(),
+        orderId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  // Check out one unit of the test product at full price
+  const purchaseProduct = async (orderId: anchor.BN, receipt: PublicKey) => {
+    const day = new anchor.BN(Math.floor(Date.now() / 1000 / 86400));
+    const [dailySales] = PublicKey.findProgramAddressSync(
+      [Buffer.from("daily"), storePda.toBuffer(), day.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .purchaseCart(orderId, {
+        productUuids: [productUuid],
+        variantIds: [],
+        quantities: [new anchor.BN(1)],
+        totalAmountPaid: new anchor.BN(productPrice),
+        gasFee: new anchor.BN(0),
+        status: { completed: {} },
+        couponCode: null,
+      })
+      .accounts({
+        store: storePda,
+        receipt: receipt,
+        buyer: buyer.publicKey,
+        storeOwner: storeOwner.publicKey,
+        escrowAccount: escrowPda,
+        dailySales: dailySales,
+        voucher: null,
+        coupon: null,
+        couponUsage: null,
+        reservation: null,
+        userProfile: null,
+        loyaltyAccount: null,
+        tokenProgram: null,
+        associatedTokenProgram: null,
+      })
+      .remainingAccounts([{ pubkey: productPda, isWritable: true, isSigner: false }])
+      .rpc();
+  };
   
   before(async () => {
     // Check if we have enough SOL in the wallet
@@ -59,6 +110,12 @@ describe("sodap payment", () => {
     console.log("Store owner:", storeOwner.publicKey.toBase58());
     console.log("Escrow PDA:", escrowPda.toBase58());
     
+    // Create the platform fee config and treasury checkout pays into
+    await program.methods
+      .initialize()
+      .accounts({ payer: storeOwner.publicKey })
+      .rpc();
+
     // Register store
     try {
       console.log("Registering store with owner:", storeOwner.publicKey.toBase58());
@@ -90,9 +147,11 @@ describe("sodap payment", () => {
       throw error;
     }
     
-    // Create a new product keypair
-    const productKeypair = Keypair.generate();
-    productPda = productKeypair.publicKey;
+    // Derive the product PDA from its UUID
+    [productPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("product"), storePda.toBuffer(), Buffer.from(productUuid)],
+      program.programId
+    );
     console.log("Product ID:", productPda.toBase58());
     
     // Register product
@@ -101,21 +160,22 @@ describe("sodap payment", () => {
       console.log("Store PDA:", storePda.toBase58());
       
       await program.methods
-        .registerProduct(
-          productPda,
-          storePda,
-          productName,
-          productDescription,
-          productImageUri,
-          new anchor.BN(productPrice),
-          new anchor.BN(10), // stock
-          [] // attributes
-        )
+        .registerProduct({
+          productUuid,
+          price: new anchor.BN(productPrice),
+          usdcPrice: new anchor.BN(0),
+          solPrice: new anchor.BN(productPrice),
+          stock: new anchor.BN(10),
+          tokenizedType: { none: {} },
+          metadataUri: productImageUri,
+          isFixedPricing: true,
+          gtinHash: null,
+        })
         .accounts({
           product: productPda,
           store: storePda,
+          barcodeIndex: null,
           authority: storeOwner.publicKey,
-          payer: storeOwner.publicKey,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -129,8 +189,9 @@ describe("sodap payment", () => {
   
   it("purchases a product", async () => {
     // Create a new receipt keypair for this test
-    const receiptKeypair = Keypair.generate();
-    console.log("Purchase Receipt ID:", receiptKeypair.publicKey.toBase58());
+    const orderId = new anchor.BN(nextOrderId++);
+    const receipt = findReceiptPda(orderId);
+    console.log("Purchase Receipt ID:", receipt.toBase58());
     
     // Get balances before purchase
     const buyerBalanceBefore = await provider.connection.getBalance(buyer.publicKey);
@@ -142,7 +203,7 @@ describe("sodap payment", () => {
     let purchaseSuccessful = false;
     try {
       console.log("Purchasing product with ID:", productPda.toBase58());
-      console.log("Receipt ID:", receiptKeypair.publicKey.toBase58());
+      console.log("Receipt ID:", receipt.toBase58());
       
       // Check if buyer has enough SOL for the transaction
       if (buyerBalanceBefore < productPrice * 2) { // Need extra for rent and fees
@@ -151,25 +212,7 @@ describe("sodap payment", () => {
         console.log("Required balance:", (productPrice * 2) / LAMPORTS_PER_SOL, "SOL");
       }
       
-      await program.methods
-        .purchaseCart(
-          [productPda],
-          [new anchor.BN(1)],
-          new anchor.BN(productPrice),
-          { fullPayment: {} }, // PaymentMethod::FullPayment
-          null, // No BNPL term
-          null  // No loyalty points
-        )
-        .accounts({
-          store: storePda,
-          receipt: receiptKeypair.publicKey,
-          buyer: buyer.publicKey,
-          storeOwner: storeOwner.publicKey,
-          escrowAccount: escrowPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([receiptKeypair])
-        .rpc();
+      await purchaseProduct(orderId, receipt);
       
       console.log("Product purchased successfully");
       purchaseSuccessful = true;
@@ -190,16 +233,16 @@ describe("sodap payment", () => {
         // Try to fetch the receipt data
         let receiptAccount;
         try {
-          receiptAccount = await program.account.purchase.fetch(receiptKeypair.publicKey);
+          receiptAccount = await program.account.purchase.fetch(receipt);
           console.log("Found purchase account:", receiptAccount);
         } catch (e) {
           console.log("Could not fetch receipt account as 'purchase'");
         }
         
         if (receiptAccount) {
-          // Check if we have productIds
-          if (receiptAccount.productIds) {
-            assert.equal(receiptAccount.productIds.length, 1, "Receipt should have 1 product");
+          // Check if we have productUuids
+          if (receiptAccount.productUuids) {
+            assert.equal(receiptAccount.productUuids.length, 1, "Receipt should have 1 product");
             assert.equal(receiptAccount.quantities.length, 1, "Receipt should have 1 quantity");
             assert.ok(receiptAccount.store.equals(storePda), "Receipt store should match store PDA");
             assert.ok(receiptAccount.buyer.equals(buyer.publicKey), "Receipt buyer should match buyer public key");
@@ -240,11 +283,12 @@ describe("sodap payment", () => {
     console.log("Escrow balance before release:", escrowBalanceBefore / LAMPORTS_PER_SOL, "SOL");
     
     // First, make another purchase to have funds in escrow
-    const receiptKeypair = Keypair.generate();
+    const orderId = new anchor.BN(nextOrderId++);
+    const receipt = findReceiptPda(orderId);
     let purchaseSuccessful = false;
     try {
       console.log("Purchasing product with ID:", productPda.toBase58());
-      console.log("Receipt ID:", receiptKeypair.publicKey.toBase58());
+      console.log("Receipt ID:", receipt.toBase58());
       
       // Check if buyer has enough SOL for the transaction
       const buyerBalance = await provider.connection.getBalance(buyer.publicKey);
@@ -254,25 +298,7 @@ describe("sodap payment", () => {
         console.log("Required balance:", (productPrice * 2) / LAMPORTS_PER_SOL, "SOL");
       }
       
-      await program.methods
-        .purchaseCart(
-          [productPda],
-          [new anchor.BN(1)],
-          new anchor.BN(productPrice),
-          { fullPayment: {} }, // PaymentMethod::FullPayment
-          null, // No BNPL term
-          null  // No loyalty points
-        )
-        .accounts({
-          store: storePda,
-          receipt: receiptKeypair.publicKey,
-          buyer: buyer.publicKey,
-          storeOwner: storeOwner.publicKey,
-          escrowAccount: escrowPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([receiptKeypair])
-        .rpc();
+      await purchaseProduct(orderId, receipt);
       
       console.log("Made purchase for release test");
       purchaseSuccessful = true;
@@ -304,14 +330,15 @@ describe("sodap payment", () => {
   
   it("handles refunds from escrow", async () => {
     // Create a new receipt keypair for this test
-    const receiptKeypair = Keypair.generate();
-    console.log("Refund Test Receipt ID:", receiptKeypair.publicKey.toBase58());
+    const orderId = new anchor.BN(nextOrderId++);
+    const receipt = findReceiptPda(orderId);
+    console.log("Refund Test Receipt ID:", receipt.toBase58());
     
     // First, make another purchase to have funds in escrow
     let purchaseSuccessful = false;
     try {
       console.log("Purchasing product with ID:", productPda.toBase58());
-      console.log("Receipt ID:", receiptKeypair.publicKey.toBase58());
+      console.log("Receipt ID:", receipt.toBase58());
       
       // Check if buyer has enough SOL for the transaction
       const buyerBalance = await provider.connection.getBalance(buyer.publicKey);
@@ -321,25 +348,7 @@ describe("sodap payment", () => {
         console.log("Required balance:", (productPrice * 2) / LAMPORTS_PER_SOL, "SOL");
       }
       
-      await program.methods
-        .purchaseCart(
-          [productPda],
-          [new anchor.BN(1)],
-          new anchor.BN(productPrice),
-          { fullPayment: {} }, // PaymentMethod::FullPayment
-          null, // No BNPL term
-          null  // No loyalty points
-        )
-        .accounts({
-          store: storePda,
-          receipt: receiptKeypair.publicKey,
-          buyer: buyer.publicKey,
-          storeOwner: storeOwner.publicKey,
-          escrowAccount: escrowPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([receiptKeypair])
-        .rpc();
+      await purchaseProduct(orderId, receipt);
       
       console.log("Product purchased successfully");
       purchaseSuccessful = true;
@@ -382,14 +391,15 @@ describe("sodap payment", () => {
   
   it("prevents unauthorized escrow operations", async () => {
     // Create a new receipt keypair for this test
-    const receiptKeypair = Keypair.generate();
-    console.log("Unauthorized Test Receipt ID:", receiptKeypair.publicKey.toBase58());
+    const orderId = new anchor.BN(nextOrderId++);
+    const receipt = findReceiptPda(orderId);
+    console.log("Unauthorized Test Receipt ID:", receipt.toBase58());
     
     // First, make another purchase to have funds in escrow
     let purchaseSuccessful = false;
     try {
       console.log("Purchasing product with ID:", productPda.toBase58());
-      console.log("Receipt ID:", receiptKeypair.publicKey.toBase58());
+      console.log("Receipt ID:", receipt.toBase58());
       
       // Check if buyer has enough SOL for the transaction
       const buyerBalance = await provider.connection.getBalance(buyer.publicKey);
@@ -399,25 +409,7 @@ describe("sodap payment", () => {
         console.log("Required balance:", (productPrice * 2) / LAMPORTS_PER_SOL, "SOL");
       }
       
-      await program.methods
-        .purchaseCart(
-          [productPda],
-          [new anchor.BN(1)],
-          new anchor.BN(productPrice),
-          { fullPayment: {} }, // PaymentMethod::FullPayment
-          null, // No BNPL term
-          null  // No loyalty points
-        )
-        .accounts({
-          store: storePda,
-          receipt: receiptKeypair.publicKey,
-          buyer: buyer.publicKey,
-          storeOwner: storeOwner.publicKey,
-          escrowAccount: escrowPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([receiptKeypair])
-        .rpc();
+      await purchaseProduct(orderId, receipt);
       
       console.log("Product purchased successfully");
       purchaseSuccessful = true;
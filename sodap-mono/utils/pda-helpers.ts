@@ -40,14 +40,35 @@ export function findEscrowPDA(storePubkey: PublicKey): PublicKey {
 }
 
 /**
- * Find the Receipt PDA for a purchase
+ * Find the Receipt PDA for a purchase. Receipts are keyed by the buyer's order id.
  */
 export function findReceiptPDA(
   storePubkey: PublicKey,
-  buyerPubkey: PublicKey
+  buyerPubkey: PublicKey,
+  orderId: BN | number
 ): PublicKey {
   const [pda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("purchase"), storePubkey.toBuffer(), buyerPubkey.toBuffer()],
+    [
+      Buffer.from("purchase"),
+      storePubkey.toBuffer(),
+      buyerPubkey.toBuffer(),
+      new BN(orderId).toArrayLike(Buffer, "le", 8),
+    ],
+    PROGRAM_ID
+  );
+  return pda;
+}
+
+/**
+ * Find the store's daily sales PDA for the day containing `timestamp` (seconds)
+ */
+export function findDailySalesPDA(
+  storePubkey: PublicKey,
+  timestamp: number = Math.floor(Date.now() / 1000)
+): PublicKey {
+  const day = new BN(Math.floor(timestamp / 86400));
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("daily"), storePubkey.toBuffer(), day.toArrayLike(Buffer, "le", 8)],
     PROGRAM_ID
   );
   return pda;
@@ -21,6 +21,7 @@ import {
   findStorePDA,
   findEscrowPDA,
   findReceiptPDA,
+  findDailySalesPDA,
   findProductPDA,
   findLoyaltyMintPDA,
   uuidToBytes,
@@ -45,7 +46,10 @@ export function getSodapProgram(wallet: any, connection: Connection) {
 }
 
 /**
- * Execute a purchase cart transaction using Solana Pay
+ * Execute a purchase cart transaction using Solana Pay.
+ * `orderId` must be unique per buyer and store, as it keys the receipt.
+ * `purchase_cart` only takes full payments: BNPL goes through the
+ * `create_bnpl_loan` instruction and points through a `claim_reward` voucher.
  */
 export async function purchaseCart(
   connection: Connection,
@@ -54,8 +58,10 @@ export async function purchaseCart(
   productUuids: string[],
   quantities: number[],
   totalAmountLamports: number,
+  orderId: number,
   reference?: PublicKey,
-  gasFee: number = 0
+  gasFee: number = 0,
+  couponCode: string | null = null
 ) {
   if (!wallet.publicKey) {
     throw new Error("Wallet not connected");
@@ -69,7 +75,7 @@ export async function purchaseCart(
 
   // Get PDAs
   const escrowPDA = findEscrowPDA(storePubkey);
-  const receiptPDA = findReceiptPDA(storePubkey, wallet.publicKey);
+  const receiptPDA = findReceiptPDA(storePubkey, wallet.publicKey, orderId);
 
   // Convert product UUIDs to bytes arrays for the contract
   const productUuidBytesArray = productUuids.map((uuid) =>
@@ -83,24 +89,27 @@ export async function purchaseCart(
 
   // Initialize program
   const program = getSodapProgram(wallet, connection);
+  const store = await (program.account as any).store.fetch(storePubkey);
 
   // Create transaction instruction
   const ix = await program.methods
-    .purchaseCart(
-      productUuidBytesArray,
-      quantities.map((q) => new BN(q)),
-      new BN(totalAmountLamports),
-      new BN(gasFee),
-      { success: {} } // Transaction status
-    )
+    .purchaseCart(new BN(orderId), {
+      productUuids: productUuidBytesArray,
+      variantIds: [],
+      quantities: quantities.map((q) => new BN(q)),
+      totalAmountPaid: new BN(totalAmountLamports),
+      gasFee: new BN(gasFee),
+      status: { completed: {} },
+      couponCode,
+    })
     .accounts({
       buyer: wallet.publicKey,
       store: storePubkey,
       receipt: receiptPDA,
-      storeOwner: wallet.publicKey, // This might need to be the actual store owner
+      storeOwner: store.owner,
       escrowAccount: escrowPDA,
+      dailySales: findDailySalesPDA(storePubkey),
       systemProgram: SystemProgram.programId,
-      payer: wallet.publicKey,
     })
     .remainingAccounts(
       // Add product accounts as remaining accounts
@@ -146,13 +155,15 @@ export async function purchaseCart(
 }
 
 /**
- * Release funds from escrow to the store owner
+ * Confirm receipt of an order as its buyer, paying the store owner from
+ * escrow minus the platform fee. Unconfirmed orders are released by
+ * `auto_release_order` once their hold period has passed.
  */
-export async function releaseEscrow(
+export async function confirmReceipt(
   connection: Connection,
   wallet: any,
   storePublicKey: string | PublicKey,
-  amountLamports: number
+  receipt: PublicKey
 ) {
   if (!wallet.publicKey) {
     throw new Error("Wallet not connected");
@@ -169,24 +180,25 @@ export async function releaseEscrow(
 
   // Initialize program
   const program = getSodapProgram(wallet, connection);
+  const store = await (program.account as any).store.fetch(storePubkey);
 
   try {
     // Create and send transaction
     const tx = await program.methods
-      .releaseEscrow(new BN(amountLamports))
+      .confirmReceipt()
       .accounts({
-        store: wallet.publicKey,
-        storeAccount: storePubkey,
-        storeOwner: wallet.publicKey,
+        store: storePubkey,
+        receipt,
         escrowAccount: escrowPDA,
+        storeOwner: store.owner,
+        caller: wallet.publicKey,
         systemProgram: SystemProgram.programId,
-        payer: wallet.publicKey,
       })
       .rpc();
 
     return tx;
   } catch (error) {
-    console.error("Error releasing escrow:", error);
+    console.error("Error confirming receipt:", error);
     throw error;
   }
 }
@@ -396,6 +408,24 @@ export async function initializeLoyaltyProgram(
   }
 }
 
+/**
+ * Find the PDA of a loyalty transaction record by its history sequence
+ */
+export function findLoyaltyTransactionPDA(
+  loyaltyAccount: PublicKey,
+  sequence: BN,
+  programId: PublicKey
+) {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("loyalty_transaction"),
+      loyaltyAccount.toBuffer(),
+      sequence.toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  )[0];
+}
+
 /**
  * Initialize loyalty account for a user at a store
  */
@@ -405,7 +435,7 @@ export async function initializeLoyaltyAccount(
   storePublicKey: string | PublicKey,
   userPublicKey: PublicKey,
   referralCode?: string,
-  referredBy?: PublicKey
+  referrerCode?: string
 ) {
   if (!wallet.publicKey) {
     throw new Error("Wallet not connected");
@@ -432,23 +462,40 @@ export async function initializeLoyaltyAccount(
     getSodapProgram(wallet, connection).programId
   )[0];
 
+  // Referral codes are fixed 8-byte values, zero padded
+  const toCodeBytes = (code: string) => {
+    const bytes = new Uint8Array(8);
+    bytes.set(new TextEncoder().encode(code).slice(0, 8));
+    return Array.from(bytes);
+  };
+  const ownCode = referralCode
+    ? toCodeBytes(referralCode)
+    : Array.from(userPublicKey.toBytes().slice(0, 8));
+
+  const findReferralCodePDA = (code: number[]) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("referral_code"), storePubkey.toBuffer(), Buffer.from(code)],
+      getSodapProgram(wallet, connection).programId
+    )[0];
+
   // Initialize program
   const program = getSodapProgram(wallet, connection);
 
   try {
     const tx = await program.methods
       .initializeLoyaltyAccount(
-        referralCode
-          ? Array.from(new TextEncoder().encode(referralCode.slice(0, 8)))
-          : null,
-        referredBy || null
+        referralCode ? ownCode : null,
+        referrerCode ? toCodeBytes(referrerCode) : null
       )
       .accounts({
         user: userPublicKey,
         store: storePubkey,
         loyaltyProgram: loyaltyProgramPDA,
         loyaltyAccount: loyaltyAccountPDA,
-        authority: wallet.publicKey,
+        referralCodeRegistry: findReferralCodePDA(ownCode),
+        referrerCodeRegistry: referrerCode
+          ? findReferralCodePDA(toCodeBytes(referrerCode))
+          : null,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -466,7 +513,8 @@ export async function initializeLoyaltyAccount(
 
 /**
  * Award loyalty points using the program's earn_loyalty_points instruction
- * Automatically initializes loyalty program and account if they don't exist
+ * Automatically initializes loyalty program and account if they don't exist.
+ * Points are earned once per completed purchase receipt, at the program's rate.
  */
 export async function earnLoyaltyPoints(
   connection: Connection,
@@ -474,7 +522,7 @@ export async function earnLoyaltyPoints(
   storePublicKey: string | PublicKey,
   userPublicKey: PublicKey,
   purchaseAmountSol: number,
-  pointType: "Purchase" | "Referral" | "Bonus" | "Welcome" = "Purchase"
+  purchaseReceipt: PublicKey
 ) {
   if (!wallet.publicKey) {
     throw new Error("Wallet not connected");
@@ -504,9 +552,6 @@ export async function earnLoyaltyPoints(
     getSodapProgram(wallet, connection).programId
   )[0];
 
-  // Create a new keypair for transaction record (not a PDA)
-  const transactionRecord = Keypair.generate();
-
   // Initialize program
   const program = getSodapProgram(wallet, connection);
 
@@ -542,20 +587,28 @@ export async function earnLoyaltyPoints(
       );
     }
 
+    // Transaction records live at the account's next history sequence
+    const loyaltyAccountData = await program.account.loyaltyAccount.fetch(
+      loyaltyAccountPDA
+    );
+    const transactionRecord = findLoyaltyTransactionPDA(
+      loyaltyAccountPDA,
+      loyaltyAccountData.transactionCount,
+      program.programId
+    );
+
     // Now earn the points
     const tx = await program.methods
-      .earnLoyaltyPoints(new BN(purchaseAmountLamports), {
-        [pointType.toLowerCase()]: {},
-      })
+      .earnLoyaltyPoints(new BN(purchaseAmountLamports))
       .accounts({
         loyaltyAccount: loyaltyAccountPDA,
         store: storePubkey,
         loyaltyProgram: loyaltyProgramPDA,
-        transactionRecord: transactionRecord.publicKey,
+        transactionRecord,
+        purchaseReceipt,
         user: wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
-      .signers([transactionRecord])
       .rpc();
 
     return {
@@ -609,9 +662,6 @@ export async function deductLoyaltyPoints(
     getSodapProgram(wallet, connection).programId
   )[0];
 
-  // Create a new keypair for transaction record (not a PDA)
-  const transactionRecord = Keypair.generate();
-
   // Initialize program
   const program = getSodapProgram(wallet, connection);
 
@@ -647,6 +697,16 @@ export async function deductLoyaltyPoints(
       );
     }
 
+    // Transaction records live at the account's next history sequence
+    const loyaltyAccountData = await program.account.loyaltyAccount.fetch(
+      loyaltyAccountPDA
+    );
+    const transactionRecord = findLoyaltyTransactionPDA(
+      loyaltyAccountPDA,
+      loyaltyAccountData.transactionCount,
+      program.programId
+    );
+
     // Now deduct the points
     const tx = await program.methods
       .deductLoyaltyPoints(new BN(refundAmountLamports), {
@@ -656,11 +716,10 @@ export async function deductLoyaltyPoints(
         loyaltyAccount: loyaltyAccountPDA,
         store: storePubkey,
         loyaltyProgram: loyaltyProgramPDA,
-        transactionRecord: transactionRecord.publicKey,
+        transactionRecord,
         user: wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
-      .signers([transactionRecord])
       .rpc();
 
     return {